@@ -1,17 +1,24 @@
 import { defineStore } from 'pinia';
 import { io, type Socket } from 'socket.io-client';
-import type { GameState } from '@/types/poker';
+import { CAPABILITIES, PROTOCOL_VERSION, type AchievementUnlocked, type Ack, type BotDifficulty, type ChatMessage, type EmoteEvent, type EmoteId, type GameState, type Hello, type LobbyChange, type LobbyFilter, type RoomInfo, type RoomsPage } from '@/types/poker';
 import { ref } from 'vue';
 import { useUserStore } from './user';
 import { soundManager } from '@/utils/sound';
+import { applyPatch, type StateDelta } from '@/utils/patch';
 
 export const useGameStore = defineStore('game', () => {
     const socket = ref<Socket | null>(null);
     const gameState = ref<GameState | null>(null);
-    const availableRooms = ref<any[]>([]); // Using any[] for now, or define RoomInfo interface
+    const availableRooms = ref<RoomInfo[]>([]);
+    const lobbyFilter = ref<LobbyFilter>({});
+    const lobbyTotal = ref(0);
     const playerId = ref('');
     const roomId = ref('');
     const error = ref('');
+    const chatMessages = ref<ChatMessage[]>([]);
+    const activeEmotes = ref<Record<string, EmoteId>>({}); // Sender id -> emote being shown
+    const emoteMutes = ref<string[]>([]);
+    let resyncRequested = false;
 
     function connect() {
         if (socket.value) return;
@@ -21,36 +28,93 @@ export const useGameStore = defineStore('game', () => {
 
         socket.value.on('connect', () => {
             console.log('Connected to', socketUrl);
+            socket.value?.emit('handshake', {
+                protocol_version: PROTOCOL_VERSION,
+                capabilities: CAPABILITIES,
+                client: 'web',
+            });
         });
 
+        socket.value.on('hello', (hello: Hello) => {
+            console.log('Server protocol', hello.protocol_version, 'with', hello.capabilities);
+        });
+
+        // Full snapshots arrive on join and resync; every other change is a
+        // delta against the version we hold.
         socket.value.on('game_state_update', (state: GameState) => {
             console.log('Game State Updated:', state);
+            resyncRequested = false;
+            setState(state);
+        });
 
-            // Simple sound triggers based on state changes (naive approach)
-            // Ideally we'd compare old state vs new state to detect events
-            if (gameState.value) {
-                // If it was my turn and now it's not (played a card)
-                if (gameState.value.current_turn === playerId.value && state.current_turn !== playerId.value) {
-                     soundManager.play('play');
-                }
+        socket.value.on('game_state_delta', (delta: StateDelta) => {
+            const current = gameState.value;
+            if (!current || current.room_id !== delta.room_id || delta.version <= current.version) return;
 
-                // If round count changed
-                if (gameState.value.round_count !== state.round_count) {
-                    soundManager.play('round_end');
+            if (current.version === delta.from_version) {
+                try {
+                    setState(applyPatch(current, delta.patch));
+                    return;
+                } catch (e) {
+                    console.warn('Could not apply delta:', e);
                 }
+            }
+            if (!resyncRequested) {
+                resyncRequested = true;
+                socket.value?.emit('resync', { room_id: delta.room_id });
+            }
+        });
 
-                // If winner
-                if (!gameState.value.winner && state.winner) {
-                    soundManager.play(state.winner === playerId.value ? 'win' : 'lose');
+        socket.value.on('chat_history', (messages: ChatMessage[]) => {
+            chatMessages.value = messages;
+        });
+
+        socket.value.on('chat_message', (message: ChatMessage) => {
+            chatMessages.value.push(message);
+        });
+
+        socket.value.on('emote', (event: EmoteEvent) => {
+            activeEmotes.value[event.sender_id] = event.emote;
+            setTimeout(() => {
+                if (activeEmotes.value[event.sender_id] === event.emote) {
+                    delete activeEmotes.value[event.sender_id];
                 }
-            }
+            }, 3000);
+        });
 
-            gameState.value = state;
-            error.value = '';
+        // Achievements show up in the chat log like a message from the player.
+        socket.value.on('achievement_unlocked', (event: AchievementUnlocked) => {
+            chatMessages.value.push({
+                sender_id: event.player_id,
+                nickname: event.nickname,
+                spectator: false,
+                text: `🏆 ${event.name}: ${event.description}`,
+                timestamp: Math.floor(Date.now() / 1000),
+            });
         });
 
-        socket.value.on('rooms_list', (rooms: any[]) => {
-            availableRooms.value = rooms;
+        socket.value.on('emote_mutes', (muted: string[]) => {
+            emoteMutes.value = muted;
+        });
+
+        socket.value.on('rooms_page', (page: RoomsPage) => {
+            availableRooms.value = page.rooms;
+            lobbyTotal.value = page.total;
+        });
+
+        // Live lobby: rooms that stop matching the filter arrive as removed.
+        socket.value.on('lobby_update', (update: LobbyChange) => {
+            const rooms = availableRooms.value;
+            if (update.change === 'removed') {
+                availableRooms.value = rooms.filter(r => r.id !== update.id);
+                return;
+            }
+            const index = rooms.findIndex(r => r.id === update.room.id);
+            if (index >= 0) {
+                rooms[index] = update.room;
+            } else if (rooms.length < (lobbyFilter.value.limit ?? 20)) {
+                availableRooms.value = [update.room, ...rooms];
+            }
         });
 
         socket.value.on('error', (msg: string) => {
@@ -59,6 +123,48 @@ export const useGameStore = defineStore('game', () => {
         });
     }
 
+    function setState(state: GameState) {
+        // Simple sound triggers based on state changes (naive approach)
+        // Ideally we'd compare old state vs new state to detect events
+        if (gameState.value) {
+            // If it was my turn and now it's not (played a card)
+            if (gameState.value.current_turn === playerId.value && state.current_turn !== playerId.value) {
+                 soundManager.play('play');
+            }
+
+            // If round count changed
+            if (gameState.value.round_count !== state.round_count) {
+                soundManager.play('round_end');
+            }
+
+            // If winner
+            if (!gameState.value.winner && state.winner) {
+                soundManager.play(state.winner === playerId.value ? 'win' : 'lose');
+            }
+        }
+
+        gameState.value = state;
+        error.value = '';
+    }
+
+    // Sent with every game action: the server answers a retried id with
+    // the first result and refuses actions made against an older state.
+    function actionGuard() {
+        return {
+            action_id: crypto.randomUUID(),
+            expected_version: gameState.value?.version
+        };
+    }
+
+    // Failures also arrive as an `error` event; the ack only tells us when
+    // our copy of the state is behind and needs a fresh snapshot.
+    function onAck(ack: Ack) {
+        const current = gameState.value;
+        if (!ack.ok && ack.error_code === 'stale_version' && current && (ack.state_version ?? 0) > current.version) {
+            socket.value?.emit('resync', { room_id: current.room_id });
+        }
+    }
+
     function joinGame(room: string, player: string) {
         connect();
         roomId.value = room;
@@ -78,8 +184,9 @@ export const useGameStore = defineStore('game', () => {
         socket.value?.emit('mulligan', {
             room_id: roomId.value,
             player_id: playerId.value,
-            card_ids: cardIds
-        });
+            card_ids: cardIds,
+            ...actionGuard()
+        }, onAck);
     }
 
     function playCard(cardId: string, targetId?: string) {
@@ -87,41 +194,86 @@ export const useGameStore = defineStore('game', () => {
             room_id: roomId.value,
             player_id: playerId.value,
             card_id: cardId,
-            target_id: targetId
-        });
+            target_id: targetId,
+            ...actionGuard()
+        }, onAck);
     }
 
     function passTurn() {
         socket.value?.emit('pass', {
             room_id: roomId.value,
-            player_id: playerId.value
-        });
+            player_id: playerId.value,
+            ...actionGuard()
+        }, onAck);
     }
 
     function restartGame() {
         socket.value?.emit('restart_game', {
             room_id: roomId.value,
-            player_id: playerId.value
+            player_id: playerId.value,
+            ...actionGuard()
+        }, onAck);
+    }
+
+    function addBot(difficulty: BotDifficulty = 'Normal') {
+        socket.value?.emit('add_bot', {
+            room_id: roomId.value,
+            difficulty
+        });
+    }
+
+    function sendChat(text: string) {
+        socket.value?.emit('chat_message', {
+            room_id: roomId.value,
+            player_id: playerId.value,
+            text
+        });
+    }
+
+    function sendEmote(emote: EmoteId) {
+        socket.value?.emit('emote', {
+            room_id: roomId.value,
+            player_id: playerId.value,
+            emote
+        });
+    }
+
+    function setEmotesMuted(targetId: string, muted: boolean) {
+        socket.value?.emit('mute_emotes', {
+            room_id: roomId.value,
+            player_id: playerId.value,
+            target_id: targetId,
+            muted
         });
     }
 
-    function fetchRooms() {
+    function fetchRooms(filter: LobbyFilter = lobbyFilter.value) {
         connect();
-        socket.value?.emit('list_rooms');
+        lobbyFilter.value = filter;
+        socket.value?.emit('watch_lobby', filter);
     }
 
     return {
         socket,
         gameState,
         availableRooms,
+        lobbyFilter,
+        lobbyTotal,
         playerId,
         roomId,
         error,
+        chatMessages,
+        activeEmotes,
+        emoteMutes,
         joinGame,
         mulligan,
         playCard,
         passTurn,
         restartGame,
+        addBot,
+        sendChat,
+        sendEmote,
+        setEmotesMuted,
         fetchRooms
     };
 });
\ No newline at end of file
@@ -6,6 +6,8 @@ export type CardAbility = 'None' | 'IronGuard' | 'Intel' | 'Medic' | 'Hero' | 'B
 
 export type GamePhase = 'Waiting' | 'Mulligan' | 'Playing' | 'RoundEnd' | 'GameEnd';
 
+export type BotDifficulty = 'Easy' | 'Normal' | 'Hard';
+
 export interface Card {
     id: string;
     suit: Suit;
@@ -20,12 +22,82 @@ export interface Player {
     id: string;
     nickname: string;
     avatar: string;
-    hand: Card[];
+    hand_size: number;
+    hand: Card[] | null; // Only sent for your own seat
     board: Card[];
     discard_pile: Card[];
     current_score: number;
     rounds_won: number;
     passed: boolean;
+    is_bot?: boolean;
+    bot_difficulty?: BotDifficulty;
+}
+
+export interface RoomOptions {
+    hints_enabled: boolean;
+    spectator_chat?: boolean;
+    seats?: string[]; // Reserved for a scheduled match
+    visibility?: 'public' | 'private'; // Private rooms are not listed in the lobby
+    rated?: boolean;
+    scenario?: string; // Name of the scenario the room was loaded from
+}
+
+// A room as the lobby lists it.
+export interface RoomInfo {
+    id: string;
+    player_count: number;
+    phase: GamePhase;
+    creator: string | null;
+    created_at: number;
+    variant: string; // 'standard', 'custom' or 'scenario'
+    rules_summary: string;
+    visibility: 'public' | 'private';
+    rated: boolean;
+    joinable: boolean;
+    spectatable: boolean;
+}
+
+export interface LobbyFilter {
+    joinable?: boolean;
+    spectatable?: boolean;
+    variant?: string;
+    offset?: number;
+    limit?: number;
+}
+
+export interface RoomsPage {
+    rooms: RoomInfo[];
+    total: number;
+    offset: number;
+    limit: number;
+}
+
+export type LobbyChange =
+    | { change: 'updated'; room: RoomInfo }
+    | { change: 'removed'; id: string };
+
+export type EmoteId = 'greetings' | 'good_luck' | 'well_played' | 'thinking' | 'oops' | 'wow' | 'thanks' | 'good_game';
+
+export interface EmoteEvent {
+    sender_id: string;
+    emote: EmoteId;
+}
+
+export interface ChatMessage {
+    sender_id: string;
+    nickname: string;
+    spectator: boolean;
+    text: string;
+    timestamp: number;
+}
+
+// Announced to the room the first time a player earns an achievement.
+export interface AchievementUnlocked {
+    player_id: string;
+    nickname: string;
+    id: string;
+    name: string;
+    description: string;
 }
 
 export interface GameState {
@@ -34,6 +106,57 @@ export interface GameState {
     players: Record<string, Player>;
     current_turn: string;
     round_count: number;
-    deck: Card[]; 
+    deck_size: number;
     winner?: string | null;
+    version: number; // Increases with every change the server publishes
+    options?: RoomOptions;
+    created_at?: number;
+    creator?: string | null;
+}
+
+export type ErrorCode =
+    | 'room_not_found' | 'room_full' | 'not_in_room' | 'not_your_turn' | 'wrong_phase'
+    | 'illegal_action' | 'game_in_progress' | 'hints_disabled' | 'invalid_message'
+    | 'unknown_emote' | 'rate_limited' | 'stale_version' | 'unauthorized' | 'challenge_not_found'
+    | 'incompatible_client' | 'tournament_not_found' | 'player_not_found';
+
+// Acknowledgement the server returns for every client event.
+export interface Ack {
+    ok: boolean;
+    error_code: ErrorCode | null;
+    state_version: number | null;
+}
+
+// Protocol this client was written for; see protocol.md.
+export const PROTOCOL_VERSION = 1;
+export const CAPABILITIES = ['state_deltas', 'action_guards', 'acks', 'chat', 'emotes'];
+
+// The server's answer to a handshake.
+export interface Hello {
+    protocol_version: number;
+    min_protocol_version: number;
+    capabilities: string[];
+    events: string[];
+}
+
+// Lifetime statistics sent as `profile`.
+export interface PlayerProfile {
+    id: string;
+    nickname: string;
+    avatar: string;
+    matches_played: number;
+    matches_won: number;
+    matches_drawn: number;
+    rounds_played: number;
+    rounds_won: number;
+    favourite_cards: { card: string; plays: number }[];
+    spy_plays: number;
+    medic_plays: number;
+    scorch_plays: number;
+    average_margin: number;
+    current_streak: number; // Negative for a losing streak
+    last_played: number;
+    achievements: { id: string; room_id: string; unlocked_at: number }[];
+    card_plays: Record<string, number>;
+    margin_total: number;
 }
@@ -0,0 +1,61 @@
+// JSON Patch (RFC 6902) subset produced by the server's `delta::diff`.
+
+export type PatchOp =
+    | { op: 'add'; path: string; value: unknown }
+    | { op: 'remove'; path: string }
+    | { op: 'replace'; path: string; value: unknown };
+
+export interface StateDelta {
+    room_id: string;
+    from_version: number;
+    version: number;
+    patch: PatchOp[];
+}
+
+function unescape(token: string): string {
+    return token.replace(/~1/g, '/').replace(/~0/g, '~');
+}
+
+/**
+ * Applies `patch` to a deep copy of `doc` and returns the copy.
+ * Throws if an operation does not fit, so the caller can resync.
+ */
+export function applyPatch<T>(doc: T, patch: PatchOp[]): T {
+    const root: { value: any } = { value: JSON.parse(JSON.stringify(doc)) };
+
+    for (const op of patch) {
+        const tokens = op.path === '' ? [] : op.path.split('/').slice(1).map(unescape);
+        if (tokens.length === 0) {
+            if (op.op === 'remove') throw new Error('Cannot remove the document');
+            root.value = op.value;
+            continue;
+        }
+
+        let parent: any = root.value;
+        for (const token of tokens.slice(0, -1)) {
+            parent = parent?.[token];
+            if (parent === undefined || parent === null) throw new Error(`No value at ${op.path}`);
+        }
+        const key = tokens[tokens.length - 1];
+
+        if (Array.isArray(parent)) {
+            const index = key === '-' ? parent.length : Number(key);
+            if (!Number.isInteger(index) || index < 0 || index > parent.length) throw new Error(`Bad index in ${op.path}`);
+            if (op.op === 'add') parent.splice(index, 0, op.value);
+            else if (index === parent.length) throw new Error(`Bad index in ${op.path}`);
+            else if (op.op === 'remove') parent.splice(index, 1);
+            else parent[index] = op.value;
+        } else if (typeof parent === 'object') {
+            if (op.op === 'remove') {
+                if (!(key in parent)) throw new Error(`No value at ${op.path}`);
+                delete parent[key];
+            } else {
+                parent[key] = op.value;
+            }
+        } else {
+            throw new Error(`No container at ${op.path}`);
+        }
+    }
+
+    return root.value;
+}
@@ -0,0 +1,20 @@
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct EmoteEvent {
+    pub sender_id: String, // The sender's seat
+    pub emote: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ChatMessage {
+    pub sender_id: String,
+    pub nickname: String,
+    pub spectator: bool,
+    pub text: String,
+    pub timestamp: u64,
+}
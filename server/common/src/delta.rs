@@ -0,0 +1,211 @@
+use crate::view::PublicGameState;
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// One JSON Patch (RFC 6902) operation. Only the three kinds `diff`
+/// produces are supported.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOp {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+}
+
+/// What a room broadcasts after a change: the patch turning version
+/// `from_version` of its state into `version`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct StateDelta {
+    pub room_id: String,
+    pub from_version: u64,
+    pub version: u64,
+    pub patch: Vec<PatchOp>,
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// Operations that turn `old` into `new`.
+pub fn diff(old: &Value, new: &Value) -> Vec<PatchOp> {
+    let mut ops = Vec::new();
+    diff_into(old, new, "", &mut ops);
+    ops
+}
+
+fn diff_into(old: &Value, new: &Value, path: &str, ops: &mut Vec<PatchOp>) {
+    match (old, new) {
+        (Value::Object(a), Value::Object(b)) => {
+            for key in a.keys().filter(|k| !b.contains_key(*k)) {
+                ops.push(PatchOp::Remove { path: format!("{}/{}", path, escape(key)) });
+            }
+            for (key, value) in b {
+                let child = format!("{}/{}", path, escape(key));
+                match a.get(key) {
+                    Some(previous) => diff_into(previous, value, &child, ops),
+                    None => ops.push(PatchOp::Add { path: child, value: value.clone() }),
+                }
+            }
+        },
+        (Value::Array(a), Value::Array(b)) => {
+            // Cards mostly leave or join a zone one at a time, so only the
+            // run between the common prefix and suffix is rewritten.
+            let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+            let suffix = a[prefix..].iter().rev()
+                .zip(b[prefix..].iter().rev())
+                .take_while(|(x, y)| x == y)
+                .count();
+            let (old_mid, new_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+            if old_mid.len() == new_mid.len() {
+                for (i, (x, y)) in old_mid.iter().zip(new_mid).enumerate() {
+                    diff_into(x, y, &format!("{}/{}", path, prefix + i), ops);
+                }
+            } else {
+                for _ in old_mid {
+                    ops.push(PatchOp::Remove { path: format!("{}/{}", path, prefix) });
+                }
+                for (i, value) in new_mid.iter().enumerate() {
+                    ops.push(PatchOp::Add { path: format!("{}/{}", path, prefix + i), value: value.clone() });
+                }
+            }
+        },
+        _ if old != new => ops.push(PatchOp::Replace { path: path.to_string(), value: new.clone() }),
+        _ => {},
+    }
+}
+
+/// Splits a pointer into its parent and last token.
+fn split(path: &str) -> Result<(&str, String), String> {
+    match path.rfind('/') {
+        Some(i) => Ok((&path[..i], unescape(&path[i + 1..]))),
+        None => Err(format!("Invalid path `{}`", path)),
+    }
+}
+
+fn parent<'a>(target: &'a mut Value, path: &str) -> Result<&'a mut Value, String> {
+    target.pointer_mut(path).ok_or(format!("No value at `{}`", path))
+}
+
+fn index(token: &str, len: usize) -> Result<usize, String> {
+    match token.parse::<usize>() {
+        Ok(i) if i <= len => Ok(i),
+        _ => Err(format!("Index `{}` out of bounds", token)),
+    }
+}
+
+/// Applies `patch` to `target` in order, stopping at the first operation
+/// that does not fit.
+pub fn apply(target: &mut Value, patch: &[PatchOp]) -> Result<(), String> {
+    for op in patch {
+        match op {
+            PatchOp::Replace { path, value } => {
+                *parent(target, path)? = value.clone();
+            },
+            PatchOp::Remove { path } => {
+                let (parent_path, key) = split(path)?;
+                match parent(target, parent_path)? {
+                    Value::Object(map) => {
+                        map.remove(&key).ok_or(format!("No value at `{}`", path))?;
+                    },
+                    Value::Array(items) => {
+                        let i = index(&key, items.len())?;
+                        if i == items.len() {
+                            return Err(format!("Index `{}` out of bounds", key));
+                        }
+                        items.remove(i);
+                    },
+                    _ => return Err(format!("Cannot remove `{}`", path)),
+                }
+            },
+            PatchOp::Add { path, value } => {
+                let (parent_path, key) = split(path)?;
+                match parent(target, parent_path)? {
+                    Value::Object(map) => {
+                        map.insert(key, value.clone());
+                    },
+                    Value::Array(items) if key == "-" => items.push(value.clone()),
+                    Value::Array(items) => {
+                        let i = index(&key, items.len())?;
+                        items.insert(i, value.clone());
+                    },
+                    _ => return Err(format!("Cannot add `{}`", path)),
+                }
+            },
+        }
+    }
+    Ok(())
+}
+
+/// The state after `update`, `None` if it is stale or for another room,
+/// or an error if an earlier delta went missing.
+pub fn apply_delta(game: Option<&PublicGameState>, update: &StateDelta) -> Result<Option<PublicGameState>, String> {
+    let game = match game {
+        Some(game) if game.room_id == update.room_id && update.version > game.version => game,
+        _ => return Ok(None),
+    };
+    if game.version != update.from_version {
+        return Err(format!("missed versions {}..{}", game.version + 1, update.from_version));
+    }
+    let mut value = serde_json::to_value(game).map_err(|e| e.to_string())?;
+    apply(&mut value, &update.patch)?;
+    serde_json::from_value(value).map(Some).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic;
+    use crate::game_types::GamePhase;
+    use rand::seq::IndexedRandom;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_then_apply_reproduces_each_state() {
+        let ids = vec!["p1".to_string(), "p/2".to_string()];
+        let mut game = game_logic::init_game("room_test".to_string(), ids.clone());
+        for pid in &ids {
+            game_logic::submit_mulligan(&mut game, pid, Vec::new()).unwrap();
+        }
+
+        let mut previous = serde_json::to_value(&game).unwrap();
+        while game.phase == GamePhase::Playing {
+            let mover = game.current_turn.clone();
+            let action = game_logic::legal_actions(&game, &mover).choose(&mut rand::rng()).unwrap().clone();
+            game_logic::apply_action(&mut game, &mover, &action).unwrap();
+
+            let current = serde_json::to_value(&game).unwrap();
+            let patch = diff(&previous, &current);
+            let size = serde_json::to_string(&patch).unwrap().len();
+            assert!(size < serde_json::to_string(&current).unwrap().len());
+
+            apply(&mut previous, &patch).unwrap();
+            assert_eq!(previous, current);
+        }
+    }
+
+    #[test]
+    fn test_array_edits_and_bad_paths() {
+        let mut doc = json!({ "hand": [1, 2, 3, 4], "a~b": 1 });
+        let target = json!({ "hand": [1, 3, 4], "c": true });
+        let patch = diff(&doc, &target);
+        assert_eq!(patch, vec![
+            PatchOp::Remove { path: "/a~0b".to_string() },
+            PatchOp::Add { path: "/c".to_string(), value: json!(true) },
+            PatchOp::Remove { path: "/hand/1".to_string() },
+        ]);
+        apply(&mut doc, &patch).unwrap();
+        assert_eq!(doc, target);
+
+        let bad = [PatchOp::Remove { path: "/hand/9".to_string() }];
+        assert!(apply(&mut doc, &bad).is_err());
+    }
+}
@@ -0,0 +1,122 @@
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Stable, machine-readable reason for a rejected request. Clients still
+/// get the human-readable message; the code is for metrics and tooling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    RoomNotFound,
+    RoomFull,
+    NotInRoom,
+    NotYourTurn,
+    WrongPhase,
+    IllegalAction,
+    GameInProgress,
+    HintsDisabled,
+    InvalidMessage,
+    UnknownEmote,
+    RateLimited,
+    StaleVersion,
+    Unauthorized,
+    ChallengeNotFound,
+    IncompatibleClient,
+    TournamentNotFound,
+    PlayerNotFound,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 17] = [
+        ErrorCode::RoomNotFound,
+        ErrorCode::RoomFull,
+        ErrorCode::NotInRoom,
+        ErrorCode::NotYourTurn,
+        ErrorCode::WrongPhase,
+        ErrorCode::IllegalAction,
+        ErrorCode::GameInProgress,
+        ErrorCode::HintsDisabled,
+        ErrorCode::InvalidMessage,
+        ErrorCode::UnknownEmote,
+        ErrorCode::RateLimited,
+        ErrorCode::StaleVersion,
+        ErrorCode::Unauthorized,
+        ErrorCode::ChallengeNotFound,
+        ErrorCode::IncompatibleClient,
+        ErrorCode::TournamentNotFound,
+        ErrorCode::PlayerNotFound,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::RoomNotFound => "room_not_found",
+            ErrorCode::RoomFull => "room_full",
+            ErrorCode::NotInRoom => "not_in_room",
+            ErrorCode::NotYourTurn => "not_your_turn",
+            ErrorCode::WrongPhase => "wrong_phase",
+            ErrorCode::IllegalAction => "illegal_action",
+            ErrorCode::GameInProgress => "game_in_progress",
+            ErrorCode::HintsDisabled => "hints_disabled",
+            ErrorCode::InvalidMessage => "invalid_message",
+            ErrorCode::UnknownEmote => "unknown_emote",
+            ErrorCode::RateLimited => "rate_limited",
+            ErrorCode::StaleVersion => "stale_version",
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::ChallengeNotFound => "challenge_not_found",
+            ErrorCode::IncompatibleClient => "incompatible_client",
+            ErrorCode::TournamentNotFound => "tournament_not_found",
+            ErrorCode::PlayerNotFound => "player_not_found",
+        }
+    }
+
+    /// Code for an error message returned by game_logic.
+    pub fn from_game_error(message: &str) -> Self {
+        if message == "Not your turn" {
+            ErrorCode::NotYourTurn
+        } else if message.starts_with("Not in ") && message.ends_with(" phase") {
+            ErrorCode::WrongPhase
+        } else {
+            ErrorCode::IllegalAction
+        }
+    }
+}
+
+/// A request a room turned down: the code plus the message for the player.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rejection {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl Rejection {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Rejection { code, message: message.into() }
+    }
+
+    /// Wraps an error message returned by game_logic.
+    pub fn from_game_error(message: String) -> Self {
+        Rejection { code: ErrorCode::from_game_error(&message), message }
+    }
+}
+
+/// Reply to a Socket.IO acknowledgement callback, so a client can tie the
+/// outcome to its request. `state_version` is the room's version after the
+/// request, or its current version when the request was refused.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct Ack {
+    pub ok: bool,
+    pub error_code: Option<ErrorCode>,
+    pub state_version: Option<u64>,
+}
+
+impl Ack {
+    pub fn ok(state_version: Option<u64>) -> Self {
+        Ack { ok: true, error_code: None, state_version }
+    }
+
+    pub fn rejected(code: ErrorCode, state_version: Option<u64>) -> Self {
+        Ack { ok: false, error_code: Some(code), state_version }
+    }
+}
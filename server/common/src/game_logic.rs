@@ -1,7 +1,8 @@
-use crate::game_types::{Card, CardAbility, GamePhase, GameState, Player, Rank, Suit};
+use crate::game_types::{BotDifficulty, Card, CardAbility, GamePhase, GameState, Player, PlayerAction, Rank, RoomOptions, RuleSet, Suit};
+use crate::notation::card_code;
 use rand::seq::SliceRandom;
 use rand::prelude::IndexedRandom; 
-use rand::rng;
+use rand::{rng, Rng};
 use std::collections::HashMap;
 use uuid::Uuid;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -13,8 +14,11 @@ fn get_timestamp() -> u64 {
         .as_secs()
 }
 
-pub fn create_deck() -> Vec<Card> {
+/// Builds the 54-card deck (or a variant of it under `rules`). Card ids are
+/// drawn from `rng` so seeded games are reproducible end to end.
+pub fn create_deck<R: Rng>(rules: &RuleSet, rng: &mut R) -> Vec<Card> {
     let mut deck = Vec::new();
+    let mut new_id = || Uuid::from_bytes(rng.random()).to_string();
 
     let suits = [Suit::Heart, Suit::Diamond, Suit::Spade, Suit::Club];
     
@@ -30,7 +34,7 @@ pub fn create_deck() -> Vec<Card> {
             };
             
             deck.push(Card {
-                id: Uuid::new_v4().to_string(),
+                id: new_id(),
                 suit: suit.clone(),
                 rank,
                 base_power: num,
@@ -42,37 +46,37 @@ pub fn create_deck() -> Vec<Card> {
 
         // Face cards
         deck.push(Card {
-            id: Uuid::new_v4().to_string(),
+            id: new_id(),
             suit: suit.clone(),
             rank: Rank::Jack,
-            base_power: 10, 
-            current_power: 10,
+            base_power: rules.jack_power,
+            current_power: rules.jack_power,
             ability: CardAbility::Intel,
             owner_id: "deck".to_string(),
         });
 
         deck.push(Card {
-            id: Uuid::new_v4().to_string(),
+            id: new_id(),
             suit: suit.clone(),
             rank: Rank::Queen,
-            base_power: 5,
-            current_power: 5,
+            base_power: rules.queen_power,
+            current_power: rules.queen_power,
             ability: CardAbility::Medic,
             owner_id: "deck".to_string(),
         });
 
         deck.push(Card {
-            id: Uuid::new_v4().to_string(),
+            id: new_id(),
             suit: suit.clone(),
             rank: Rank::King,
-            base_power: 15,
-            current_power: 15,
+            base_power: rules.king_power,
+            current_power: rules.king_power,
             ability: CardAbility::Hero,
             owner_id: "deck".to_string(),
         });
 
         deck.push(Card {
-            id: Uuid::new_v4().to_string(),
+            id: new_id(),
             suit: suit.clone(),
             rank: Rank::Ace,
             base_power: 0,
@@ -82,10 +86,10 @@ pub fn create_deck() -> Vec<Card> {
         });
     }
 
-    // Add 2 Jokers
-    for _ in 0..2 {
+    // Add Jokers (2 in the standard deck)
+    for _ in 0..rules.jokers {
         deck.push(Card {
-            id: Uuid::new_v4().to_string(),
+            id: new_id(),
             suit: Suit::Joker,
             rank: Rank::Joker,
             base_power: 0,
@@ -99,14 +103,19 @@ pub fn create_deck() -> Vec<Card> {
 }
 
 pub fn init_game(room_id: String, player_ids: Vec<String>) -> GameState {
-    let mut deck = create_deck();
-    let mut rng = rng();
-    deck.shuffle(&mut rng);
+    init_game_with(room_id, player_ids, RuleSet::default(), &mut rng())
+}
+
+/// Like `init_game`, but under a custom ruleset and with all randomness
+/// (card ids, shuffle, first player) taken from `rng`.
+pub fn init_game_with<R: Rng>(room_id: String, player_ids: Vec<String>, rules: RuleSet, rng: &mut R) -> GameState {
+    let mut deck = create_deck(&rules, rng);
+    deck.shuffle(rng);
 
     let mut players = HashMap::new();
     
     for id in &player_ids {
-        let hand: Vec<Card> = deck.drain(0..10).map(|mut c| {
+        let hand: Vec<Card> = deck.drain(0..rules.hand_size.min(deck.len())).map(|mut c| {
             c.owner_id = id.clone();
             c
         }).collect();
@@ -121,10 +130,12 @@ pub fn init_game(room_id: String, player_ids: Vec<String>) -> GameState {
             current_score: 0,
             rounds_won: 0,
             passed: false,
+            is_bot: false,
+            bot_difficulty: BotDifficulty::default(),
         });
     }
 
-    let current_turn = player_ids.choose(&mut rng).unwrap().clone();
+    let current_turn = player_ids.choose(rng).unwrap().clone();
 
     GameState {
         room_id,
@@ -135,6 +146,11 @@ pub fn init_game(room_id: String, player_ids: Vec<String>) -> GameState {
         deck,
         winner: None,
         last_update: get_timestamp(),
+        version: 0,
+        options: RoomOptions::default(),
+        rules,
+        created_at: get_timestamp(),
+        creator: None,
     }
 }
 
@@ -145,11 +161,11 @@ pub fn handle_mulligan(game: &mut GameState, player_id: &String, card_ids_to_rep
 
     let player = game.players.get_mut(player_id).ok_or("Player not found")?;
 
-    if card_ids_to_replace.len() > 2 {
-        return Err("Cannot replace more than 2 cards".to_string());
+    if card_ids_to_replace.len() > game.rules.max_mulligan {
+        return Err(format!("Cannot replace more than {} cards", game.rules.max_mulligan));
     }
 
-    let (to_keep, mut to_replace): (Vec<Card>, Vec<Card>) = player.hand.drain(..)
+    let (to_keep, to_replace): (Vec<Card>, Vec<Card>) = player.hand.drain(..)
         .partition(|c| !card_ids_to_replace.contains(&c.id));
 
     if to_replace.len() != card_ids_to_replace.len() {
@@ -177,6 +193,25 @@ pub fn handle_mulligan(game: &mut GameState, player_id: &String, card_ids_to_rep
     Ok(())
 }
 
+/// Applies a player's mulligan and marks them ready; once every player is
+/// ready the match moves on to the Playing phase.
+pub fn submit_mulligan(game: &mut GameState, player_id: &String, card_ids_to_replace: Vec<String>) -> Result<(), String> {
+    handle_mulligan(game, player_id, card_ids_to_replace)?;
+
+    let player = game.players.get_mut(player_id).unwrap();
+    player.passed = true;
+
+    let all_ready = game.players.values().all(|p| p.passed);
+    if all_ready {
+        game.phase = GamePhase::Playing;
+        for p in game.players.values_mut() {
+            p.passed = false;
+        }
+    }
+
+    Ok(())
+}
+
 pub fn play_card(
     game: &mut GameState, 
     player_id: &String, 
@@ -204,7 +239,7 @@ pub fn play_card(
             let opponent = game.players.get_mut(&opponent_id).unwrap();
             opponent.board.push(card_to_play);
             
-            let cards_to_draw = 2;
+            let cards_to_draw = game.rules.spy_draw;
             for _ in 0..cards_to_draw {
                 if !game.deck.is_empty() {
                     let mut drawn = game.deck.remove(0);
@@ -233,7 +268,7 @@ pub fn play_card(
                          let opponent_id = get_opponent_id_from_keys(game.players.keys(), player_id);
                          revived.owner_id = opponent_id.clone();
                          game.players.get_mut(&opponent_id).unwrap().board.push(revived);
-                         let cards_to_draw = 2;
+                         let cards_to_draw = game.rules.spy_draw;
                          for _ in 0..cards_to_draw {
                             if !game.deck.is_empty() {
                                 let mut drawn = game.deck.remove(0);
@@ -301,6 +336,22 @@ pub fn play_card(
     Ok(())
 }
 
+/// Ends the match in the opponent's favour. Allowed at any point between
+/// the deal and the end of the match, on either player's turn.
+pub fn resign(game: &mut GameState, player_id: &String) -> Result<(), String> {
+    if matches!(game.phase, GamePhase::Waiting | GamePhase::GameEnd) {
+        return Err("Not in a running match".to_string());
+    }
+    if !game.players.contains_key(player_id) {
+        return Err("Player not found".to_string());
+    }
+
+    game.winner = Some(get_opponent_id_from_keys(game.players.keys(), player_id));
+    game.phase = GamePhase::GameEnd;
+    game.last_update = get_timestamp();
+    Ok(())
+}
+
 pub fn pass_turn(game: &mut GameState, player_id: &String) -> Result<(), String> {
     if game.phase != GamePhase::Playing {
         return Err("Not in Playing phase".to_string());
@@ -326,22 +377,62 @@ pub fn pass_turn(game: &mut GameState, player_id: &String) -> Result<(), String>
     Ok(())
 }
 
+/// Every action `player_id` may legally take right now. Empty when it is
+/// not their turn to act.
+pub fn legal_actions(game: &GameState, player_id: &String) -> Vec<PlayerAction> {
+    if game.phase != GamePhase::Playing || &game.current_turn != player_id {
+        return Vec::new();
+    }
+    let player = match game.players.get(player_id) {
+        Some(p) => p,
+        None => return Vec::new(),
+    };
+
+    let mut actions = Vec::new();
+    for card in &player.hand {
+        match card.ability {
+            CardAbility::Medic => {
+                actions.push(PlayerAction::Play { card_id: card.id.clone(), target_id: None });
+                for dead in player.discard_pile.iter().filter(|c| c.ability != CardAbility::Hero) {
+                    actions.push(PlayerAction::Play { card_id: card.id.clone(), target_id: Some(dead.id.clone()) });
+                }
+            },
+            CardAbility::Decoy => {
+                // Swapping one Joker for another changes nothing and would let
+                // search and bots cycle forever, so it is not offered.
+                for unit in player.board.iter().filter(|c| c.ability != CardAbility::Hero && c.ability != CardAbility::Decoy) {
+                    actions.push(PlayerAction::Play { card_id: card.id.clone(), target_id: Some(unit.id.clone()) });
+                }
+            },
+            _ => actions.push(PlayerAction::Play { card_id: card.id.clone(), target_id: None }),
+        }
+    }
+    actions.push(PlayerAction::Pass);
+    actions
+}
+
+pub fn apply_action(game: &mut GameState, player_id: &String, action: &PlayerAction) -> Result<(), String> {
+    match action {
+        PlayerAction::Play { card_id, target_id } => play_card(game, player_id, card_id, target_id.clone()),
+        PlayerAction::Pass => pass_turn(game, player_id),
+        PlayerAction::Resign => resign(game, player_id),
+    }
+}
+
 fn trigger_scorch(game: &mut GameState) {
     let mut max_power = 0;
 
-    for (_pid, player) in &game.players {
+    for player in game.players.values() {
         for card in &player.board {
-            if card.ability != CardAbility::Hero {
-                if card.current_power > max_power {
-                    max_power = card.current_power;
-                }
+            if card.ability != CardAbility::Hero && card.current_power > max_power {
+                max_power = card.current_power;
             }
         }
     }
 
     let mut ids_to_remove = Vec::new();
 
-    for (_pid, player) in &game.players {
+    for player in game.players.values() {
         for card in &player.board {
             if card.ability != CardAbility::Hero && card.current_power == max_power {
                 ids_to_remove.push(card.id.clone());
@@ -363,7 +454,9 @@ fn trigger_scorch(game: &mut GameState) {
     }
 }
 
-fn update_scores(game: &mut GameState) {
+/// Recomputes bonded 2s and every player's score from the boards.
+pub fn update_scores(game: &mut GameState) {
+    let bond_power = game.rules.bond_power;
     for player in game.players.values_mut() {
         let iron_guard_count = player.board.iter()
             .filter(|c| c.ability == CardAbility::IronGuard)
@@ -372,7 +465,7 @@ fn update_scores(game: &mut GameState) {
         for card in &mut player.board {
             if card.ability == CardAbility::IronGuard {
                 if iron_guard_count >= 2 {
-                    card.current_power = 6;
+                    card.current_power = bond_power;
                 } else {
                     card.current_power = 2;
                 }
@@ -402,15 +495,16 @@ fn resolve_round(game: &mut GameState) {
     let p1_wins = game.players.get(p1_id).unwrap().rounds_won;
     let p2_wins = game.players.get(p2_id).unwrap().rounds_won;
 
-    if p1_wins >= 2 && p2_wins >= 2 {
+    let target = game.rules.rounds_to_win;
+    if p1_wins >= target && p2_wins >= target {
         game.winner = Some("Draw".to_string()); 
         game.phase = GamePhase::GameEnd;
         return;
-    } else if p1_wins >= 2 {
+    } else if p1_wins >= target {
         game.winner = Some(p1_id.clone());
         game.phase = GamePhase::GameEnd;
         return;
-    } else if p2_wins >= 2 {
+    } else if p2_wins >= target {
         game.winner = Some(p2_id.clone());
         game.phase = GamePhase::GameEnd;
         return;
@@ -431,7 +525,38 @@ fn resolve_round(game: &mut GameState) {
     }
 }
 
-fn get_opponent_id(game: &GameState, player_id: &String) -> Result<String, String> {
+/// Checks that `game` holds exactly the cards of its rule set's deck,
+/// counting hands, boards, discard piles and the deck, each under its own
+/// id. Positions built by hand must pass this before they are played.
+pub fn check_conservation(game: &GameState) -> Result<(), String> {
+    let mut expected: HashMap<String, i32> = HashMap::new();
+    for card in create_deck(&game.rules, &mut rng()) {
+        *expected.entry(card_code(&card)).or_default() += 1;
+    }
+
+    let mut ids = std::collections::HashSet::new();
+    let cards = game.players.values()
+        .flat_map(|p| p.hand.iter().chain(&p.board).chain(&p.discard_pile))
+        .chain(&game.deck);
+    for card in cards {
+        if !ids.insert(card.id.as_str()) {
+            return Err(format!("Card id {} is used twice", card.id));
+        }
+        *expected.entry(card_code(card)).or_default() -= 1;
+    }
+
+    let mut problems: Vec<String> = expected.iter()
+        .filter(|(_, n)| **n != 0)
+        .map(|(code, n)| if *n > 0 { format!("{} missing", code) } else { format!("{} extra", code) })
+        .collect();
+    if problems.is_empty() {
+        return Ok(());
+    }
+    problems.sort();
+    Err(format!("Cards are not conserved: {}", problems.join(", ")))
+}
+
+pub fn get_opponent_id(game: &GameState, player_id: &String) -> Result<String, String> {
     for id in game.players.keys() {
         if id != player_id {
             return Ok(id.clone());
@@ -489,7 +614,7 @@ mod tests {
 
     #[test]
     fn test_create_deck_count() {
-        let deck = create_deck();
+        let deck = create_deck(&RuleSet::default(), &mut rng());
         // 4 花色 * 13 張 (2-10, J, Q, K, A) + 2 張鬼牌 = 54 張
         assert_eq!(deck.len(), 54);
     }
@@ -642,4 +767,42 @@ mod tests {
         // - board 上應該有 Decoy
         assert!(p1_state.board.iter().any(|c| c.id == decoy_id));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_legal_actions_decoy_needs_target() {
+        let (mut game, p1, p2) = setup_test_game();
+        game.players.get_mut(&p1).unwrap().hand.clear();
+
+        let decoy_id = add_card_to_hand(&mut game, &p1, Rank::Joker, CardAbility::Decoy, 0);
+
+        // 場上沒有可收回的牌時，鬼牌不能出，只能 Pass
+        assert_eq!(legal_actions(&game, &p1), vec![PlayerAction::Pass]);
+        // 不是自己的回合時沒有任何合法動作
+        assert!(legal_actions(&game, &p2).is_empty());
+
+        let hero = Card { id: "hero".to_string(), suit: Suit::Club, rank: Rank::King, base_power: 15, current_power: 15, ability: CardAbility::Hero, owner_id: p1.clone() };
+        let unit = Card { id: "unit".to_string(), suit: Suit::Club, rank: Rank::Number(7), base_power: 7, current_power: 7, ability: CardAbility::None, owner_id: p1.clone() };
+        game.players.get_mut(&p1).unwrap().board.push(hero);
+        game.players.get_mut(&p1).unwrap().board.push(unit);
+
+        // 場上的鬼牌也不提供為目標（互換兩張鬼牌沒有任何效果）
+        let joker = Card { id: "joker".to_string(), suit: Suit::Joker, rank: Rank::Joker, base_power: 0, current_power: 0, ability: CardAbility::Decoy, owner_id: p1.clone() };
+        game.players.get_mut(&p1).unwrap().board.push(joker);
+
+        // 英雄不能被收回
+        let actions = legal_actions(&game, &p1);
+        assert_eq!(actions.len(), 2);
+        assert!(actions.contains(&PlayerAction::Play { card_id: decoy_id, target_id: Some("unit".to_string()) }));
+    }
+
+    #[test]
+    fn test_resign_ends_match() {
+        let (mut game, p1, p2) = setup_test_game();
+        // 不是自己的回合也可以認輸
+        assert_ne!(game.current_turn, p2);
+        apply_action(&mut game, &p2, &PlayerAction::Resign).unwrap();
+        assert_eq!(game.phase, GamePhase::GameEnd);
+        assert_eq!(game.winner, Some(p1.clone()));
+        assert!(resign(&mut game, &p1).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub enum Suit {
+    Heart,
+    Diamond,
+    Spade,
+    Club,
+    Joker,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub enum Rank {
+    Number(u8),
+    Jack,
+    Queen,
+    King,
+    Ace,
+    Joker,
+}
+
+impl Rank {
+    /// Short face label: "2".."10", "J", "Q", "K", "A" or "Joker".
+    pub fn label(&self) -> String {
+        match self {
+            Rank::Number(n) => n.to_string(),
+            Rank::Jack => "J".to_string(),
+            Rank::Queen => "Q".to_string(),
+            Rank::King => "K".to_string(),
+            Rank::Ace => "A".to_string(),
+            Rank::Joker => "Joker".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub enum CardAbility {
+    None,      // Normal cards
+    IronGuard, // For Rank 2: pairs get stronger
+    Intel,     // For Jack: spy logic
+    Medic,     // For Queen: revive unit
+    Hero,      // For King: immune to effects
+    Burn,      // For Ace: scorch strongest unit
+    Decoy,     // For Joker: return card to hand
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub enum GamePhase {
+    Waiting,
+    Mulligan,
+    Playing,
+    RoundEnd,
+    GameEnd,
+}
+
+/// Strength of a server-side bot. Easy plays the heuristic policy; the
+/// others run ISMCTS with a growing budget.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub enum BotDifficulty {
+    #[default]
+    Easy,
+    Normal,
+    Hard,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct Card {
+    pub id: String,
+    pub suit: Suit,
+    pub rank: Rank,
+    pub base_power: u8,
+    pub current_power: u8, // Can change (e.g., IronGuard)
+    pub ability: CardAbility,
+    pub owner_id: String, // The player who "controls" this card on the board (for scoring)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct Player {
+    pub id: String,
+    pub nickname: String,
+    pub avatar: String,
+    pub hand: Vec<Card>,
+    pub board: Vec<Card>,         // Cards currently on this player's side of the field
+    pub discard_pile: Vec<Card>,  // Cards in the graveyard
+    pub current_score: u32,       // Current round score
+    pub rounds_won: u8,
+    pub passed: bool,             // If true, player cannot play more cards this round
+    #[serde(default)]
+    pub is_bot: bool,             // Seat is driven by the server-side bot
+    #[serde(default)]
+    pub bot_difficulty: BotDifficulty,
+}
+
+/// A single turn decision, shared by human handlers and bots.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub enum PlayerAction {
+    Play {
+        card_id: String,
+        target_id: Option<String>,
+    },
+    Pass,
+    Resign, // Concede the match; never offered by `legal_actions`
+}
+
+/// Tunable numbers of the ruleset. The defaults are the rules in
+/// game-rules.md; overrides are used for variants and balance experiments.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(default)]
+pub struct RuleSet {
+    pub hand_size: usize,
+    pub max_mulligan: usize,
+    pub rounds_to_win: u8,
+    pub spy_draw: usize,
+    pub bond_power: u8,
+    pub jack_power: u8,
+    pub queen_power: u8,
+    pub king_power: u8,
+    pub jokers: usize,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet {
+            hand_size: 10,
+            max_mulligan: 2,
+            rounds_to_win: 2,
+            spy_draw: 2,
+            bond_power: 6,
+            jack_power: 10,
+            queen_power: 5,
+            king_power: 15,
+            jokers: 2,
+        }
+    }
+}
+
+impl RuleSet {
+    /// Rejects rule sets that cannot deal a playable match.
+    pub fn validate(&self) -> Result<(), String> {
+        let deck_size = 52 + self.jokers;
+        if self.hand_size == 0 || self.hand_size * 2 > deck_size {
+            return Err(format!("hand_size must be between 1 and {} for a {}-card deck", deck_size / 2, deck_size));
+        }
+        if self.max_mulligan > self.hand_size {
+            return Err("max_mulligan cannot exceed hand_size".to_string());
+        }
+        if self.rounds_to_win == 0 {
+            return Err("rounds_to_win must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Private rooms are left out of the lobby; players join them by id.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    #[default]
+    Public,
+    Private,
+}
+
+/// Per-room switches chosen by whoever creates the room.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct RoomOptions {
+    #[serde(default = "default_true")]
+    pub hints_enabled: bool,
+    #[serde(default)]
+    pub spectator_chat: bool, // Spectators may post in the room chat
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub seats: Vec<String>, // Player ids allowed to sit, for scheduled matches; empty lets anyone
+    #[serde(default)]
+    pub visibility: Visibility,
+    #[serde(default = "default_true")]
+    pub rated: bool, // Whether matches between two humans move their ratings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scenario: Option<String>, // Name of the scenario the room was loaded from
+}
+
+impl Default for RoomOptions {
+    fn default() -> Self {
+        RoomOptions { hints_enabled: true, spectator_chat: false, seats: Vec::new(), visibility: Visibility::Public, rated: true, scenario: None }
+    }
+}
+
+impl RoomOptions {
+    /// The options as a client may choose them: reserved seats and the
+    /// scenario name are only set by the server, so they are dropped.
+    pub fn requested(self) -> Self {
+        RoomOptions { seats: Vec::new(), scenario: None, ..self }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct GameState {
+    pub room_id: String,
+    pub phase: GamePhase,
+    pub players: HashMap<String, Player>, // Key: Player ID
+    pub current_turn: String,             // Player ID
+    pub round_count: u32,
+    pub deck: Vec<Card>,
+    pub winner: Option<String>,           // Player ID of the match winner
+    pub last_update: u64, // Timestamp of last update
+    #[serde(default)]
+    pub version: u64, // Bumped by the room on every published change
+    #[serde(default)]
+    pub options: RoomOptions,
+    #[serde(default)]
+    pub rules: RuleSet,
+    #[serde(default)]
+    pub created_at: u64, // When the room opened
+    #[serde(default)]
+    pub creator: Option<String>, // Nickname of the first player to sit
+}
@@ -0,0 +1,12 @@
+//! Game rules and protocol types shared by the server and its clients, so
+//! neither side can drift from the other. Enable `schema` for the
+//! `JsonSchema` derives the server needs to publish protocol.schema.json.
+
+pub mod chat;
+pub mod delta;
+pub mod errors;
+pub mod game_logic;
+pub mod game_types;
+pub mod notation;
+pub mod protocol;
+pub mod view;
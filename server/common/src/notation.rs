@@ -0,0 +1,69 @@
+use crate::game_types::{Card, Rank, Suit};
+
+/// Short text code for a card: suit letter followed by its rank label,
+/// e.g. "H7", "SQ", "D10". Jokers are "JK".
+pub fn card_code(card: &Card) -> String {
+    let suit = match card.suit {
+        Suit::Heart => "H",
+        Suit::Diamond => "D",
+        Suit::Spade => "S",
+        Suit::Club => "C",
+        Suit::Joker => return "JK".to_string(),
+    };
+    format!("{}{}", suit, card.rank.label())
+}
+
+/// Parses a code produced by `card_code` (case-insensitive).
+pub fn parse_card_code(code: &str) -> Result<(Suit, Rank), String> {
+    let upper = code.trim().to_uppercase();
+    if upper == "JK" || upper == "JOKER" {
+        return Ok((Suit::Joker, Rank::Joker));
+    }
+
+    let mut chars = upper.chars();
+    let suit = match chars.next() {
+        Some('H') => Suit::Heart,
+        Some('D') => Suit::Diamond,
+        Some('S') => Suit::Spade,
+        Some('C') => Suit::Club,
+        _ => return Err(format!("Unknown suit in card `{}`", code)),
+    };
+    let rank = match chars.as_str() {
+        "J" => Rank::Jack,
+        "Q" => Rank::Queen,
+        "K" => Rank::King,
+        "A" => Rank::Ace,
+        n => match n.parse::<u8>() {
+            Ok(v) if (2..=10).contains(&v) => Rank::Number(v),
+            _ => return Err(format!("Unknown rank in card `{}`", code)),
+        },
+    };
+    Ok((suit, rank))
+}
+
+/// Finds the first card in `cards` matching `code`.
+pub fn find_card<'a>(cards: &'a [Card], code: &str) -> Result<&'a Card, String> {
+    let (suit, rank) = parse_card_code(code)?;
+    cards.iter()
+        .find(|c| c.suit == suit && c.rank == rank)
+        .ok_or(format!("No {} there", code.to_uppercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic::create_deck;
+    use crate::game_types::RuleSet;
+
+    #[test]
+    fn test_card_codes_round_trip() {
+        let deck = create_deck(&RuleSet::default(), &mut rand::rng());
+        for card in &deck {
+            let (suit, rank) = parse_card_code(&card_code(card)).unwrap();
+            assert_eq!((suit, rank), (card.suit.clone(), card.rank.clone()));
+        }
+        assert_eq!(parse_card_code("sq").unwrap(), (Suit::Spade, Rank::Queen));
+        assert!(parse_card_code("X5").is_err());
+        assert!(parse_card_code("H11").is_err());
+    }
+}
@@ -0,0 +1,233 @@
+use crate::game_types::{BotDifficulty, RoomOptions};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Version of the event set and payload shapes below. Bump it whenever a
+/// change would break an existing client.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Oldest client protocol the server still serves.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Optional behaviours a client can declare in its `handshake`. Without
+/// `state_deltas` the server sends a full `game_state_update` wherever it
+/// would send a `game_state_delta`; the others only describe the client.
+pub const CAPABILITIES: [&str; 5] = ["state_deltas", "action_guards", "acks", "chat", "emotes"];
+
+/// Events a client may send, the same over Socket.IO and `/ws`.
+pub const CLIENT_EVENTS: [&str; 19] = [
+    "handshake",
+    "ping",
+    "join_game",
+    "spectate",
+    "resync",
+    "mulligan",
+    "play_card",
+    "pass",
+    "resign",
+    "restart_game",
+    "add_bot",
+    "request_hint",
+    "list_rooms",
+    "watch_lobby",
+    "chat_message",
+    "emote",
+    "mute_emotes",
+    "watch_tournament",
+    "get_profile",
+];
+
+/// Optional client fields on a state-changing request. A retry with an
+/// `action_id` already seen from the same player gets the first answer
+/// back instead of being applied again; `expected_version` rejects the
+/// request if the room has changed since the client last looked.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ActionGuard {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_version: Option<u64>,
+}
+
+/// Whether the server still serves clients of `version`.
+pub fn supports(version: u32) -> bool {
+    (MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version)
+}
+
+/// A client introducing itself, normally its first event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct HandshakePayload {
+    pub protocol_version: u32,
+    #[serde(default)]
+    pub capabilities: Vec<String>, // Unknown ones are ignored
+    #[serde(default)]
+    pub client: Option<String>, // e.g. "web 1.4.0", for logs
+}
+
+/// Follow one tournament's pairings and standings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct WatchTournamentPayload {
+    pub tournament_id: String,
+}
+
+/// Ask for a player's lifetime statistics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct GetProfilePayload {
+    pub player_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct JoinGamePayload {
+    pub room_id: String,
+    pub player_id: String,
+    pub nickname: String,
+    pub avatar: String,
+    #[serde(default)]
+    pub options: Option<RoomOptions>, // Only applied when this join creates the room
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct MulliganPayload {
+    pub room_id: String,
+    pub player_id: String,
+    pub card_ids: Vec<String>,
+    #[serde(flatten)]
+    pub guard: ActionGuard,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct PlayCardPayload {
+    pub room_id: String,
+    pub player_id: String,
+    pub card_id: String,
+    pub target_id: Option<String>,
+    #[serde(flatten)]
+    pub guard: ActionGuard,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct PassPayload {
+    pub room_id: String,
+    pub player_id: String,
+    #[serde(flatten)]
+    pub guard: ActionGuard,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ResignPayload {
+    pub room_id: String,
+    pub player_id: String,
+    #[serde(flatten)]
+    pub guard: ActionGuard,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct RestartGamePayload {
+    pub room_id: String,
+    pub player_id: String,
+    #[serde(flatten)]
+    pub guard: ActionGuard,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct RequestHintPayload {
+    pub room_id: String,
+    pub player_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct SpectatePayload {
+    pub room_id: String,
+    pub nickname: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ResyncPayload {
+    pub room_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ChatPayload {
+    pub room_id: String,
+    pub player_id: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct EmotePayload {
+    pub room_id: String,
+    pub player_id: String,
+    pub emote: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct MuteEmotesPayload {
+    pub room_id: String,
+    pub player_id: String,
+    pub target_id: String,
+    pub muted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct AddBotPayload {
+    pub room_id: String,
+    #[serde(default)]
+    pub difficulty: BotDifficulty,
+}
+
+/// A `/ws` frame in either direction: a Socket.IO event name and its
+/// payload. A client frame with an `id` is answered by an `ack` frame
+/// carrying the same `id`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct Frame {
+    #[serde(rename = "type")]
+    pub event: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Value>,
+    #[serde(default)]
+    pub data: Value,
+}
+
+/// The server's side of the handshake: the first frame on `/ws`, and the
+/// reply to a `handshake` event with the capabilities both sides share.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct Hello {
+    pub protocol_version: u32,
+    #[serde(default)]
+    pub min_protocol_version: u32,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    pub events: Vec<String>,
+}
+
+impl Hello {
+    pub fn new(capabilities: Vec<String>) -> Self {
+        Hello {
+            protocol_version: PROTOCOL_VERSION,
+            min_protocol_version: MIN_PROTOCOL_VERSION,
+            capabilities,
+            events: CLIENT_EVENTS.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use crate::game_logic;
+use crate::game_types::{BotDifficulty, Card, GamePhase, GameState, Player, PlayerAction, RoomOptions, RuleSet};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct PublicPlayer {
+    pub id: String,
+    pub nickname: String,
+    pub avatar: String,
+    pub hand_size: usize,
+    pub hand: Option<Vec<Card>>, // Only for the viewer's own seat
+    pub board: Vec<Card>,
+    pub discard_pile: Vec<Card>,
+    pub current_score: u32,
+    pub rounds_won: u8,
+    pub passed: bool,
+    pub is_bot: bool,
+    pub bot_difficulty: BotDifficulty,
+}
+
+/// `GameState` without the information a given viewer may not see: other
+/// players' hands and the deck order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct PublicGameState {
+    pub room_id: String,
+    pub phase: GamePhase,
+    pub players: HashMap<String, PublicPlayer>,
+    pub current_turn: String,
+    pub round_count: u32,
+    pub deck_size: usize,
+    pub winner: Option<String>,
+    pub last_update: u64,
+    pub version: u64,
+    pub options: RoomOptions,
+    pub rules: RuleSet,
+    pub created_at: u64,
+    pub creator: Option<String>,
+}
+
+impl PublicGameState {
+    /// The state with what the viewer cannot see left empty: other hands
+    /// and the deck. Enough to work out the viewer's legal actions.
+    pub fn known_game(&self) -> GameState {
+        let players = self.players.iter()
+            .map(|(id, p)| {
+                let player = Player {
+                    id: p.id.clone(),
+                    nickname: p.nickname.clone(),
+                    avatar: p.avatar.clone(),
+                    hand: p.hand.clone().unwrap_or_default(),
+                    board: p.board.clone(),
+                    discard_pile: p.discard_pile.clone(),
+                    current_score: p.current_score,
+                    rounds_won: p.rounds_won,
+                    passed: p.passed,
+                    is_bot: p.is_bot,
+                    bot_difficulty: p.bot_difficulty,
+                };
+                (id.clone(), player)
+            })
+            .collect();
+
+        GameState {
+            room_id: self.room_id.clone(),
+            phase: self.phase.clone(),
+            players,
+            current_turn: self.current_turn.clone(),
+            round_count: self.round_count,
+            deck: Vec::new(),
+            winner: self.winner.clone(),
+            last_update: self.last_update,
+            version: self.version,
+            options: self.options.clone(),
+            rules: self.rules.clone(),
+            created_at: self.created_at,
+            creator: self.creator.clone(),
+        }
+    }
+}
+
+/// Redacts `game` for `viewer`; `None` gives the spectator view.
+pub fn public_state(game: &GameState, viewer: Option<&str>) -> PublicGameState {
+    let players = game.players.iter()
+        .map(|(id, p)| {
+            let player = PublicPlayer {
+                id: p.id.clone(),
+                nickname: p.nickname.clone(),
+                avatar: p.avatar.clone(),
+                hand_size: p.hand.len(),
+                hand: (viewer == Some(id.as_str())).then(|| p.hand.clone()),
+                board: p.board.clone(),
+                discard_pile: p.discard_pile.clone(),
+                current_score: p.current_score,
+                rounds_won: p.rounds_won,
+                passed: p.passed,
+                is_bot: p.is_bot,
+                bot_difficulty: p.bot_difficulty,
+            };
+            (id.clone(), player)
+        })
+        .collect();
+
+    PublicGameState {
+        room_id: game.room_id.clone(),
+        phase: game.phase.clone(),
+        players,
+        current_turn: game.current_turn.clone(),
+        round_count: game.round_count,
+        deck_size: game.deck.len(),
+        winner: game.winner.clone(),
+        last_update: game.last_update,
+        version: game.version,
+        options: game.options.clone(),
+        rules: game.rules.clone(),
+        created_at: game.created_at,
+        creator: game.creator.clone(),
+    }
+}
+
+/// What a bot sees of a room: its redacted state, and when it has to act,
+/// the actions it may take. During the mulligan `legal_actions` is empty
+/// and the bot answers on `/mulligan`.
+#[derive(Debug, Serialize)]
+pub struct BotGameView {
+    pub version: u64,
+    pub your_turn: bool,
+    pub legal_actions: Vec<PlayerAction>,
+    pub state: PublicGameState,
+}
+
+impl BotGameView {
+    /// The view of `state`, already redacted for `player_id`.
+    pub fn new(state: PublicGameState, player_id: &str) -> Self {
+        let player_id = player_id.to_string();
+        let your_turn = match state.phase {
+            GamePhase::Mulligan => state.players.get(&player_id).is_some_and(|p| !p.passed),
+            GamePhase::Playing => state.current_turn == player_id,
+            _ => false,
+        };
+        BotGameView {
+            version: state.version,
+            your_turn,
+            legal_actions: game_logic::legal_actions(&state.known_game(), &player_id),
+            state,
+        }
+    }
+}
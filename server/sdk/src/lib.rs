@@ -0,0 +1,545 @@
+//! Async client for the Poker Tactics server, speaking the `/ws` protocol
+//! described in protocol.md. The game and payload types come from
+//! `poker_tactics_common`, which the server uses too, so a client built
+//! against this crate cannot disagree with the server about their shape.
+//!
+//! ```no_run
+//! use futures_util::StreamExt;
+//! use poker_tactics_client::{Client, ClientConfig, Event};
+//!
+//! # async fn play() -> Result<(), poker_tactics_client::ClientError> {
+//! let (client, mut events) = Client::connect(ClientConfig::new("http://localhost:3000", "room1", "me")).await?;
+//! while let Some(event) = events.next().await {
+//!     if let Event::State(_) = event {
+//!         if let Some(action) = client.view().filter(|v| v.your_turn).and_then(|v| v.legal_actions.last().cloned()) {
+//!             client.act(action).await?;
+//!         }
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub use poker_tactics_common::chat::{ChatMessage, EmoteEvent};
+pub use poker_tactics_common::errors::ErrorCode;
+pub use poker_tactics_common::game_types::{Card, GamePhase, PlayerAction};
+pub use poker_tactics_common::view::{BotGameView, PublicGameState, PublicPlayer};
+
+use futures_util::{SinkExt, Stream, StreamExt};
+use serde_json::Value;
+use poker_tactics_common::delta::{self, StateDelta};
+use poker_tactics_common::errors::Ack;
+use poker_tactics_common::protocol::*;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+type Socket = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+type Reply = oneshot::Sender<Result<u64, ClientError>>;
+
+/// Where to play, and as whom.
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    pub url: String, // Server base URL, e.g. http://localhost:3000
+    pub room_id: String,
+    pub player_id: String,
+    pub nickname: String,
+    pub avatar: String,
+    pub reconnect_delay: Duration, // First wait after the connection drops; doubles on each failed attempt
+    pub max_reconnect_delay: Duration,
+}
+
+impl ClientConfig {
+    pub fn new(url: impl Into<String>, room_id: impl Into<String>, player_id: impl Into<String>) -> Self {
+        let player_id = player_id.into();
+        ClientConfig {
+            url: url.into(),
+            room_id: room_id.into(),
+            nickname: player_id.clone(),
+            player_id,
+            avatar: "🤖".to_string(),
+            reconnect_delay: Duration::from_millis(500),
+            max_reconnect_delay: Duration::from_secs(10),
+        }
+    }
+
+    /// The `/ws` endpoint; `http(s)://` becomes `ws(s)://`.
+    fn ws_url(&self) -> String {
+        let base = self.url.trim_end_matches('/');
+        let base = match base.strip_prefix("http") {
+            Some(rest) => format!("ws{}", rest),
+            None => base.to_string(),
+        };
+        format!("{}/ws?version={}", base, PROTOCOL_VERSION)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientError {
+    /// The server refused the request.
+    Rejected { code: ErrorCode, message: String },
+    /// The server could not be reached, or speaks another protocol.
+    Connect(String),
+    /// The client was dropped before the server answered.
+    Closed,
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Rejected { code, message } => write!(f, "{} ({})", message, code.as_str()),
+            ClientError::Connect(message) => write!(f, "cannot connect: {}", message),
+            ClientError::Closed => write!(f, "client closed"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+/// What happened in the room, in the order the server sent it.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The room's new state as this player sees it: a snapshot, or the
+    /// last state with a delta applied. `Client::state` returns the same
+    /// value.
+    State(Box<PublicGameState>),
+    /// The room's recent messages, sent on every (re)join.
+    ChatHistory(Vec<ChatMessage>),
+    Chat(ChatMessage),
+    Emote(EmoteEvent),
+    /// The server's message for a failed request.
+    Error(String),
+    /// The connection dropped; the client is reconnecting.
+    Disconnected,
+    /// Back in the room. Requests sent while away have been retried.
+    Reconnected,
+    /// An event this crate does not model, such as `hints`.
+    Other { event: String, data: Value },
+}
+
+/// The stream of room events. Ends when the client is dropped.
+pub struct Events(mpsc::UnboundedReceiver<Event>);
+
+impl Stream for Events {
+    type Item = Event;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+        self.0.poll_recv(cx)
+    }
+}
+
+struct Request {
+    event: &'static str,
+    data: Value,
+    reply: Reply,
+}
+
+/// A seat in one room. Dropping the client closes the connection.
+pub struct Client {
+    config: ClientConfig,
+    requests: mpsc::UnboundedSender<Request>,
+    state: watch::Receiver<Option<PublicGameState>>,
+}
+
+fn to_value(payload: &impl serde::Serialize) -> Value {
+    serde_json::to_value(payload).expect("payloads serialize")
+}
+
+impl Client {
+    /// Connects and joins the room, returning once the first snapshot has
+    /// arrived.
+    pub async fn connect(config: ClientConfig) -> Result<(Client, Events), ClientError> {
+        let socket = open(&config).await?;
+        let (requests, incoming) = mpsc::unbounded_channel();
+        let (events, received) = mpsc::unbounded_channel();
+        let (published, state) = watch::channel(None);
+
+        let driver = Driver {
+            config: config.clone(),
+            requests: incoming,
+            events,
+            state: published,
+            pending: BTreeMap::new(),
+            next_id: 1,
+            last_error: None,
+            resync_requested: false,
+        };
+        tokio::spawn(driver.run(socket));
+
+        let client = Client { config, requests, state };
+        client.request("join_game", to_value(&join_payload(&client.config))).await?;
+        Ok((client, Events(received)))
+    }
+
+    /// The latest state of the room, with only this player's hand.
+    pub fn state(&self) -> Option<PublicGameState> {
+        self.state.borrow().clone()
+    }
+
+    /// Receiver that wakes on every new state.
+    pub fn watch(&self) -> watch::Receiver<Option<PublicGameState>> {
+        self.state.clone()
+    }
+
+    /// The room as the bot API shows it to this player: the redacted
+    /// state, whether it is their move, and their legal actions.
+    pub fn view(&self) -> Option<BotGameView> {
+        self.state.borrow().clone().map(|state| BotGameView::new(state, &self.config.player_id))
+    }
+
+    pub fn player_id(&self) -> &str {
+        &self.config.player_id
+    }
+
+    /// A fresh action id, and the version the action is meant for.
+    fn guard(&self) -> ActionGuard {
+        ActionGuard {
+            action_id: Some(uuid::Uuid::new_v4().to_string()),
+            expected_version: self.state.borrow().as_ref().map(|game| game.version),
+        }
+    }
+
+    /// Sends a request and waits for its ack, then for the state it
+    /// reports, so `state()` reflects the request once this returns.
+    async fn request(&self, event: &'static str, data: Value) -> Result<u64, ClientError> {
+        let (reply, response) = oneshot::channel();
+        self.requests.send(Request { event, data, reply }).map_err(|_| ClientError::Closed)?;
+        let version = response.await.map_err(|_| ClientError::Closed)??;
+        let mut state = self.state.clone();
+        state.wait_for(|game| game.as_ref().is_some_and(|g| g.version >= version)).await.map_err(|_| ClientError::Closed)?;
+        Ok(version)
+    }
+
+    /// The game calls below return the state version the action left the
+    /// room at.
+    pub async fn mulligan(&self, card_ids: Vec<String>) -> Result<u64, ClientError> {
+        let (room_id, player_id) = (self.config.room_id.clone(), self.config.player_id.clone());
+        let payload = MulliganPayload { room_id, player_id, card_ids, guard: self.guard() };
+        self.request("mulligan", to_value(&payload)).await
+    }
+
+    pub async fn play(&self, card_id: impl Into<String>, target_id: Option<String>) -> Result<u64, ClientError> {
+        let (room_id, player_id) = (self.config.room_id.clone(), self.config.player_id.clone());
+        let payload = PlayCardPayload { room_id, player_id, card_id: card_id.into(), target_id, guard: self.guard() };
+        self.request("play_card", to_value(&payload)).await
+    }
+
+    pub async fn pass(&self) -> Result<u64, ClientError> {
+        let (room_id, player_id) = (self.config.room_id.clone(), self.config.player_id.clone());
+        let payload = PassPayload { room_id, player_id, guard: self.guard() };
+        self.request("pass", to_value(&payload)).await
+    }
+
+    pub async fn resign(&self) -> Result<u64, ClientError> {
+        let (room_id, player_id) = (self.config.room_id.clone(), self.config.player_id.clone());
+        let payload = ResignPayload { room_id, player_id, guard: self.guard() };
+        self.request("resign", to_value(&payload)).await
+    }
+
+    /// Plays, passes or resigns, e.g. with one of `view().legal_actions`.
+    pub async fn act(&self, action: PlayerAction) -> Result<u64, ClientError> {
+        match action {
+            PlayerAction::Play { card_id, target_id } => self.play(card_id, target_id).await,
+            PlayerAction::Pass => self.pass().await,
+            PlayerAction::Resign => self.resign().await,
+        }
+    }
+
+    /// Asks for a full snapshot. The client already does this when it
+    /// notices a missed delta.
+    pub async fn resync(&self) -> Result<u64, ClientError> {
+        let payload = ResyncPayload { room_id: self.config.room_id.clone() };
+        self.request("resync", to_value(&payload)).await
+    }
+}
+
+fn join_payload(config: &ClientConfig) -> JoinGamePayload {
+    JoinGamePayload {
+        room_id: config.room_id.clone(),
+        player_id: config.player_id.clone(),
+        nickname: config.nickname.clone(),
+        avatar: config.avatar.clone(),
+        options: None,
+    }
+}
+
+/// The next frame, or `None` once the connection is gone.
+async fn next_frame(socket: &mut Socket) -> Option<Frame> {
+    while let Some(Ok(message)) = socket.next().await {
+        match message {
+            Message::Text(text) => {
+                if let Ok(frame) = serde_json::from_str(&text) {
+                    return Some(frame);
+                }
+            },
+            Message::Close(_) => return None,
+            _ => {},
+        }
+    }
+    None
+}
+
+/// Connects and checks the server's `hello`.
+async fn open(config: &ClientConfig) -> Result<Socket, ClientError> {
+    let (mut socket, _) = tokio_tungstenite::connect_async(config.ws_url()).await
+        .map_err(|e| ClientError::Connect(e.to_string()))?;
+    let hello = match next_frame(&mut socket).await {
+        Some(frame) if frame.event == "hello" => serde_json::from_value::<Hello>(frame.data)
+            .map_err(|e| ClientError::Connect(format!("bad hello: {}", e)))?,
+        _ => return Err(ClientError::Connect("the server did not say hello".to_string())),
+    };
+    if !(hello.min_protocol_version..=hello.protocol_version).contains(&PROTOCOL_VERSION) {
+        return Err(ClientError::Connect(format!(
+            "the server supports protocol {} to {}, this client speaks {}",
+            hello.min_protocol_version, hello.protocol_version, PROTOCOL_VERSION
+        )));
+    }
+    Ok(socket)
+}
+
+/// A request sent and not yet acknowledged, kept to resend after a
+/// reconnect. Game actions carry an action id, so a resend the server has
+/// already applied just returns the first result.
+struct Pending {
+    frame: String,
+    reply: Option<Reply>,
+}
+
+/// Owns the connection: sends requests, matches acks to them, keeps the
+/// state current, and reconnects when the socket drops.
+struct Driver {
+    config: ClientConfig,
+    requests: mpsc::UnboundedReceiver<Request>,
+    events: mpsc::UnboundedSender<Event>,
+    state: watch::Sender<Option<PublicGameState>>,
+    pending: BTreeMap<u64, Pending>, // By frame id, so resends keep their order
+    next_id: u64,
+    last_error: Option<String>, // The `error` event that precedes a failed ack
+    resync_requested: bool,
+}
+
+impl Driver {
+    async fn run(mut self, mut socket: Socket) {
+        loop {
+            if self.serve(&mut socket).await {
+                let _ = socket.close(None).await;
+                return;
+            }
+            let _ = self.events.send(Event::Disconnected);
+            socket = match self.reconnect().await {
+                Some(socket) => socket,
+                None => return,
+            };
+            let _ = self.events.send(Event::Reconnected);
+        }
+    }
+
+    /// Handles traffic until the socket drops (false) or the client is
+    /// dropped (true).
+    async fn serve(&mut self, socket: &mut Socket) -> bool {
+        loop {
+            tokio::select! {
+                message = socket.next() => match message {
+                    Some(Ok(Message::Text(text))) => self.receive(socket, &text).await,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return false,
+                    Some(Ok(_)) => {},
+                },
+                request = self.requests.recv() => match request {
+                    Some(request) => self.send(socket, request.event, request.data, Some(request.reply)).await,
+                    None => return true,
+                },
+            }
+        }
+    }
+
+    /// Waits with backoff until the server is back, then rejoins and
+    /// resends what was pending. `None` if the client was dropped first.
+    async fn reconnect(&mut self) -> Option<Socket> {
+        let mut delay = self.config.reconnect_delay;
+        loop {
+            tokio::time::sleep(delay).await;
+            if self.requests.is_closed() {
+                return None;
+            }
+            if let Ok(mut socket) = open(&self.config).await {
+                let frames: Vec<String> = self.pending.values().map(|p| p.frame.clone()).collect();
+                self.send(&mut socket, "join_game", to_value(&join_payload(&self.config)), None).await;
+                for frame in frames {
+                    let _ = socket.send(Message::Text(frame)).await;
+                }
+                return Some(socket);
+            }
+            delay = (delay * 2).min(self.config.max_reconnect_delay);
+        }
+    }
+
+    /// Sends a request that expects an ack. A send that fails stays
+    /// pending and goes out again after the reconnect.
+    async fn send(&mut self, socket: &mut Socket, event: &str, data: Value, reply: Option<Reply>) {
+        let id = self.next_id;
+        self.next_id += 1;
+        let frame = serde_json::to_string(&Frame { event: event.to_string(), id: Some(id.into()), data }).expect("frames serialize");
+        let _ = socket.send(Message::Text(frame.clone())).await;
+        self.pending.insert(id, Pending { frame, reply });
+    }
+
+    fn publish(&mut self, game: PublicGameState) {
+        self.state.send_replace(Some(game.clone()));
+        let _ = self.events.send(Event::State(Box::new(game)));
+    }
+
+    async fn receive(&mut self, socket: &mut Socket, text: &str) {
+        let frame: Frame = match serde_json::from_str(text) {
+            Ok(frame) => frame,
+            Err(_) => return,
+        };
+        match frame.event.as_str() {
+            "game_state_update" => {
+                if let Ok(game) = serde_json::from_value::<PublicGameState>(frame.data) {
+                    if game.room_id == self.config.room_id {
+                        self.resync_requested = false;
+                        self.publish(game);
+                    }
+                }
+            },
+            "game_state_delta" => {
+                let update: StateDelta = match serde_json::from_value(frame.data) {
+                    Ok(update) => update,
+                    Err(_) => return,
+                };
+                let patched = delta::apply_delta(self.state.borrow().as_ref(), &update);
+                match patched {
+                    Ok(Some(game)) => self.publish(game),
+                    Ok(None) => {},
+                    // A delta went missing: start over from a snapshot.
+                    Err(_) if !self.resync_requested => {
+                        self.resync_requested = true;
+                        let payload = ResyncPayload { room_id: update.room_id };
+                        let frame = Frame { event: "resync".to_string(), id: None, data: to_value(&payload) };
+                        let _ = socket.send(Message::Text(serde_json::to_string(&frame).expect("frames serialize"))).await;
+                    },
+                    Err(_) => {},
+                }
+            },
+            "chat_history" => {
+                if let Ok(messages) = serde_json::from_value(frame.data) {
+                    let _ = self.events.send(Event::ChatHistory(messages));
+                }
+            },
+            "chat_message" => {
+                if let Ok(message) = serde_json::from_value(frame.data) {
+                    let _ = self.events.send(Event::Chat(message));
+                }
+            },
+            "emote" => {
+                if let Ok(emote) = serde_json::from_value(frame.data) {
+                    let _ = self.events.send(Event::Emote(emote));
+                }
+            },
+            "error" => {
+                let message = frame.data.as_str().unwrap_or_default().to_string();
+                self.last_error = Some(message.clone());
+                let _ = self.events.send(Event::Error(message));
+            },
+            "ack" => {
+                let pending = frame.id.as_ref().and_then(Value::as_u64).and_then(|id| self.pending.remove(&id));
+                let ack: Ack = match serde_json::from_value(frame.data) {
+                    Ok(ack) => ack,
+                    Err(_) => return,
+                };
+                let message = self.last_error.take().unwrap_or_default();
+                let result = match ack.error_code {
+                    None if ack.ok => Ok(ack.state_version.unwrap_or_default()),
+                    code => Err(ClientError::Rejected { code: code.unwrap_or(ErrorCode::InvalidMessage), message }),
+                };
+                if let Some(reply) = pending.and_then(|p| p.reply) {
+                    let _ = reply.send(result);
+                }
+            },
+            "hello" => {},
+            _ => {
+                let _ = self.events.send(Event::Other { event: frame.event, data: frame.data });
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use server::chat::ChatConfig;
+    use server::game_types::RuleSet;
+    use server::records::Records;
+    use server::tournament::Tournaments;
+    use server::room::RoomRegistry;
+    use server::session::Services;
+    use std::sync::Arc;
+
+    async fn serve() -> String {
+        let chat = Arc::new(ChatConfig::default());
+        let records = Arc::new(tokio::sync::RwLock::new(Records::default()));
+        let rooms = RoomRegistry::new(records.clone(), chat.clone());
+        let app = server::ws::router(Services { tournaments: Tournaments::new(rooms.clone(), records.clone(), RuleSet::default()), rooms, default_rules: RuleSet::default(), chat, records });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        url
+    }
+
+    /// Waits for the next state event matching `done`.
+    async fn until(events: &mut Events, done: impl Fn(&PublicGameState) -> bool) -> PublicGameState {
+        let wait = async {
+            while let Some(event) = events.next().await {
+                if let Event::State(game) = event {
+                    if done(&game) {
+                        return *game;
+                    }
+                }
+            }
+            panic!("events ended");
+        };
+        tokio::time::timeout(Duration::from_secs(5), wait).await.expect("no matching state")
+    }
+
+    #[tokio::test]
+    async fn test_two_clients_play_a_match() {
+        let url = serve().await;
+        let (alice, mut alice_events) = Client::connect(ClientConfig::new(&url, "sdk", "alice")).await.unwrap();
+        let (bob, mut bob_events) = Client::connect(ClientConfig::new(&url, "sdk", "bob")).await.unwrap();
+        assert_eq!(bob.state().unwrap().phase, GamePhase::Mulligan);
+
+        // Alice learns of Bob's join through a delta.
+        until(&mut alice_events, |g| g.players.len() == 2).await;
+        alice.mulligan(Vec::new()).await.unwrap();
+        bob.mulligan(Vec::new()).await.unwrap();
+        let game = until(&mut alice_events, |g| g.phase == GamePhase::Playing).await;
+
+        let (mover, waiter) = if game.current_turn == "alice" { (&alice, &bob) } else { (&bob, &alice) };
+        let error = waiter.pass().await.unwrap_err();
+        assert!(matches!(error, ClientError::Rejected { code: ErrorCode::NotYourTurn, .. }), "{:?}", error);
+
+        let view = mover.view().unwrap();
+        assert!(view.your_turn);
+        assert!(view.state.players.values().all(|p| p.hand.is_some() == (p.id == mover.player_id())));
+        let version = mover.act(view.legal_actions[0].clone()).await.unwrap();
+        assert_eq!(mover.state().unwrap().version, version);
+
+        bob.resign().await.unwrap();
+        let game = until(&mut bob_events, |g| g.phase == GamePhase::GameEnd).await;
+        assert_eq!(game.winner.as_deref(), Some("alice"));
+        until(&mut alice_events, |g| g.phase == GamePhase::GameEnd).await;
+    }
+
+    #[tokio::test]
+    async fn test_urls_and_unreachable_servers() {
+        let config = ClientConfig::new("https://example.com/", "r", "p");
+        assert_eq!(config.ws_url(), format!("wss://example.com/ws?version={}", PROTOCOL_VERSION));
+        let closed = Client::connect(ClientConfig::new("http://127.0.0.1:9", "r", "p")).await;
+        assert!(matches!(closed, Err(ClientError::Connect(_))));
+    }
+}
@@ -0,0 +1,225 @@
+use crate::game_types::{CardAbility, GamePhase, GameState, PlayerAction};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// What has to happen, from one player's point of view, for an achievement
+/// to unlock. Checked against the states before and after every action.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// Win a round outright with at least two 2s bonded on the board.
+    RoundWonWithBond,
+    /// One play of the player sends at least this many units to the discard.
+    Scorched(usize),
+    /// Win the match without dropping a round or playing a card with this ability.
+    SweepWithout(CardAbility),
+    /// Revive a card with this ability with a Medic.
+    Revived(CardAbility),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Achievement {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    #[serde(skip)]
+    pub condition: Condition,
+}
+
+pub static ACHIEVEMENTS: [Achievement; 4] = [
+    Achievement {
+        id: "iron_bond",
+        name: "Iron Bond",
+        description: "Win a round with both 2s bonded",
+        condition: Condition::RoundWonWithBond,
+    },
+    Achievement {
+        id: "wildfire",
+        name: "Wildfire",
+        description: "Scorch three or more units at once",
+        condition: Condition::Scorched(3),
+    },
+    Achievement {
+        id: "no_kings_needed",
+        name: "No Kings Needed",
+        description: "Win the match 2-0 without playing a King",
+        condition: Condition::SweepWithout(CardAbility::Hero),
+    },
+    Achievement {
+        id: "double_agent",
+        name: "Double Agent",
+        description: "Revive a Spy with a Medic",
+        condition: Condition::Revived(CardAbility::Intel),
+    },
+];
+
+/// Sent to the room when a player unlocks an achievement for the first time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AchievementUnlocked {
+    pub player_id: String,
+    pub nickname: String,
+    pub id: String,
+    pub name: String,
+    pub description: String,
+}
+
+impl AchievementUnlocked {
+    pub fn new(player_id: &str, nickname: &str, achievement: &Achievement) -> Self {
+        AchievementUnlocked {
+            player_id: player_id.to_string(),
+            nickname: nickname.to_string(),
+            id: achievement.id.to_string(),
+            name: achievement.name.to_string(),
+            description: achievement.description.to_string(),
+        }
+    }
+}
+
+fn discarded_ids(game: &GameState) -> HashSet<&str> {
+    game.players.values().flat_map(|p| p.discard_pile.iter().map(|c| c.id.as_str())).collect()
+}
+
+impl Condition {
+    /// Whether `actor` taking `action` on `before`, giving `after`, meets
+    /// this condition for `player_id`.
+    fn met(&self, before: &GameState, after: &GameState, actor: &str, action: &PlayerAction, player_id: &str) -> bool {
+        let (Some(own), Some(own_after)) = (before.players.get(player_id), after.players.get(player_id)) else {
+            return false;
+        };
+        let other_after = after.players.values().find(|p| p.id != player_id);
+        let won_round = own_after.rounds_won > own.rounds_won
+            && other_after.is_some_and(|o| before.players.get(&o.id).is_some_and(|b| b.rounds_won == o.rounds_won));
+        let own_play = match action {
+            PlayerAction::Play { card_id, target_id } if actor == player_id => Some((card_id, target_id)),
+            _ => None,
+        };
+
+        match self {
+            Condition::RoundWonWithBond => {
+                won_round && own.board.iter().filter(|c| c.ability == CardAbility::IronGuard).count() >= 2
+            },
+            Condition::Scorched(units) => own_play.is_some() && {
+                let earlier = discarded_ids(before);
+                discarded_ids(after).difference(&earlier).count() >= *units
+            },
+            Condition::SweepWithout(ability) => {
+                before.phase != GamePhase::GameEnd
+                    && after.winner.as_deref() == Some(player_id)
+                    && own_after.rounds_won >= after.rules.rounds_to_win
+                    && other_after.is_some_and(|o| o.rounds_won == 0)
+                    && !own_after.board.iter().chain(&own_after.discard_pile).any(|c| c.ability == *ability)
+            },
+            Condition::Revived(ability) => match own_play {
+                Some((card_id, Some(target_id))) => {
+                    let played_medic = own.hand.iter().any(|c| &c.id == card_id && c.ability == CardAbility::Medic);
+                    let target = own.discard_pile.iter().find(|c| &c.id == target_id);
+                    played_medic
+                        && target.is_some_and(|c| c.ability == *ability)
+                        && !own_after.discard_pile.iter().any(|c| &c.id == target_id)
+                },
+                _ => false,
+            },
+        }
+    }
+}
+
+/// Achievements met by `actor`'s action, as `(player id, achievement)`
+/// pairs for every human player. Whether one is new is up to the caller.
+pub fn evaluate(before: &GameState, after: &GameState, actor: &str, action: &PlayerAction) -> Vec<(String, &'static Achievement)> {
+    let mut met = Vec::new();
+    let mut players: Vec<&String> = after.players.iter().filter(|(_, p)| !p.is_bot).map(|(id, _)| id).collect();
+    players.sort();
+    for player_id in players {
+        for achievement in &ACHIEVEMENTS {
+            if achievement.condition.met(before, after, actor, action, player_id) {
+                met.push((player_id.clone(), achievement));
+            }
+        }
+    }
+    met
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic;
+    use crate::game_types::{Card, Rank, Suit};
+
+    fn card(id: &str, rank: Rank, power: u8, ability: CardAbility) -> Card {
+        Card {
+            id: id.to_string(),
+            suit: Suit::Heart,
+            rank,
+            base_power: power,
+            current_power: power,
+            ability,
+            owner_id: "p1".to_string(),
+        }
+    }
+
+    fn playing() -> GameState {
+        let mut game = game_logic::init_game("room_test".to_string(), vec!["p1".to_string(), "p2".to_string()]);
+        game.phase = GamePhase::Playing;
+        game.current_turn = "p1".to_string();
+        for player in game.players.values_mut() {
+            player.hand.clear();
+        }
+        game
+    }
+
+    fn ids<'a>(met: &'a [(String, &'static Achievement)]) -> Vec<(&'a str, &'a str)> {
+        met.iter().map(|(player, a)| (player.as_str(), a.id)).collect()
+    }
+
+    #[test]
+    fn test_scorch_and_medic_achievements() {
+        let mut game = playing();
+        game.players.get_mut("p1").unwrap().hand.push(card("ace", Rank::Ace, 1, CardAbility::Burn));
+        for (i, owner) in ["p1", "p2", "p2"].iter().enumerate() {
+            game.players.get_mut(*owner).unwrap().board.push(card(&format!("nine{}", i), Rank::Number(9), 9, CardAbility::None));
+        }
+        let before = game.clone();
+        let action = PlayerAction::Play { card_id: "ace".to_string(), target_id: None };
+        game_logic::apply_action(&mut game, &"p1".to_string(), &action).unwrap();
+        assert_eq!(ids(&evaluate(&before, &game, "p1", &action)), [("p1", "wildfire")]);
+
+        let mut game = playing();
+        let p1 = game.players.get_mut("p1").unwrap();
+        p1.hand.push(card("queen", Rank::Queen, 1, CardAbility::Medic));
+        p1.discard_pile.push(card("jack", Rank::Jack, 1, CardAbility::Intel));
+        let before = game.clone();
+        let action = PlayerAction::Play { card_id: "queen".to_string(), target_id: Some("jack".to_string()) };
+        game_logic::apply_action(&mut game, &"p1".to_string(), &action).unwrap();
+        assert_eq!(ids(&evaluate(&before, &game, "p1", &action)), [("p1", "double_agent")]);
+    }
+
+    #[test]
+    fn test_round_and_match_achievements() {
+        let mut game = playing();
+        let p1 = game.players.get_mut("p1").unwrap();
+        p1.board.push(card("two_a", Rank::Number(2), 2, CardAbility::IronGuard));
+        p1.board.push(card("two_b", Rank::Number(2), 2, CardAbility::IronGuard));
+        p1.current_score = 4;
+        p1.rounds_won = 1;
+        game.players.get_mut("p2").unwrap().passed = true;
+
+        let before = game.clone();
+        game_logic::apply_action(&mut game, &"p1".to_string(), &PlayerAction::Pass).unwrap();
+        assert_eq!(game.winner.as_deref(), Some("p1"));
+        assert_eq!(ids(&evaluate(&before, &game, "p1", &PlayerAction::Pass)), [("p1", "iron_bond"), ("p1", "no_kings_needed")]);
+
+        // A King played earlier in the match rules out the sweep.
+        let mut game = before.clone();
+        game.players.get_mut("p1").unwrap().discard_pile.push(card("king", Rank::King, 10, CardAbility::Hero));
+        let before = game.clone();
+        game_logic::apply_action(&mut game, &"p1".to_string(), &PlayerAction::Pass).unwrap();
+        assert_eq!(ids(&evaluate(&before, &game, "p1", &PlayerAction::Pass)), [("p1", "iron_bond")]);
+
+        // Winning by resignation is not a sweep.
+        let mut game = playing();
+        let before = game.clone();
+        game_logic::apply_action(&mut game, &"p2".to_string(), &PlayerAction::Resign).unwrap();
+        assert_eq!(game.winner.as_deref(), Some("p1"));
+        assert!(evaluate(&before, &game, "p2", &PlayerAction::Resign).is_empty());
+    }
+}
@@ -0,0 +1,161 @@
+use crate::achievements::{Achievement, ACHIEVEMENTS};
+use crate::endgame;
+use crate::game_types::GameState;
+use crate::lobby::{LobbyFilter, RoomInfo};
+use crate::metrics::metrics;
+use crate::records::{MatchRecord, PlayerStats, Records};
+use crate::room::RoomRegistry;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, LazyLock};
+use std::time::Instant;
+use tokio::sync::RwLock;
+
+pub use poker_tactics_common::view::{public_state, PublicGameState, PublicPlayer};
+
+/// Search budget per position for `/api/matches/:id/analysis`.
+const ANALYSIS_NODE_LIMIT: u64 = 200_000;
+
+static STARTED: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+pub type SharedRecords = Arc<RwLock<Records>>;
+
+#[derive(Clone)]
+pub struct ApiState {
+    pub rooms: RoomRegistry,
+    pub records: SharedRecords,
+}
+
+#[derive(Debug, Serialize)]
+struct ApiError {
+    error: String,
+}
+
+fn not_found(what: &str) -> Response {
+    (StatusCode::NOT_FOUND, Json(ApiError { error: format!("{} not found", what) })).into_response()
+}
+
+/// A finished match with every intermediate state. Hands are not redacted
+/// since the match is over.
+#[derive(Debug, Serialize)]
+struct Replay {
+    #[serde(flatten)]
+    record: MatchRecord,
+    frames: Vec<GameState>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LeaderboardQuery {
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct Health {
+    status: &'static str,
+    uptime_seconds: u64,
+    rooms: usize,
+}
+
+/// Read-only JSON endpoints under `/api`, plus `/healthz` and the
+/// Prometheus `/metrics`.
+pub fn router(state: ApiState) -> Router {
+    LazyLock::force(&STARTED);
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/metrics", get(prometheus_metrics))
+        .route("/api/protocol", get(protocol_schema))
+        .route("/api/rooms", get(list_rooms))
+        .route("/api/rooms/:id", get(get_room))
+        .route("/api/matches/:id/replay", get(get_replay))
+        .route("/api/matches/:id/analysis", get(get_analysis))
+        .route("/api/players/:id", get(get_player))
+        .route("/api/players/:id/profile", get(get_profile))
+        .route("/api/leaderboard", get(get_leaderboard))
+        .route("/api/achievements", get(list_achievements))
+        .with_state(state)
+}
+
+/// The JSON Schema for every client and server event.
+async fn protocol_schema() -> Json<serde_json::Value> {
+    Json(crate::protocol::schema())
+}
+
+async fn healthz(State(state): State<ApiState>) -> Json<Health> {
+    Json(Health {
+        status: "ok",
+        uptime_seconds: STARTED.elapsed().as_secs(),
+        rooms: state.rooms.len(),
+    })
+}
+
+async fn prometheus_metrics(State(state): State<ApiState>) -> impl IntoResponse {
+    let text = metrics().render(state.rooms.snapshots().iter());
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], text)
+}
+
+/// One page of the public rooms; the number matching the filter is in
+/// `X-Total-Count`.
+async fn list_rooms(State(state): State<ApiState>, Query(filter): Query<LobbyFilter>) -> ([(&'static str, String); 1], Json<Vec<RoomInfo>>) {
+    let page = filter.page(&state.rooms.snapshots());
+    ([("x-total-count", page.total.to_string())], Json(page.rooms))
+}
+
+async fn get_room(State(state): State<ApiState>, Path(id): Path<String>) -> Response {
+    match state.rooms.get(&id) {
+        Ok(room) => Json(public_state(&room.snapshot(), None)).into_response(),
+        Err(_) => not_found("Room"),
+    }
+}
+
+async fn get_replay(State(state): State<ApiState>, Path(id): Path<String>) -> Response {
+    let record = match state.records.read().await.get_match(&id) {
+        Some(record) => record.clone(),
+        None => return not_found("Match"),
+    };
+    match record.frames() {
+        Ok(frames) => Json(Replay { record, frames }).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError { error: e })).into_response(),
+    }
+}
+
+/// The endgame solver's review of every move it can settle in a match.
+/// Solving is CPU-bound, so it runs off the async workers.
+async fn get_analysis(State(state): State<ApiState>, Path(id): Path<String>) -> Response {
+    let record = match state.records.read().await.get_match(&id) {
+        Some(record) => record.clone(),
+        None => return not_found("Match"),
+    };
+    let reviews = tokio::task::spawn_blocking(move || endgame::review(&record.opening, &record.steps, ANALYSIS_NODE_LIMIT)).await;
+    match reviews {
+        Ok(Ok(reviews)) => Json(reviews).into_response(),
+        Ok(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError { error: e })).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError { error: e.to_string() })).into_response(),
+    }
+}
+
+async fn get_player(State(state): State<ApiState>, Path(id): Path<String>) -> Response {
+    match state.records.read().await.player(&id) {
+        Some(stats) => Json(stats.clone()).into_response(),
+        None => not_found("Player"),
+    }
+}
+
+async fn get_profile(State(state): State<ApiState>, Path(id): Path<String>) -> Response {
+    match state.records.read().await.profile(&id) {
+        Some(profile) => Json(profile.clone()).into_response(),
+        None => not_found("Player"),
+    }
+}
+
+async fn list_achievements() -> Json<&'static [Achievement]> {
+    Json(&ACHIEVEMENTS)
+}
+
+async fn get_leaderboard(State(state): State<ApiState>, Query(query): Query<LeaderboardQuery>) -> Json<Vec<PlayerStats>> {
+    let limit = query.limit.unwrap_or(50).min(500);
+    Json(state.records.read().await.leaderboard(limit))
+}
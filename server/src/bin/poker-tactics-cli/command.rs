@@ -0,0 +1,86 @@
+use server::game_types::{CardAbility, GameState, PlayerAction};
+use server::notation::find_card;
+
+pub const HELP: &str = "\
+Commands:
+  play <card> [--target <card>]   e.g. `play H7`, `play SQ --target D5`
+  pass                            pass for the rest of the round
+  mulligan <card> [<card>]        swap up to two cards, e.g. `mulligan H3 C4`
+  keep                            keep your opening hand
+  board                           show the board again
+  resign                          concede the match
+  restart                         start a new match after game over
+  say <text>                      chat with the room (remote games)
+  help, quit";
+
+/// A line of input, resolved against the current state into card ids.
+#[derive(Debug)]
+pub enum Command {
+    Action(PlayerAction),
+    Mulligan(Vec<String>),
+    Restart,
+    Chat(String),
+    Board,
+    Help,
+    Quit,
+}
+
+pub fn parse(line: &str, game: Option<&GameState>, viewer: &String) -> Result<Command, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (verb, args) = match words.split_first() {
+        Some((verb, args)) => (verb.to_lowercase(), args),
+        None => return Err("Type `help` for commands".to_string()),
+    };
+
+    match verb.as_str() {
+        "help" | "?" => return Ok(Command::Help),
+        "quit" | "exit" => return Ok(Command::Quit),
+        "board" => return Ok(Command::Board),
+        "restart" => return Ok(Command::Restart),
+        "pass" => return Ok(Command::Action(PlayerAction::Pass)),
+        "resign" => return Ok(Command::Action(PlayerAction::Resign)),
+        "say" => {
+            let text = line.trim_start()[verb.len()..].trim();
+            return Ok(Command::Chat(text.to_string()));
+        },
+        _ => {},
+    }
+
+    let me = game
+        .and_then(|g| g.players.get(viewer))
+        .ok_or("No game yet")?;
+
+    match verb.as_str() {
+        "keep" => Ok(Command::Mulligan(Vec::new())),
+        "mulligan" => {
+            // Naming the same card twice is an error, not a double swap.
+            let mut remaining = me.hand.clone();
+            let mut ids: Vec<String> = Vec::new();
+            for code in args {
+                let id = find_card(&remaining, code)?.id.clone();
+                remaining.retain(|c| c.id != id);
+                ids.push(id);
+            }
+            Ok(Command::Mulligan(ids))
+        },
+        "play" => {
+            let (code, rest) = args.split_first().ok_or("Usage: play <card> [--target <card>]")?;
+            let card = find_card(&me.hand, code)?;
+
+            let target_id = match rest {
+                [] => None,
+                ["--target", target] | ["-t", target] => {
+                    let zone = match card.ability {
+                        CardAbility::Medic => &me.discard_pile,
+                        _ => &me.board,
+                    };
+                    Some(find_card(zone, target)?.id.clone())
+                },
+                _ => return Err("Usage: play <card> [--target <card>]".to_string()),
+            };
+
+            Ok(Command::Action(PlayerAction::Play { card_id: card.id.clone(), target_id }))
+        },
+        other => Err(format!("Unknown command `{}`; type `help`", other)),
+    }
+}
@@ -0,0 +1,132 @@
+use crate::command::{self, Command, HELP};
+use crate::render::render;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use server::api::public_state;
+use server::game_logic;
+use server::game_types::{BotDifficulty, GamePhase, GameState, PlayerAction};
+use server::notation::card_code;
+use server::scenario::Scenario;
+use server::bot;
+use std::io::{self, BufRead, Write};
+
+const YOU: &str = "you";
+const BOT: &str = "bot";
+
+/// A fresh deal, or the scenario's position with its first player as you
+/// and its second as the bot.
+fn new_game(difficulty: BotDifficulty, scenario: Option<&Scenario>, rng: &mut StdRng) -> Result<GameState, String> {
+    let mut game = match scenario {
+        Some(scenario) => {
+            let mut scenario = scenario.clone();
+            let mut turn = scenario.current_turn.clone();
+            for (player, id) in scenario.players.iter_mut().zip([YOU, BOT]) {
+                if player.id == scenario.current_turn {
+                    turn = id.to_string();
+                }
+                player.id = id.to_string();
+            }
+            scenario.current_turn = turn;
+            scenario.to_game("local", rng)?
+        },
+        None => {
+            let ids = vec![YOU.to_string(), BOT.to_string()];
+            game_logic::init_game_with("local".to_string(), ids, Default::default(), rng)
+        },
+    };
+    game.players.get_mut(YOU).unwrap().nickname = "You".to_string();
+    let opponent = game.players.get_mut(BOT).unwrap();
+    opponent.nickname = "Bot".to_string();
+    opponent.avatar = "🤖".to_string();
+    opponent.is_bot = true;
+    opponent.bot_difficulty = difficulty;
+    bot::play_pending_mulligans(&mut game);
+    Ok(game)
+}
+
+pub fn run(difficulty: BotDifficulty, seed: Option<u64>, scenario: Option<Scenario>) -> Result<(), String> {
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    };
+    let you = YOU.to_string();
+    let mut game = new_game(difficulty, scenario.as_ref(), &mut rng)?;
+    if let Some(scenario) = &scenario {
+        println!("{}\n{}\n", scenario.name, scenario.description);
+    }
+
+    println!("{}\n", HELP);
+    println!("{}", render(&public_state(&game, Some(&you)), &you));
+
+    let stdin = io::stdin();
+    loop {
+        bot_turns(&mut game, &mut rng);
+
+        print!("> ");
+        io::stdout().flush().map_err(|e| e.to_string())?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let result = match command::parse(&line, Some(&game), &you) {
+            Ok(Command::Quit) => return Ok(()),
+            Ok(Command::Help) => {
+                println!("{}", HELP);
+                continue;
+            },
+            Ok(Command::Board) => Ok(()),
+            Ok(Command::Restart) if game.phase == GamePhase::GameEnd => {
+                new_game(difficulty, scenario.as_ref(), &mut rng).map(|fresh| game = fresh)
+            },
+            Ok(Command::Restart) => Err("The match is still running".to_string()),
+            Ok(Command::Chat(_)) => Err("Chat is only available in remote games".to_string()),
+            Ok(Command::Mulligan(ids)) => game_logic::submit_mulligan(&mut game, &you, ids),
+            Ok(Command::Action(action)) => game_logic::apply_action(&mut game, &you, &action),
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(()) => {
+                bot_turns(&mut game, &mut rng);
+                println!("{}", render(&public_state(&game, Some(&you)), &you));
+            },
+            Err(e) => println!("! {}", e),
+        }
+    }
+}
+
+/// Lets the bot move until it is the player's turn again.
+fn bot_turns(game: &mut GameState, rng: &mut StdRng) {
+    let bot_id = BOT.to_string();
+    while game.phase == GamePhase::Playing && game.current_turn == bot_id {
+        let action = bot::choose_action(game, &bot_id, rng);
+        println!("Bot: {}", describe(game, &bot_id, &action));
+        if game_logic::apply_action(game, &bot_id, &action).is_err() {
+            let _ = game_logic::pass_turn(game, &bot_id);
+        }
+    }
+}
+
+fn describe(game: &GameState, player_id: &String, action: &PlayerAction) -> String {
+    let player = &game.players[player_id];
+    match action {
+        PlayerAction::Pass => "pass".to_string(),
+        PlayerAction::Resign => "resign".to_string(),
+        PlayerAction::Play { card_id, target_id } => {
+            let code = |id: &String| {
+                player.hand.iter().chain(&player.board).chain(&player.discard_pile)
+                    .find(|c| &c.id == id)
+                    .map(card_code)
+                    .unwrap_or_else(|| "?".to_string())
+            };
+            match target_id {
+                Some(target) => format!("play {} --target {}", code(card_id), code(target)),
+                None => format!("play {}", code(card_id)),
+            }
+        },
+    }
+}
@@ -0,0 +1,89 @@
+mod command;
+mod local;
+mod remote;
+mod render;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use server::game_types::BotDifficulty;
+use server::scenario::Scenario;
+use std::path::PathBuf;
+
+/// Plays Poker Tactics in the terminal, against a local bot or in a room on
+/// a running server.
+#[derive(Debug, Parser)]
+#[command(name = "poker-tactics-cli")]
+struct Args {
+    #[command(subcommand)]
+    mode: Mode,
+}
+
+#[derive(Debug, Subcommand)]
+enum Mode {
+    /// Play against a bot in this process
+    Local {
+        #[arg(long, value_enum, default_value_t = Bot::Normal)]
+        difficulty: Bot,
+
+        /// Seed for the deal and the bot, for replaying a game
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Start from the position in this TOML or JSON scenario file
+        #[arg(long)]
+        scenario: Option<PathBuf>,
+    },
+    /// Join a room on a server
+    Remote {
+        #[arg(long, default_value = "http://localhost:3000")]
+        url: String,
+
+        #[arg(long)]
+        room: String,
+
+        /// Reuse a player id to reconnect to a seat
+        #[arg(long)]
+        player_id: Option<String>,
+
+        #[arg(long, default_value = "Terminal")]
+        nickname: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Bot {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl From<Bot> for BotDifficulty {
+    fn from(bot: Bot) -> Self {
+        match bot {
+            Bot::Easy => BotDifficulty::Easy,
+            Bot::Normal => BotDifficulty::Normal,
+            Bot::Hard => BotDifficulty::Hard,
+        }
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let result = match args.mode {
+        Mode::Local { difficulty, seed, scenario } => scenario.as_deref()
+            .map(Scenario::load)
+            .transpose()
+            .and_then(|scenario| local::run(difficulty.into(), seed, scenario)),
+        Mode::Remote { url, room, player_id, nickname } => {
+            let player_id = player_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+            tokio::runtime::Runtime::new()
+                .map_err(|e| e.to_string())
+                .and_then(|rt| rt.block_on(remote::run(url, room, player_id, nickname)))
+        },
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
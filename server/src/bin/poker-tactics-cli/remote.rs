@@ -0,0 +1,182 @@
+use crate::command::{self, Command, HELP};
+use crate::render::render;
+use futures_util::FutureExt;
+use rust_socketio::asynchronous::ClientBuilder;
+use rust_socketio::Payload;
+use serde_json::{json, Value};
+use server::delta::{self, StateDelta};
+use server::api::PublicGameState;
+use server::game_types::PlayerAction;
+use server::protocol::{CAPABILITIES, PROTOCOL_VERSION};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// First JSON argument of a Socket.IO event, if any.
+fn first_value(payload: Payload) -> Option<Value> {
+    match payload {
+        Payload::Text(values) => values.into_iter().next(),
+        _ => None,
+    }
+}
+
+fn print_chat(message: &Value) {
+    let nickname = message["nickname"].as_str().unwrap_or("?");
+    let text = message["text"].as_str().unwrap_or("");
+    println!("[{}] {}", nickname, text);
+}
+
+pub async fn run(url: String, room: String, player_id: String, nickname: String) -> Result<(), String> {
+    let latest: Arc<Mutex<Option<PublicGameState>>> = Arc::new(Mutex::new(None));
+
+    let latest_x = latest.clone();
+    let latest_delta = latest.clone();
+    let me = player_id.clone();
+    let me_delta = player_id.clone();
+    let join = json!({ "room_id": room, "player_id": player_id, "nickname": nickname, "avatar": "⌨️" });
+    let client = ClientBuilder::new(url.as_str())
+        .namespace("/")
+        // The server greets every new socket, including after a reconnect,
+        // so (re)joining the room from here keeps the seat attached.
+        .on("connected", move |_, client| {
+            let join = join.clone();
+            async move {
+                let handshake = json!({ "protocol_version": PROTOCOL_VERSION, "capabilities": CAPABILITIES, "client": "cli" });
+                if let Err(e) = client.emit("handshake", handshake).await {
+                    println!("! Cannot handshake: {}", e);
+                }
+                if let Err(e) = client.emit("join_game", join).await {
+                    println!("! Cannot join: {}", e);
+                }
+            }
+            .boxed()
+        })
+        .on("game_state_update", move |payload, _| {
+            let latest = latest_x.clone();
+            let me = me.clone();
+            async move {
+                let game = first_value(payload).and_then(|v| serde_json::from_value::<PublicGameState>(v).ok());
+                if let Some(game) = game {
+                    println!("\n{}", render(&game, &me));
+                    *latest.lock().unwrap() = Some(game);
+                }
+            }
+            .boxed()
+        })
+        .on("game_state_delta", move |payload, client| {
+            let latest = latest_delta.clone();
+            let me = me_delta.clone();
+            async move {
+                let update = match first_value(payload).and_then(|v| serde_json::from_value::<StateDelta>(v).ok()) {
+                    Some(update) => update,
+                    None => return,
+                };
+                let patched = delta::apply_delta(latest.lock().unwrap().as_ref(), &update);
+                match patched {
+                    Ok(Some(game)) => {
+                        println!("\n{}", render(&game, &me));
+                        *latest.lock().unwrap() = Some(game);
+                    },
+                    Ok(None) => {},
+                    Err(_) => {
+                        let _ = client.emit("resync", json!({ "room_id": update.room_id })).await;
+                    },
+                }
+            }
+            .boxed()
+        })
+        .on("chat_message", |payload, _| {
+            async move {
+                if let Some(message) = first_value(payload) {
+                    print_chat(&message);
+                }
+            }
+            .boxed()
+        })
+        .on("chat_history", |payload, _| {
+            async move {
+                if let Some(Value::Array(messages)) = first_value(payload) {
+                    messages.iter().for_each(print_chat);
+                }
+            }
+            .boxed()
+        })
+        .on("error", |payload, _| {
+            async move {
+                match first_value(payload) {
+                    Some(Value::String(message)) => println!("! {}", message),
+                    Some(other) => println!("! {}", other),
+                    None => println!("! Server error"),
+                }
+            }
+            .boxed()
+        })
+        .connect()
+        .await
+        .map_err(|e| format!("Cannot connect to {}: {}", url, e))?;
+
+    println!("Joining room {} as {} (player id {})", room, nickname, player_id);
+    println!("{}\n", HELP);
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Some(line) = lines.next_line().await.map_err(|e| e.to_string())? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let parsed = {
+            let game = latest.lock().unwrap();
+            command::parse(&line, game.as_ref().map(|g| g.known_game()).as_ref(), &player_id).map(|cmd| (cmd, game.clone()))
+        };
+        let (event, data) = match parsed {
+            Ok((Command::Quit, _)) => break,
+            Ok((Command::Help, _)) => {
+                println!("{}", HELP);
+                continue;
+            },
+            Ok((Command::Board, game)) => {
+                match game {
+                    Some(game) => println!("{}", render(&game, &player_id)),
+                    None => println!("! No game yet"),
+                }
+                continue;
+            },
+            Ok((Command::Chat(text), _)) => (
+                "chat_message",
+                json!({ "room_id": room, "player_id": player_id, "text": text }),
+            ),
+            Ok((Command::Restart, _)) => ("restart_game", json!({ "room_id": room, "player_id": player_id })),
+            Ok((Command::Mulligan(ids), _)) => (
+                "mulligan",
+                json!({ "room_id": room, "player_id": player_id, "card_ids": ids }),
+            ),
+            Ok((Command::Action(PlayerAction::Pass), _)) => (
+                "pass",
+                json!({ "room_id": room, "player_id": player_id }),
+            ),
+            Ok((Command::Action(PlayerAction::Resign), _)) => (
+                "resign",
+                json!({ "room_id": room, "player_id": player_id }),
+            ),
+            Ok((Command::Action(PlayerAction::Play { card_id, target_id }), _)) => (
+                "play_card",
+                json!({ "room_id": room, "player_id": player_id, "card_id": card_id, "target_id": target_id }),
+            ),
+            Err(e) => {
+                println!("! {}", e);
+                continue;
+            },
+        };
+
+        // Guard game actions so a retried emit is not applied twice and a
+        // command typed against an old board is refused.
+        let mut data = data;
+        if event != "chat_message" {
+            data["action_id"] = json!(uuid::Uuid::new_v4().to_string());
+            data["expected_version"] = json!(latest.lock().unwrap().as_ref().map(|g| g.version));
+        }
+        client.emit(event, data).await.map_err(|e| e.to_string())?;
+    }
+
+    let _ = client.disconnect().await;
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use server::api::{PublicGameState, PublicPlayer};
+use server::game_types::{Card, GamePhase};
+use server::notation::card_code;
+
+/// Text view of `game` as seen by `viewer`: the opponent's hand is only
+/// shown as a count.
+pub fn render(game: &PublicGameState, viewer: &String) -> String {
+    let mut out = String::new();
+    out.push_str(&format!(
+        "== Room {} · Round {} · {:?} ==\n",
+        game.room_id, game.round_count, game.phase
+    ));
+
+    let me = game.players.get(viewer);
+    let opponent = game.players.values().find(|p| &p.id != viewer);
+
+    match opponent {
+        Some(opp) => {
+            out.push_str(&seat_line("Opponent", opp, game));
+            out.push_str(&format!("  hand:    {} cards\n", opp.hand_size));
+            out.push_str(&format!("  board:   {}\n", cards(&opp.board, true)));
+            out.push_str(&format!("  discard: {}\n", cards(&opp.discard_pile, false)));
+        },
+        None => out.push_str("Opponent  (waiting for a player)\n"),
+    }
+    out.push_str(&"-".repeat(48));
+    out.push('\n');
+    if let Some(me) = me {
+        out.push_str(&seat_line("You", me, game));
+        out.push_str(&format!("  board:   {}\n", cards(&me.board, true)));
+        out.push_str(&format!("  discard: {}\n", cards(&me.discard_pile, false)));
+        out.push_str(&format!("  hand:    {}\n", cards(me.hand.as_deref().unwrap_or_default(), false)));
+    }
+
+    out.push_str(&format!("Deck: {} cards · {}\n", game.deck_size, status(game, viewer)));
+    out
+}
+
+fn seat_line(label: &str, player: &PublicPlayer, game: &PublicGameState) -> String {
+    let bot = if player.is_bot { format!(" [{:?} bot]", player.bot_difficulty) } else { String::new() };
+    // `passed` doubles as "mulligan done" before play starts.
+    let passed = if player.passed && game.phase == GamePhase::Playing { "  PASSED" } else { "" };
+    format!(
+        "{:<9} {} {}{}  score {}  rounds {}{}\n",
+        label, player.avatar, player.nickname, bot, player.current_score, player.rounds_won, passed
+    )
+}
+
+/// Space-separated card codes; board cards also show their power when it
+/// differs from the printed value (e.g. a bonded 2).
+fn cards(list: &[Card], show_power: bool) -> String {
+    if list.is_empty() {
+        return "-".to_string();
+    }
+    list.iter()
+        .map(|c| {
+            if show_power && c.current_power != c.base_power {
+                format!("{}({})", card_code(c), c.current_power)
+            } else {
+                card_code(c)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn status(game: &PublicGameState, viewer: &String) -> String {
+    match game.phase {
+        GamePhase::Waiting => "Waiting for opponent".to_string(),
+        GamePhase::Mulligan => match game.players.get(viewer) {
+            Some(p) if p.passed => "Waiting for opponent's mulligan".to_string(),
+            _ => "Mulligan: `mulligan <cards>` or `keep`".to_string(),
+        },
+        GamePhase::Playing | GamePhase::RoundEnd => {
+            if &game.current_turn == viewer {
+                "Your turn".to_string()
+            } else {
+                "Opponent's turn".to_string()
+            }
+        },
+        GamePhase::GameEnd => match game.winner.as_deref() {
+            Some(w) if w == viewer => "You won! (`restart` or `quit`)".to_string(),
+            Some("Draw") => "Draw! (`restart` or `quit`)".to_string(),
+            _ => "You lost. (`restart` or `quit`)".to_string(),
+        },
+    }
+}
@@ -0,0 +1,6 @@
+/// Prints the JSON Schema of the client/server protocol. The checked-in
+/// copy is protocol.schema.json at the repository root:
+/// `cargo run --bin schema > ../protocol.schema.json`.
+fn main() {
+    println!("{}", serde_json::to_string_pretty(&server::protocol::schema()).expect("schema serializes"));
+}
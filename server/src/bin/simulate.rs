@@ -0,0 +1,127 @@
+use clap::{Parser, ValueEnum};
+use server::game_types::{BotDifficulty, RuleSet};
+use server::simulation::{self, SimulationConfig};
+use std::path::PathBuf;
+
+/// Runs bot-vs-bot matches through game_logic and reports balance statistics.
+#[derive(Debug, Parser)]
+#[command(name = "simulate")]
+struct Args {
+    /// Number of matches to play
+    #[arg(short = 'n', long, default_value_t = 1000)]
+    matches: u32,
+
+    /// Seed of the first match; match i uses seed + i
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Bot playing seat A
+    #[arg(long, value_enum, default_value_t = Bot::Easy)]
+    bot_a: Bot,
+
+    /// Bot playing seat B
+    #[arg(long, value_enum, default_value_t = Bot::Easy)]
+    bot_b: Bot,
+
+    /// JSON file with rule-set overrides (missing fields keep their defaults)
+    #[arg(long)]
+    rules: Option<PathBuf>,
+
+    /// Single rule override, e.g. `--set king_power=12`; may be repeated
+    #[arg(long = "set", value_name = "RULE=VALUE")]
+    overrides: Vec<String>,
+
+    #[arg(long, value_enum, default_value_t = Format::Json)]
+    format: Format,
+
+    /// Write the report here instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Bot {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl From<Bot> for BotDifficulty {
+    fn from(bot: Bot) -> Self {
+        match bot {
+            Bot::Easy => BotDifficulty::Easy,
+            Bot::Normal => BotDifficulty::Normal,
+            Bot::Hard => BotDifficulty::Hard,
+        }
+    }
+}
+
+/// CSV is the match summary, a blank line, then the per-card table.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Csv,
+}
+
+fn load_rules(args: &Args) -> Result<RuleSet, String> {
+    let mut rules = match &args.rules {
+        Some(path) => {
+            let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            serde_json::to_value(serde_json::from_str::<RuleSet>(&text).map_err(|e| format!("{}: {}", path.display(), e))?)
+        },
+        None => serde_json::to_value(RuleSet::default()),
+    }
+    .map_err(|e| e.to_string())?;
+
+    for entry in &args.overrides {
+        let (key, value) = entry.split_once('=').ok_or(format!("Expected RULE=VALUE, got `{}`", entry))?;
+        let slot = rules.get_mut(key).ok_or(format!("Unknown rule `{}`", key))?;
+        *slot = serde_json::from_str(value).map_err(|_| format!("Invalid value for `{}`: {}", key, value))?;
+    }
+
+    let rules: RuleSet = serde_json::from_value(rules).map_err(|e| e.to_string())?;
+    rules.validate()?;
+    Ok(rules)
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let rules = match load_rules(&args) {
+        Ok(rules) => rules,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(2);
+        }
+    };
+
+    let config = SimulationConfig {
+        matches: args.matches,
+        seed: args.seed,
+        rules,
+        bots: [args.bot_a.into(), args.bot_b.into()],
+    };
+
+    eprintln!("Simulating {} matches ({:?} vs {:?}, seed {})", config.matches, config.bots[0], config.bots[1], config.seed);
+    let report = simulation::run(&config);
+
+    let text = match args.format {
+        Format::Json => serde_json::to_string_pretty(&report).unwrap(),
+        Format::Csv => report.to_csv(),
+    };
+    eprintln!(
+        "Seat A {} / seat B {} / draws {}; first player win rate {:.3}; average {:.2} rounds, {:.1} actions",
+        report.seat_wins[0], report.seat_wins[1], report.draws,
+        report.first_player_win_rate, report.average_rounds, report.average_actions
+    );
+
+    match &args.output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, text) {
+                eprintln!("error: {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None => println!("{}", text),
+    }
+}
@@ -0,0 +1,328 @@
+use crate::endgame;
+use crate::game_logic;
+use crate::game_types::{BotDifficulty, Card, CardAbility, GamePhase, GameState, Player, PlayerAction, Rank};
+use crate::ismcts::{self, SearchBudget};
+use rand::Rng;
+use std::time::Duration;
+
+// Node budget for solving perfect-information endgames outright.
+const ENDGAME_NODE_LIMIT: u64 = 200_000;
+
+// Rough worth of one card in hand, in board points. Cards are the only
+// resource carried between rounds, so spending one has to buy at least this.
+const CARD_VALUE: f64 = 8.0;
+const ROUND_VALUE: f64 = 60.0;
+const MATCH_VALUE: f64 = 1000.0;
+
+pub fn bot_player_id() -> String {
+    format!("bot-{}", uuid::Uuid::new_v4())
+}
+
+/// Iteration and per-move time limits for the search-based difficulties.
+pub fn search_budget(difficulty: BotDifficulty) -> Option<SearchBudget> {
+    match difficulty {
+        BotDifficulty::Easy => None,
+        BotDifficulty::Normal => Some(SearchBudget { iterations: 800, time_limit: Duration::from_millis(500) }),
+        BotDifficulty::Hard => Some(SearchBudget { iterations: 10_000, time_limit: Duration::from_millis(2000) }),
+    }
+}
+
+/// Submits a mulligan for every bot seat that has not chosen one yet.
+pub fn play_pending_mulligans(game: &mut GameState) {
+    if game.phase == GamePhase::Mulligan {
+        let mut pending: Vec<String> = game.players.values()
+            .filter(|p| p.is_bot && !p.passed)
+            .map(|p| p.id.clone())
+            .collect();
+        // Replacements come off the top of the deck, so keep the order stable.
+        pending.sort();
+        for bot_id in pending {
+            let card_ids = choose_mulligan(game, &bot_id);
+            if game_logic::submit_mulligan(game, &bot_id, card_ids).is_err() {
+                // Keeping the hand can never fail.
+                let _ = game_logic::submit_mulligan(game, &bot_id, Vec::new());
+            }
+        }
+    }
+}
+
+/// Swaps up to two cards that are worth less than an average draw.
+pub fn choose_mulligan(game: &GameState, player_id: &String) -> Vec<String> {
+    let player = match game.players.get(player_id) {
+        Some(p) => p,
+        None => return Vec::new(),
+    };
+    let twos = player.hand.iter().filter(|c| c.ability == CardAbility::IronGuard).count();
+
+    let mut ranked: Vec<(f64, &Card)> = player.hand.iter()
+        .map(|c| (keep_value(c, twos), c))
+        .collect();
+    ranked.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    ranked.iter()
+        .take(2)
+        .filter(|(value, _)| *value < 5.0)
+        .map(|(_, c)| c.id.clone())
+        .collect()
+}
+
+fn keep_value(card: &Card, twos_in_hand: usize) -> f64 {
+    match card.ability {
+        CardAbility::IronGuard if twos_in_hand >= 2 => 6.0,
+        CardAbility::IronGuard => 2.0,
+        CardAbility::Intel => 12.0,
+        CardAbility::Medic => 9.0,
+        CardAbility::Hero => 15.0,
+        CardAbility::Burn => 7.0,
+        CardAbility::Decoy => 6.0,
+        CardAbility::None => card.base_power as f64,
+    }
+}
+
+/// Picks the bot's next action at the seat's configured difficulty.
+pub fn choose_action<R: Rng>(game: &GameState, player_id: &String, rng: &mut R) -> PlayerAction {
+    let difficulty = game.players.get(player_id).map(|p| p.bot_difficulty).unwrap_or_default();
+    choose_action_with_budget(game, player_id, search_budget(difficulty), rng)
+}
+
+/// Picks the bot's next action with an explicit search budget; `None` plays
+/// the heuristic policy.
+pub fn choose_action_with_budget<R: Rng>(game: &GameState, player_id: &String, budget: Option<SearchBudget>, rng: &mut R) -> PlayerAction {
+    // With the opponent out of cards nothing is hidden any more.
+    let opponent_empty = game.players.values().any(|p| &p.id != player_id && p.hand.is_empty());
+    if budget.is_some() && opponent_empty {
+        if let Some(action) = endgame::solve(game, player_id, ENDGAME_NODE_LIMIT).and_then(|s| s.best_action) {
+            return action;
+        }
+    }
+
+    match budget {
+        Some(budget) => ismcts::search(game, player_id, budget, rng),
+        None => heuristic_action(game, player_id),
+    }
+}
+
+/// Picks the legal action whose resulting position evaluates best for
+/// `player_id`. Every candidate is tried on a copy through game_logic, so
+/// the bot obeys exactly the same rules as a human.
+pub fn heuristic_action(game: &GameState, player_id: &String) -> PlayerAction {
+    let mut best = PlayerAction::Pass;
+    let mut best_value = f64::NEG_INFINITY;
+
+    for action in game_logic::legal_actions(game, player_id) {
+        let mut next = game.clone();
+        if game_logic::apply_action(&mut next, player_id, &action).is_err() {
+            continue;
+        }
+        let value = evaluate(&next, player_id);
+        if value > best_value {
+            best_value = value;
+            best = action;
+        }
+    }
+
+    best
+}
+
+/// Static evaluation of `game` from `player_id`'s point of view. Only uses
+/// information that player can see: their own hand, both boards, both
+/// discard piles and the opponent's hand size.
+pub fn evaluate(game: &GameState, player_id: &String) -> f64 {
+    let (me, opp) = match seats(game, player_id) {
+        Some(seats) => seats,
+        None => return 0.0,
+    };
+
+    if game.phase == GamePhase::GameEnd {
+        return match &game.winner {
+            Some(w) if w == player_id => MATCH_VALUE,
+            Some(w) if w == "Draw" => 0.0,
+            _ => -MATCH_VALUE,
+        };
+    }
+
+    let mut value = 0.0;
+
+    value += (me.rounds_won as f64 - opp.rounds_won as f64) * ROUND_VALUE * 1.5;
+    value += (me.hand.len() as f64 - opp.hand.len() as f64) * CARD_VALUE;
+
+    // Losing a round hurts more when the opponent is one round from winning.
+    let stakes = if opp.rounds_won >= 1 { 2.0 } else { 1.0 };
+    value += round_outlook(me, opp) * stakes;
+
+    value += iron_guard_potential(me);
+
+    // Spies turn into two cards later, as long as there is something to draw.
+    if !game.deck.is_empty() {
+        let spies = me.hand.iter().filter(|c| c.ability == CardAbility::Intel).count();
+        value += spies as f64 * 4.0;
+    }
+
+    value -= scorch_risk(game, me, opp);
+
+    value
+}
+
+fn seats<'a>(game: &'a GameState, player_id: &String) -> Option<(&'a Player, &'a Player)> {
+    let me = game.players.get(player_id)?;
+    let opp = game.players.values().find(|p| &p.id != player_id)?;
+    Some((me, opp))
+}
+
+/// Expected value of the current round, net of the cards either side will
+/// still have to spend to decide it.
+fn round_outlook(me: &Player, opp: &Player) -> f64 {
+    let lead = me.current_score as i64 - opp.current_score as i64;
+
+    match (me.passed, opp.passed) {
+        (false, true) => {
+            if lead > 0 {
+                ROUND_VALUE
+            } else {
+                match cards_needed(me, -lead) {
+                    Some(n) if (n as f64) * CARD_VALUE < ROUND_VALUE * 1.5 => ROUND_VALUE - n as f64 * CARD_VALUE,
+                    _ => -ROUND_VALUE * 0.5,
+                }
+            }
+        },
+        (true, false) => {
+            if lead <= 0 {
+                -ROUND_VALUE
+            } else {
+                match cards_needed(opp, lead) {
+                    Some(n) => -ROUND_VALUE + n as f64 * CARD_VALUE,
+                    None => ROUND_VALUE,
+                }
+            }
+        },
+        _ => (lead as f64 * 0.8).clamp(-ROUND_VALUE, ROUND_VALUE),
+    }
+}
+
+/// How many cards `player` would need to play to get strictly ahead by
+/// more than `deficit`, assuming they open with their strongest units.
+fn cards_needed(player: &Player, deficit: i64) -> Option<usize> {
+    let mut powers: Vec<i64> = player.hand.iter()
+        .map(|c| match c.ability {
+            CardAbility::Intel | CardAbility::Burn | CardAbility::Decoy => 0,
+            _ => c.current_power as i64,
+        })
+        .filter(|p| *p > 0)
+        .collect();
+    if powers.is_empty() {
+        return None;
+    }
+    powers.sort_unstable_by(|a, b| b.cmp(a));
+
+    let mut total = 0;
+    for (i, p) in powers.iter().enumerate() {
+        total += p;
+        if total > deficit {
+            return Some(i + 1);
+        }
+    }
+    None
+}
+
+fn iron_guard_potential(me: &Player) -> f64 {
+    let on_board = me.board.iter().filter(|c| c.ability == CardAbility::IronGuard).count();
+    let in_hand = me.hand.iter().filter(|c| c.ability == CardAbility::IronGuard).count();
+
+    if on_board == 1 && in_hand >= 1 {
+        // One more 2 is worth 6 + 4 from the bond.
+        6.0
+    } else if on_board == 0 && in_hand >= 2 {
+        3.0
+    } else {
+        0.0
+    }
+}
+
+/// Penalty for exposing our strongest non-Hero units to an Ace we have not
+/// seen yet, scaled by how many are still unaccounted for.
+fn scorch_risk(game: &GameState, me: &Player, opp: &Player) -> f64 {
+    if opp.passed || opp.hand.is_empty() {
+        return 0.0;
+    }
+
+    let seen_aces = me.hand.iter()
+        .chain(game.players.values().flat_map(|p| p.board.iter().chain(p.discard_pile.iter())))
+        .filter(|c| c.rank == Rank::Ace)
+        .count();
+    let unseen_aces = 4usize.saturating_sub(seen_aces);
+    if unseen_aces == 0 {
+        return 0.0;
+    }
+
+    let strongest = game.players.values()
+        .flat_map(|p| p.board.iter())
+        .filter(|c| c.ability != CardAbility::Hero)
+        .map(|c| c.current_power)
+        .max()
+        .unwrap_or(0);
+    if strongest == 0 {
+        return 0.0;
+    }
+
+    let mine: u32 = me.board.iter()
+        .filter(|c| c.ability != CardAbility::Hero && c.current_power == strongest)
+        .map(|c| c.current_power as u32)
+        .sum();
+    let theirs: u32 = opp.board.iter()
+        .filter(|c| c.ability != CardAbility::Hero && c.current_power == strongest)
+        .map(|c| c.current_power as u32)
+        .sum();
+
+    // Chance the opponent holds one of the unseen Aces, very roughly.
+    let unseen_cards = game.deck.len() + opp.hand.len();
+    let chance = (unseen_aces * opp.hand.len()) as f64 / unseen_cards.max(1) as f64;
+    chance.min(1.0) * (mine as f64 - theirs as f64).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play_pending_turns(game: &mut GameState) {
+        play_pending_mulligans(game);
+
+        while game.phase == GamePhase::Playing {
+            let bot_id = game.current_turn.clone();
+            let action = heuristic_action(game, &bot_id);
+            game_logic::apply_action(game, &bot_id, &action).expect("bot picked an illegal action");
+        }
+    }
+
+    fn bot_vs_bot() -> GameState {
+        let ids = vec!["bot-a".to_string(), "bot-b".to_string()];
+        let mut game = game_logic::init_game("room_test".to_string(), ids);
+        for player in game.players.values_mut() {
+            player.is_bot = true;
+        }
+        game
+    }
+
+    #[test]
+    fn test_bots_finish_a_match() {
+        for _ in 0..20 {
+            let mut game = bot_vs_bot();
+            play_pending_turns(&mut game);
+
+            assert_eq!(game.phase, GamePhase::GameEnd);
+            assert!(game.winner.is_some());
+        }
+    }
+
+    #[test]
+    fn test_mulligan_keeps_strong_cards() {
+        let game = bot_vs_bot();
+        let hand = &game.players["bot-a"].hand;
+
+        let swapped = choose_mulligan(&game, &"bot-a".to_string());
+        assert!(swapped.len() <= 2);
+        for id in &swapped {
+            let card = hand.iter().find(|c| &c.id == id).unwrap();
+            assert!(matches!(card.ability, CardAbility::None | CardAbility::IronGuard));
+        }
+    }
+}
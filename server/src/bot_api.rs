@@ -0,0 +1,508 @@
+use crate::api::public_state;
+use crate::errors::{Ack, ApiError, ErrorCode, Rejection};
+use crate::game_types::{GameState, PlayerAction, RoomOptions};
+use crate::profiles::Profiles;
+use crate::protocol::ActionGuard;
+use crate::room::RoomHandle;
+use crate::session::Services;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+pub use poker_tactics_common::view::BotGameView;
+
+/// Longest a `GET /api/bot/games/:id?after_version=` waits for a change.
+const MAX_WAIT: Duration = Duration::from_secs(30);
+
+/// Start of every bot account's seat id. Sockets may not use it, so only
+/// the token-authenticated routes below can act in those seats.
+pub const BOT_PREFIX: &str = "bot:";
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+/// A registered program playing through `/api/bot`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BotAccount {
+    pub name: String,
+    pub player_id: String, // Seat id in rooms: `bot:<name>`
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChallengeStatus {
+    Pending,
+    Accepted,
+    Declined,
+}
+
+/// An invitation to a match. A challenge without an `opponent` is open:
+/// its room exists from the start with the challenger seated, so a human
+/// can join it by id from the web client, or any bot can accept it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Challenge {
+    pub id: String,
+    pub challenger: String, // Account names
+    pub opponent: Option<String>,
+    pub options: RoomOptions,
+    pub status: ChallengeStatus,
+    pub room_id: String,
+    pub created_at: u64,
+}
+
+/// An account as saved to disk: the token only as its hash.
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedAccount {
+    #[serde(flatten)]
+    account: BotAccount,
+    token_sha256: String,
+}
+
+/// Bot accounts, their API tokens and challenges. Accounts are saved to
+/// `path` when one is set, so a name and its ratings and profile stay
+/// with the program holding the token across restarts; challenges are
+/// kept in memory only.
+#[derive(Debug, Default)]
+pub struct BotAccounts {
+    accounts: HashMap<String, BotAccount>, // By name
+    tokens: HashMap<String, String>, // SHA-256 of the token to account name
+    challenges: HashMap<String, Challenge>,
+    path: Option<PathBuf>,
+}
+
+/// Where bot accounts are saved when profiles go to `profiles_file`.
+pub fn accounts_file(profiles_file: &std::path::Path) -> PathBuf {
+    profiles_file.with_extension("bots.json")
+}
+
+fn token_hash(token: &str) -> String {
+    Sha256::digest(token.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn valid_name(name: &str) -> bool {
+    (3..=24).contains(&name.len()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+pub(crate) fn new_token() -> String {
+    let bytes: [u8; 24] = rand::rng().random();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl BotAccounts {
+    /// Reads the accounts saved at `path`; a missing file starts empty.
+    pub fn load(path: Option<PathBuf>) -> Result<Self, String> {
+        let mut accounts = BotAccounts { path, ..BotAccounts::default() };
+        if let Some(p) = accounts.path.as_ref().filter(|p| p.exists()) {
+            let text = std::fs::read_to_string(p).map_err(|e| format!("{}: {}", p.display(), e))?;
+            let saved: Vec<SavedAccount> = serde_json::from_str(&text).map_err(|e| format!("{}: {}", p.display(), e))?;
+            for SavedAccount { account, token_sha256 } in saved {
+                accounts.tokens.insert(token_sha256, account.name.clone());
+                accounts.accounts.insert(account.name.clone(), account);
+            }
+        }
+        Ok(accounts)
+    }
+
+    /// The file and its contents with every account, if they are saved.
+    pub fn saved(&self) -> Option<(PathBuf, String)> {
+        let path = self.path.clone()?;
+        let mut saved: Vec<SavedAccount> = self.tokens.iter()
+            .filter_map(|(hash, name)| Some(SavedAccount { account: self.accounts.get(name)?.clone(), token_sha256: hash.clone() }))
+            .collect();
+        saved.sort_by(|a, b| a.account.name.cmp(&b.account.name));
+        Some((path, serde_json::to_string_pretty(&saved).expect("accounts serialize")))
+    }
+
+    /// Creates an account and returns it with its API token, which is not
+    /// shown again.
+    pub fn register(&mut self, name: &str) -> Result<(BotAccount, String), Rejection> {
+        if !valid_name(name) {
+            return Err(Rejection::new(ErrorCode::InvalidMessage, "Names are 3 to 24 letters, digits, `-` or `_`"));
+        }
+        if self.accounts.contains_key(name) {
+            return Err(Rejection::new(ErrorCode::InvalidMessage, format!("The name `{}` is taken", name)));
+        }
+
+        let account = BotAccount { name: name.to_string(), player_id: format!("{}{}", BOT_PREFIX, name), created_at: now() };
+        let token = new_token();
+        self.accounts.insert(name.to_string(), account.clone());
+        self.tokens.insert(token_hash(&token), name.to_string());
+        Ok((account, token))
+    }
+
+    pub fn authenticate(&self, token: &str) -> Option<&BotAccount> {
+        self.tokens.get(&token_hash(token)).and_then(|name| self.accounts.get(name))
+    }
+
+    pub fn challenge(&mut self, challenger: &str, opponent: Option<String>, options: RoomOptions) -> Result<Challenge, Rejection> {
+        if let Some(opponent) = &opponent {
+            if !self.accounts.contains_key(opponent) {
+                return Err(Rejection::new(ErrorCode::InvalidMessage, format!("No bot named `{}`", opponent)));
+            }
+            if opponent == challenger {
+                return Err(Rejection::new(ErrorCode::InvalidMessage, "A bot cannot challenge itself"));
+            }
+        }
+
+        let id = Uuid::new_v4().simple().to_string()[..12].to_string();
+        let challenge = Challenge {
+            room_id: format!("challenge-{}", id),
+            id: id.clone(),
+            challenger: challenger.to_string(),
+            opponent,
+            options,
+            status: ChallengeStatus::Pending,
+            created_at: now(),
+        };
+        self.challenges.insert(id, challenge.clone());
+        Ok(challenge)
+    }
+
+    /// Challenges `name` sent or received, and every pending open one,
+    /// oldest first.
+    pub fn challenges_for(&self, name: &str) -> Vec<Challenge> {
+        let mut challenges: Vec<Challenge> = self.challenges.values()
+            .filter(|c| {
+                c.challenger == name
+                    || c.opponent.as_deref() == Some(name)
+                    || (c.opponent.is_none() && c.status == ChallengeStatus::Pending)
+            })
+            .cloned()
+            .collect();
+        challenges.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+        challenges
+    }
+
+    /// Checks that `name` may accept or decline a pending challenge: one
+    /// addressed to it, or an open one anyone may accept. Only the
+    /// challenger may decline an open challenge, which withdraws it.
+    pub fn answerable(&self, id: &str, name: &str, accept: bool) -> Result<&Challenge, Rejection> {
+        let challenge = self.challenges.get(id)
+            .ok_or_else(|| Rejection::new(ErrorCode::ChallengeNotFound, "Challenge not found"))?;
+        let allowed = match (challenge.opponent.as_deref(), accept) {
+            (Some(opponent), _) if opponent == name => true,
+            (None, true) => true,
+            _ => challenge.challenger == name && !accept,
+        };
+        if !allowed {
+            return Err(Rejection::new(ErrorCode::ChallengeNotFound, "Challenge not found"));
+        }
+        if accept && challenge.challenger == name {
+            return Err(Rejection::new(ErrorCode::InvalidMessage, "A bot cannot accept its own challenge"));
+        }
+        if challenge.status != ChallengeStatus::Pending {
+            return Err(Rejection::new(ErrorCode::GameInProgress, "The challenge was already answered"));
+        }
+        Ok(challenge)
+    }
+
+    /// Accepts or declines a challenge, after the checks of `answerable`.
+    pub fn respond(&mut self, id: &str, name: &str, accept: bool) -> Result<Challenge, Rejection> {
+        self.answerable(id, name, accept)?;
+        let challenge = self.challenges.get_mut(id).expect("answerable found it");
+        if accept {
+            challenge.status = ChallengeStatus::Accepted;
+            challenge.opponent = Some(name.to_string());
+        } else {
+            challenge.status = ChallengeStatus::Declined;
+        }
+        Ok(challenge.clone())
+    }
+}
+
+/// The token in an `Authorization: Bearer <token>` header.
+pub(crate) fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers.get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+#[derive(Clone)]
+struct BotApiState {
+    services: Services,
+    accounts: Arc<Mutex<BotAccounts>>,
+    registration_key: Option<String>, // Required in `X-Registration-Key` when set
+}
+
+pub fn game_view(game: &GameState, player_id: &str) -> BotGameView {
+    BotGameView::new(public_state(game, Some(player_id)), player_id)
+}
+
+#[derive(Debug, Deserialize)]
+struct RegisterRequest {
+    name: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Registered {
+    account: BotAccount,
+    token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChallengeRequest {
+    opponent: Option<String>, // Bot name; omit for an open challenge
+    #[serde(default)]
+    options: RoomOptions,
+}
+
+#[derive(Debug, Deserialize)]
+struct GameQuery {
+    after_version: Option<u64>, // Wait until the room is past this version
+}
+
+#[derive(Debug, Deserialize)]
+struct MoveRequest {
+    action: PlayerAction,
+    #[serde(flatten)]
+    guard: ActionGuard,
+}
+
+#[derive(Debug, Deserialize)]
+struct MulliganRequest {
+    card_ids: Vec<String>,
+    #[serde(flatten)]
+    guard: ActionGuard,
+}
+
+/// The bot API under `/api/bot`, for programs that play with an account
+/// token instead of a socket. See protocol.md.
+pub fn router(services: Services, accounts: BotAccounts, registration_key: Option<String>) -> Router {
+    let state = BotApiState { services, accounts: Arc::new(Mutex::new(accounts)), registration_key };
+    Router::new()
+        .route("/api/bot/accounts", post(register))
+        .route("/api/bot/account", get(get_account))
+        .route("/api/bot/challenges", get(list_challenges).post(create_challenge))
+        .route("/api/bot/challenges/:id/accept", post(accept_challenge))
+        .route("/api/bot/challenges/:id/decline", post(decline_challenge))
+        .route("/api/bot/games/:room_id", get(get_game))
+        .route("/api/bot/games/:room_id/move", post(make_move))
+        .route("/api/bot/games/:room_id/mulligan", post(mulligan))
+        .with_state(state)
+}
+
+impl BotApiState {
+    /// The account named by the `Authorization: Bearer <token>` header.
+    fn account(&self, headers: &HeaderMap) -> Result<BotAccount, Rejection> {
+        bearer_token(headers).and_then(|token| self.accounts.lock().unwrap().authenticate(token).cloned())
+            .ok_or_else(|| Rejection::new(ErrorCode::Unauthorized, "Missing or unknown bot token"))
+    }
+
+    async fn seat(&self, room: &RoomHandle, account: &BotAccount) -> Result<(), Rejection> {
+        room.join(account.player_id.clone(), account.name.clone(), "🤖".to_string()).await.map(|_| ())
+    }
+
+    /// The room `account` is seated in.
+    fn game(&self, room_id: &str, account: &BotAccount) -> Result<RoomHandle, Rejection> {
+        let room = self.services.rooms.get(room_id)?;
+        if !room.snapshot().players.contains_key(&account.player_id) {
+            return Err(Rejection::new(ErrorCode::NotInRoom, "This bot is not seated in that room"));
+        }
+        Ok(room)
+    }
+}
+
+async fn register(State(state): State<BotApiState>, headers: HeaderMap, Json(request): Json<RegisterRequest>) -> Result<Response, ApiError> {
+    if let Some(key) = &state.registration_key {
+        if headers.get("x-registration-key").and_then(|v| v.to_str().ok()) != Some(key.as_str()) {
+            return Err(Rejection::new(ErrorCode::Unauthorized, "Registration needs the server's registration key").into());
+        }
+    }
+    let (account, token, saved) = {
+        let mut accounts = state.accounts.lock().unwrap();
+        let (account, token) = accounts.register(&request.name)?;
+        (account, token, accounts.saved())
+    };
+    info!("Registered bot account {}", account.name);
+    // Registrations are rare, so each one is written out at once.
+    if let Some((path, text)) = saved {
+        match tokio::task::spawn_blocking(move || Profiles::write_file(&path, text)).await {
+            Ok(Err(e)) => warn!("Could not save bot accounts: {}", e),
+            Err(e) => warn!("Bot account save failed: {}", e),
+            Ok(Ok(())) => {},
+        }
+    }
+    Ok((StatusCode::CREATED, Json(Registered { account, token })).into_response())
+}
+
+async fn get_account(State(state): State<BotApiState>, headers: HeaderMap) -> Result<Json<BotAccount>, ApiError> {
+    Ok(Json(state.account(&headers)?))
+}
+
+async fn list_challenges(State(state): State<BotApiState>, headers: HeaderMap) -> Result<Json<Vec<Challenge>>, ApiError> {
+    let account = state.account(&headers)?;
+    Ok(Json(state.accounts.lock().unwrap().challenges_for(&account.name)))
+}
+
+async fn create_challenge(State(state): State<BotApiState>, headers: HeaderMap, Json(request): Json<ChallengeRequest>) -> Result<Response, ApiError> {
+    let account = state.account(&headers)?;
+    let challenge = state.accounts.lock().unwrap().challenge(&account.name, request.opponent, request.options.requested())?;
+    if challenge.opponent.is_none() {
+        let room = state.services.rooms.get_or_create(&challenge.room_id, challenge.options.clone(), state.services.default_rules.clone());
+        state.seat(&room, &account).await?;
+    }
+    info!("Bot {} created challenge {}", account.name, challenge.id);
+    Ok((StatusCode::CREATED, Json(challenge)).into_response())
+}
+
+async fn accept_challenge(State(state): State<BotApiState>, headers: HeaderMap, Path(id): Path<String>) -> Result<Json<Challenge>, ApiError> {
+    let account = state.account(&headers)?;
+    let (challenge, challenger) = {
+        let accounts = state.accounts.lock().unwrap();
+        let challenge = accounts.answerable(&id, &account.name, true)?.clone();
+        let challenger = accounts.accounts[&challenge.challenger].clone();
+        (challenge, challenger)
+    };
+
+    // Seat both bots before recording the answer, so a challenge whose
+    // room filled up in the meantime stays pending.
+    let room = state.services.rooms.get_or_create(&challenge.room_id, challenge.options.clone(), state.services.default_rules.clone());
+    state.seat(&room, &challenger).await?;
+    state.seat(&room, &account).await?;
+    let challenge = state.accounts.lock().unwrap().respond(&id, &account.name, true)?;
+    Ok(Json(challenge))
+}
+
+async fn decline_challenge(State(state): State<BotApiState>, headers: HeaderMap, Path(id): Path<String>) -> Result<Json<Challenge>, ApiError> {
+    let account = state.account(&headers)?;
+    let challenge = state.accounts.lock().unwrap().respond(&id, &account.name, false)?;
+    Ok(Json(challenge))
+}
+
+/// The bot's view of a room. With `after_version`, waits (up to
+/// `MAX_WAIT`) for the room to move past that version first, so a bot
+/// can poll without spinning.
+async fn get_game(State(state): State<BotApiState>, headers: HeaderMap, Path(room_id): Path<String>, Query(query): Query<GameQuery>) -> Result<Json<BotGameView>, ApiError> {
+    let account = state.account(&headers)?;
+    let room = state.game(&room_id, &account)?;
+    if let Some(after) = query.after_version {
+        let mut changes = room.watch();
+        let _ = tokio::time::timeout(MAX_WAIT, changes.wait_for(|game| game.version > after)).await;
+    }
+    Ok(Json(game_view(&room.snapshot(), &account.player_id)))
+}
+
+async fn make_move(State(state): State<BotApiState>, headers: HeaderMap, Path(room_id): Path<String>, Json(request): Json<MoveRequest>) -> Result<Json<Ack>, ApiError> {
+    let account = state.account(&headers)?;
+    let room = state.game(&room_id, &account)?;
+    let version = room.act(account.player_id, request.action, request.guard).await?;
+    Ok(Json(Ack::ok(Some(version))))
+}
+
+async fn mulligan(State(state): State<BotApiState>, headers: HeaderMap, Path(room_id): Path<String>, Json(request): Json<MulliganRequest>) -> Result<Json<Ack>, ApiError> {
+    let account = state.account(&headers)?;
+    let room = state.game(&room_id, &account)?;
+    let version = room.mulligan(account.player_id, request.card_ids, request.guard).await?;
+    Ok(Json(Ack::ok(Some(version))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic;
+    use crate::game_types::GamePhase;
+
+    #[test]
+    fn test_accounts_and_challenges() {
+        let mut accounts = BotAccounts::default();
+        let (alpha, token) = accounts.register("alpha").unwrap();
+        assert_eq!(alpha.player_id, "bot:alpha");
+        assert_eq!(accounts.authenticate(&token).unwrap().name, "alpha");
+        assert!(accounts.authenticate("guess").is_none());
+        assert!(accounts.register("alpha").is_err());
+        assert!(accounts.register("no spaces").is_err());
+        accounts.register("beta").unwrap();
+        accounts.register("gamma").unwrap();
+
+        let direct = accounts.challenge("alpha", Some("beta".to_string()), RoomOptions::default()).unwrap();
+        let open = accounts.challenge("alpha", None, RoomOptions::default()).unwrap();
+        assert!(accounts.challenge("alpha", Some("alpha".to_string()), RoomOptions::default()).is_err());
+
+        // Gamma sees only the open challenge and cannot answer Beta's.
+        let seen: Vec<String> = accounts.challenges_for("gamma").into_iter().map(|c| c.id).collect();
+        assert_eq!(seen, vec![open.id.clone()]);
+        assert_eq!(accounts.respond(&direct.id, "gamma", true).unwrap_err().code, ErrorCode::ChallengeNotFound);
+        assert!(accounts.respond(&open.id, "alpha", true).is_err());
+        assert_eq!(accounts.respond(&open.id, "gamma", false).unwrap_err().code, ErrorCode::ChallengeNotFound);
+
+        let accepted = accounts.respond(&open.id, "gamma", true).unwrap();
+        assert_eq!((accepted.status, accepted.opponent.as_deref()), (ChallengeStatus::Accepted, Some("gamma")));
+        assert!(accounts.respond(&open.id, "beta", true).is_err());
+        assert_eq!(accounts.respond(&direct.id, "beta", false).unwrap().status, ChallengeStatus::Declined);
+        let withdrawn = accounts.challenge("beta", None, RoomOptions::default()).unwrap();
+        assert_eq!(accounts.respond(&withdrawn.id, "beta", false).unwrap().status, ChallengeStatus::Declined);
+    }
+
+    #[test]
+    fn test_accounts_survive_a_restart() {
+        let profiles = std::env::temp_dir().join(format!("profiles-{}.json", Uuid::new_v4()));
+        let path = accounts_file(&profiles);
+        let mut accounts = BotAccounts::load(Some(path.clone())).unwrap();
+        let (_, token) = accounts.register("alpha").unwrap();
+        let (path, text) = accounts.saved().unwrap();
+        assert!(!text.contains(&token), "tokens are saved only as hashes");
+        Profiles::write_file(&path, text).unwrap();
+
+        let mut reloaded = BotAccounts::load(Some(path.clone())).unwrap();
+        assert_eq!(reloaded.authenticate(&token).unwrap().player_id, "bot:alpha");
+        assert!(reloaded.register("alpha").is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_view_redacts_and_lists_actions() {
+        let mut game = game_logic::init_game("r1".to_string(), vec!["bot:a".to_string(), "bot:b".to_string()]);
+        let view = game_view(&game, "bot:a");
+        assert!(view.your_turn, "both players owe a mulligan");
+        assert!(view.legal_actions.is_empty());
+
+        game.phase = GamePhase::Playing;
+        game.current_turn = "bot:b".to_string();
+        let view = game_view(&game, "bot:b");
+        assert!(view.your_turn);
+        assert_eq!(view.legal_actions.last(), Some(&PlayerAction::Pass));
+        assert!(view.state.players["bot:b"].hand.is_some());
+        assert!(view.state.players["bot:a"].hand.is_none());
+        assert!(!game_view(&game, "bot:a").your_turn);
+    }
+
+    #[tokio::test]
+    async fn test_accepting_a_taken_open_challenge_keeps_it_pending() {
+        let records = Arc::new(tokio::sync::RwLock::new(crate::records::Records::default()));
+        let chat = Arc::new(crate::chat::ChatConfig::default());
+        let rooms = crate::room::RoomRegistry::new(records.clone(), chat.clone());
+        let tournaments = crate::tournament::Tournaments::new(rooms.clone(), records.clone(), Default::default());
+        let services = Services { rooms: rooms.clone(), default_rules: Default::default(), chat, tournaments, records };
+        let state = BotApiState { services, accounts: Arc::new(Mutex::new(BotAccounts::default())), registration_key: None };
+        let bearer = |token: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("authorization", format!("Bearer {}", token).parse().unwrap());
+            headers
+        };
+        let (_, alpha) = state.accounts.lock().unwrap().register("alpha").unwrap();
+        let (_, beta) = state.accounts.lock().unwrap().register("beta").unwrap();
+
+        let request = ChallengeRequest { opponent: None, options: RoomOptions::default() };
+        assert!(create_challenge(State(state.clone()), bearer(&alpha), Json(request)).await.is_ok());
+        let open = state.accounts.lock().unwrap().challenges_for("beta").remove(0);
+        rooms.get(&open.room_id).unwrap().join("carol".to_string(), "Carol".to_string(), "a".to_string()).await.unwrap();
+
+        let taken = accept_challenge(State(state.clone()), bearer(&beta), Path(open.id.clone())).await;
+        assert_eq!(taken.err().map(|e| e.0.code), Some(ErrorCode::RoomFull));
+        assert_eq!(state.accounts.lock().unwrap().challenges_for("beta")[0].status, ChallengeStatus::Pending);
+    }
+}
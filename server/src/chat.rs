@@ -0,0 +1,213 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+pub use poker_tactics_common::chat::{ChatMessage, EmoteEvent};
+
+/// Limits applied to every room chat.
+#[derive(Debug, Clone)]
+pub struct ChatConfig {
+    pub max_length: usize, // In characters, after trimming
+    pub history_size: usize,
+    pub rate_limit: usize, // Messages allowed per socket within `rate_window`
+    pub rate_window: Duration,
+    pub banned_words: Vec<String>,
+    pub emote_cooldown: Duration, // Per socket
+}
+
+impl Default for ChatConfig {
+    fn default() -> Self {
+        ChatConfig {
+            max_length: 200,
+            history_size: 50,
+            rate_limit: 5,
+            rate_window: Duration::from_secs(10),
+            banned_words: Vec::new(),
+            emote_cooldown: Duration::from_secs(3),
+        }
+    }
+}
+
+/// Quick emotes a player may send. Clients map the ids to localized text.
+pub const EMOTES: [&str; 8] = [
+    "greetings",
+    "good_luck",
+    "well_played",
+    "thinking",
+    "oops",
+    "wow",
+    "thanks",
+    "good_game",
+];
+
+/// The most recent messages of one room, oldest first.
+#[derive(Debug, Clone, Default)]
+pub struct ChatHistory {
+    messages: VecDeque<ChatMessage>,
+}
+
+impl ChatHistory {
+    pub fn push(&mut self, message: ChatMessage, capacity: usize) {
+        self.messages.push_back(message);
+        while self.messages.len() > capacity {
+            self.messages.pop_front();
+        }
+    }
+
+    pub fn messages(&self) -> Vec<ChatMessage> {
+        self.messages.iter().cloned().collect()
+    }
+}
+
+/// Chat state kept for each room.
+#[derive(Debug, Clone, Default)]
+pub struct RoomChat {
+    pub history: ChatHistory,
+    muted_emotes: HashMap<String, HashSet<String>>, // Player id -> senders they muted
+}
+
+impl RoomChat {
+    pub fn set_emotes_muted(&mut self, player_id: &str, sender_id: &str, muted: bool) {
+        let muted_by_player = self.muted_emotes.entry(player_id.to_string()).or_default();
+        if muted {
+            muted_by_player.insert(sender_id.to_string());
+        } else {
+            muted_by_player.remove(sender_id);
+        }
+    }
+
+    /// Senders whose emotes `player_id` does not want to see.
+    pub fn muted_by(&self, player_id: &str) -> Vec<String> {
+        let mut muted: Vec<String> = self.muted_emotes.get(player_id).into_iter().flatten().cloned().collect();
+        muted.sort();
+        muted
+    }
+
+    /// Players who muted `sender_id` and should not receive their emotes.
+    pub fn muting(&self, sender_id: &str) -> Vec<String> {
+        self.muted_emotes.iter()
+            .filter(|(_, senders)| senders.contains(sender_id))
+            .map(|(player_id, _)| player_id.clone())
+            .collect()
+    }
+}
+
+/// Sliding-window limiter, one per socket.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    sent: VecDeque<Instant>,
+}
+
+impl RateLimiter {
+    /// Records a message at `now` if fewer than `limit` were sent within
+    /// the last `window`.
+    pub fn try_send(&mut self, limit: usize, window: Duration, now: Instant) -> bool {
+        while let Some(&first) = self.sent.front() {
+            if now.duration_since(first) >= window {
+                self.sent.pop_front();
+            } else {
+                break;
+            }
+        }
+        if self.sent.len() >= limit {
+            return false;
+        }
+        self.sent.push_back(now);
+        true
+    }
+}
+
+pub fn validate_emote(emote: &str) -> Result<(), String> {
+    if EMOTES.contains(&emote) {
+        Ok(())
+    } else {
+        Err(format!("Unknown emote `{}`", emote))
+    }
+}
+
+/// Trims and length-checks a message, then masks banned words.
+pub fn prepare_text(text: &str, config: &ChatConfig) -> Result<String, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Message is empty".to_string());
+    }
+    if text.chars().count() > config.max_length {
+        return Err(format!("Message is longer than {} characters", config.max_length));
+    }
+    Ok(filter_words(text, &config.banned_words))
+}
+
+/// Replaces every case-insensitive occurrence of a banned word with `*`s.
+/// Matching is by substring so it also works for languages without spaces.
+pub fn filter_words(text: &str, banned_words: &[String]) -> String {
+    let mut chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+
+    for word in banned_words {
+        let word: Vec<char> = word.chars().flat_map(|c| c.to_lowercase()).collect();
+        if word.is_empty() || word.len() > lower.len() {
+            continue;
+        }
+        for start in 0..=lower.len() - word.len() {
+            if lower[start..start + word.len()] == word[..] {
+                chars[start..start + word.len()].fill('*');
+            }
+        }
+    }
+
+    chars.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(text: &str) -> ChatMessage {
+        ChatMessage { sender_id: "p1".to_string(), nickname: "P1".to_string(), spectator: false, text: text.to_string(), timestamp: 0 }
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut history = ChatHistory::default();
+        for i in 0..5 {
+            history.push(message(&i.to_string()), 3);
+        }
+        let texts: Vec<String> = history.messages().into_iter().map(|m| m.text).collect();
+        assert_eq!(texts, vec!["2", "3", "4"]);
+    }
+
+    #[test]
+    fn test_rate_limit_window() {
+        let window = Duration::from_secs(10);
+        let mut limiter = RateLimiter::default();
+        let start = Instant::now();
+
+        assert!(limiter.try_send(2, window, start));
+        assert!(limiter.try_send(2, window, start + Duration::from_secs(1)));
+        assert!(!limiter.try_send(2, window, start + Duration::from_secs(2)));
+        assert!(limiter.try_send(2, window, start + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_prepare_text() {
+        let config = ChatConfig { max_length: 10, banned_words: vec!["noob".to_string(), "笨蛋".to_string()], ..Default::default() };
+
+        assert_eq!(prepare_text("  gg NOOB ", &config).unwrap(), "gg ****");
+        assert_eq!(prepare_text("你這笨蛋", &config).unwrap(), "你這**");
+        assert!(prepare_text("   ", &config).is_err());
+        assert!(prepare_text("this is far too long", &config).is_err());
+    }
+
+    #[test]
+    fn test_emote_mutes() {
+        let mut chat = RoomChat::default();
+        chat.set_emotes_muted("p1", "p2", true);
+        assert_eq!(chat.muting("p2"), vec!["p1"]);
+        assert_eq!(chat.muted_by("p1"), vec!["p2"]);
+        assert!(chat.muting("p1").is_empty());
+
+        chat.set_emotes_muted("p1", "p2", false);
+        assert!(chat.muting("p2").is_empty());
+        assert!(validate_emote("well_played").is_ok());
+        assert!(validate_emote("rude_gesture").is_err());
+    }
+}
@@ -0,0 +1,261 @@
+use crate::game_types::RuleSet;
+use clap::Parser;
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Everything the server reads at startup. Values come from, in order of
+/// precedence: command-line flags, environment variables, the optional
+/// TOML file, and the defaults below.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind: String,
+    pub static_dir: PathBuf,
+    pub cors_origins: Vec<String>, // "*" allows any origin
+    pub cleanup_interval_secs: u64,
+    pub room_timeout_secs: u64,
+    pub log_level: String,
+    pub chat_banned_words: Vec<String>,
+    pub rules: RuleSet, // Rule set of newly created rooms
+    pub bot_registration_key: Option<String>, // Required to register bot accounts when set
+    pub profiles_file: Option<PathBuf>, // Player profiles are kept in memory only when unset
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind: "0.0.0.0:3000".to_string(),
+            static_dir: PathBuf::from("dist"),
+            cors_origins: vec!["*".to_string()],
+            cleanup_interval_secs: 600,
+            room_timeout_secs: 3600,
+            log_level: "info".to_string(),
+            chat_banned_words: Vec::new(),
+            rules: RuleSet::default(),
+            bot_registration_key: None,
+            profiles_file: None,
+        }
+    }
+}
+
+/// Command-line flags of the server; each also reads an environment
+/// variable.
+#[derive(Debug, Default, Parser)]
+#[command(name = "server", about = "Poker Tactics game server")]
+pub struct ConfigArgs {
+    /// TOML file with any of the settings below
+    #[arg(long, env = "POKER_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Address to listen on
+    #[arg(long, env = "POKER_BIND")]
+    pub bind: Option<String>,
+
+    /// Directory with the built client
+    #[arg(long, env = "POKER_STATIC_DIR")]
+    pub static_dir: Option<PathBuf>,
+
+    /// Comma-separated allowed origins, or `*` for any
+    #[arg(long, env = "POKER_CORS_ORIGINS", value_delimiter = ',')]
+    pub cors_origins: Option<Vec<String>>,
+
+    /// Seconds between inactive-room sweeps
+    #[arg(long, env = "POKER_CLEANUP_INTERVAL")]
+    pub cleanup_interval: Option<u64>,
+
+    /// Seconds without updates before a room is removed
+    #[arg(long, env = "POKER_ROOM_TIMEOUT")]
+    pub room_timeout: Option<u64>,
+
+    /// trace, debug, info, warn or error
+    #[arg(long, env = "POKER_LOG_LEVEL")]
+    pub log_level: Option<String>,
+
+    /// Comma-separated words masked in chat
+    #[arg(long, env = "POKER_CHAT_BANNED_WORDS", value_delimiter = ',')]
+    pub chat_banned_words: Option<Vec<String>>,
+
+    /// JSON or TOML file with the default rule set (missing fields keep
+    /// their defaults)
+    #[arg(long, env = "POKER_RULES")]
+    pub rules: Option<PathBuf>,
+
+    /// Key bot programs must send to register an account; open
+    /// registration when unset
+    #[arg(long, env = "POKER_BOT_REGISTRATION_KEY")]
+    pub bot_registration_key: Option<String>,
+
+    /// JSON file player profiles are loaded from and saved to; bot
+    /// accounts go next to it as `<name>.bots.json`
+    #[arg(long, env = "POKER_PROFILES_FILE")]
+    pub profiles_file: Option<PathBuf>,
+}
+
+fn read_file(path: &Path) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn parse_rules(path: &Path) -> Result<RuleSet, String> {
+    let text = read_file(path)?;
+    let parsed = if path.extension().is_some_and(|ext| ext == "toml") {
+        toml::from_str(&text).map_err(|e| e.to_string())
+    } else {
+        serde_json::from_str(&text).map_err(|e| e.to_string())
+    };
+    parsed.map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+impl ServerConfig {
+    /// Layers `args` over the TOML file they name (if any) and validates
+    /// the result.
+    pub fn load(args: ConfigArgs) -> Result<Self, String> {
+        let mut config = match &args.config {
+            Some(path) => toml::from_str(&read_file(path)?).map_err(|e| format!("{}: {}", path.display(), e))?,
+            None => ServerConfig::default(),
+        };
+
+        if let Some(bind) = args.bind {
+            config.bind = bind;
+        }
+        if let Some(dir) = args.static_dir {
+            config.static_dir = dir;
+        }
+        if let Some(origins) = args.cors_origins {
+            config.cors_origins = origins;
+        }
+        if let Some(secs) = args.cleanup_interval {
+            config.cleanup_interval_secs = secs;
+        }
+        if let Some(secs) = args.room_timeout {
+            config.room_timeout_secs = secs;
+        }
+        if let Some(level) = args.log_level {
+            config.log_level = level;
+        }
+        if let Some(words) = args.chat_banned_words {
+            config.chat_banned_words = words;
+        }
+        if let Some(path) = &args.rules {
+            config.rules = parse_rules(path)?;
+        }
+        if let Some(key) = args.bot_registration_key {
+            config.bot_registration_key = Some(key);
+        }
+        if let Some(path) = args.profiles_file {
+            config.profiles_file = Some(path);
+        }
+
+        config.cors_origins = config.cors_origins.iter().map(|o| o.trim().to_string()).filter(|o| !o.is_empty()).collect();
+        config.chat_banned_words = config.chat_banned_words.iter().map(|w| w.trim().to_string()).filter(|w| !w.is_empty()).collect();
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks every setting, reporting all problems at once.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut problems = Vec::new();
+
+        if self.bind.parse::<SocketAddr>().is_err() {
+            problems.push(format!("bind: `{}` is not an address like 0.0.0.0:3000", self.bind));
+        }
+        if self.static_dir.exists() && !self.static_dir.is_dir() {
+            problems.push(format!("static_dir: `{}` is not a directory", self.static_dir.display()));
+        }
+        if self.cors_origins.is_empty() {
+            problems.push("cors_origins: list at least one origin, or `*`".to_string());
+        }
+        for origin in &self.cors_origins {
+            let valid = origin == "*"
+                || ((origin.starts_with("http://") || origin.starts_with("https://")) && !origin.ends_with('/'));
+            if !valid {
+                problems.push(format!("cors_origins: `{}` should look like https://example.com", origin));
+            }
+        }
+        if self.cleanup_interval_secs == 0 {
+            problems.push("cleanup_interval_secs must be greater than 0".to_string());
+        }
+        if self.room_timeout_secs == 0 {
+            problems.push("room_timeout_secs must be greater than 0".to_string());
+        }
+        if self.log_level().is_none() {
+            problems.push(format!("log_level: `{}` is not one of trace, debug, info, warn, error", self.log_level));
+        }
+        if self.bot_registration_key.as_ref().is_some_and(|key| key.trim().is_empty()) {
+            problems.push("bot_registration_key must not be empty; leave it out for open registration".to_string());
+        }
+        if self.profiles_file.as_ref().is_some_and(|path| path.is_dir()) {
+            problems.push("profiles_file must be a file, not a directory".to_string());
+        }
+        if let Err(e) = self.rules.validate() {
+            problems.push(format!("rules: {}", e));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("invalid configuration:\n  {}", problems.join("\n  ")))
+        }
+    }
+
+    pub fn log_level(&self) -> Option<tracing::Level> {
+        self.log_level.parse().ok()
+    }
+
+    pub fn allows_any_origin(&self) -> bool {
+        self.cors_origins.iter().any(|o| o == "*")
+    }
+
+    pub fn cleanup_interval(&self) -> Duration {
+        Duration::from_secs(self.cleanup_interval_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flags_override_file() {
+        let path = std::env::temp_dir().join(format!("poker-config-{}.toml", std::process::id()));
+        std::fs::write(&path, "bind = \"127.0.0.1:4000\"\nroom_timeout_secs = 60\n\n[rules]\nking_power = 12\n").unwrap();
+
+        let args = ConfigArgs { config: Some(path.clone()), room_timeout: Some(120), ..Default::default() };
+        let config = ServerConfig::load(args).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.bind, "127.0.0.1:4000");
+        assert_eq!(config.room_timeout_secs, 120);
+        assert_eq!(config.rules.king_power, 12);
+        assert_eq!(config.rules.hand_size, 10);
+        assert_eq!(config.cleanup_interval_secs, 600);
+    }
+
+    #[test]
+    fn test_validation_lists_every_problem() {
+        let config = ServerConfig {
+            bind: "localhost".to_string(),
+            cors_origins: vec!["example.com".to_string()],
+            log_level: "loud".to_string(),
+            rules: RuleSet { hand_size: 40, ..RuleSet::default() },
+            ..ServerConfig::default()
+        };
+        let error = config.validate().unwrap_err();
+        for field in ["bind", "cors_origins", "log_level", "rules"] {
+            assert!(error.contains(field), "{} missing from {}", field, error);
+        }
+        assert!(ServerConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_every_env_var_is_prefixed() {
+        use clap::CommandFactory;
+        for arg in ConfigArgs::command().get_arguments() {
+            if let Some(env) = arg.get_env() {
+                assert!(env.to_string_lossy().starts_with("POKER_"), "{:?}", env);
+            }
+        }
+    }
+}
@@ -0,0 +1,394 @@
+use crate::game_logic;
+use crate::game_types::{Card, CardAbility, GamePhase, GameState, Player, PlayerAction};
+use crate::ismcts::action_key;
+use crate::records::ReplayStep;
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// Game-theoretic result of a position for one player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl Outcome {
+    fn from_value(value: i8) -> Self {
+        match value {
+            v if v > 0 => Outcome::Win,
+            0 => Outcome::Draw,
+            _ => Outcome::Loss,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Solution {
+    pub outcome: Outcome,
+    pub best_action: Option<PlayerAction>,
+    pub nodes: u64,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+struct Entry {
+    value: i8,
+    bound: Bound,
+    best: Option<String>,
+}
+
+/// Alpha-beta search with a transposition table. Values are always from the
+/// perspective player's side: +1 win, 0 draw, -1 loss.
+pub struct Solver {
+    perspective: String,
+    table: HashMap<u64, Entry>,
+    nodes: u64,
+    max_nodes: u64,
+}
+
+/// True when no card either side can still play would draw from the deck,
+/// so the deck order cannot influence the result.
+pub fn deck_is_irrelevant(game: &GameState) -> bool {
+    game.deck.is_empty() || game.players.values().all(|p| !can_draw(p))
+}
+
+/// Whether `player` can still get a Spy into play. Works out which cards
+/// can come back to hand: a Decoy takes back anything on the board (a
+/// Medic included), and a Medic with a Decoy to pick it up again brings
+/// back the discard pile. A Medic revives a Spy from the discard pile, and
+/// the board ends up there at the end of the round.
+fn can_draw(player: &Player) -> bool {
+    fn add<'a>(reachable: &mut Vec<&'a CardAbility>, cards: &'a [Card]) -> bool {
+        let before = reachable.len();
+        for card in cards {
+            if !reachable.contains(&&card.ability) {
+                reachable.push(&card.ability);
+            }
+        }
+        reachable.len() > before
+    }
+
+    let mut reachable = Vec::new();
+    add(&mut reachable, &player.hand);
+    while reachable.contains(&&CardAbility::Decoy) {
+        let board = add(&mut reachable, &player.board);
+        let discard = reachable.contains(&&CardAbility::Medic) && add(&mut reachable, &player.discard_pile);
+        if !board && !discard {
+            break;
+        }
+    }
+
+    let spy_in = |cards: &Vec<Card>| cards.iter().any(|c| c.ability == CardAbility::Intel);
+    reachable.contains(&&CardAbility::Intel)
+        || (reachable.contains(&&CardAbility::Medic) && (spy_in(&player.discard_pile) || spy_in(&player.board)))
+}
+
+/// Solves the rest of the match from `player_id`'s point of view, treating
+/// both hands as known. Returns `None` when the position is not in the
+/// Playing phase, the deck could still matter, or the search would need
+/// more than `max_nodes` nodes.
+pub fn solve(game: &GameState, player_id: &String, max_nodes: u64) -> Option<Solution> {
+    if game.phase != GamePhase::Playing || !game.players.contains_key(player_id) || !deck_is_irrelevant(game) {
+        return None;
+    }
+
+    let mut root = game.clone();
+    root.deck.clear();
+
+    let mut solver = Solver {
+        perspective: player_id.clone(),
+        table: HashMap::new(),
+        nodes: 0,
+        max_nodes,
+    };
+    let value = solver.search(&root, -1, 1)?;
+
+    let hash = solver.canonical_hash(&root);
+    let best_key = solver.table.get(&hash).and_then(|e| e.best.clone());
+    let best_action = if &root.current_turn == player_id {
+        best_key.and_then(|key| {
+            game_logic::legal_actions(game, player_id)
+                .into_iter()
+                .find(|a| action_key(game, player_id, a) == key)
+        })
+    } else {
+        None
+    };
+
+    Some(Solution {
+        outcome: Outcome::from_value(value),
+        best_action,
+        nodes: solver.nodes,
+    })
+}
+
+/// The solver's verdict on one recorded move: the best result the mover
+/// could force before it and the one they could still force after it.
+#[derive(Debug, Clone, Serialize)]
+pub struct MoveReview {
+    pub step: usize, // Index into the match's steps
+    pub player_id: String,
+    pub action: PlayerAction,
+    pub best_outcome: Outcome,
+    pub best_action: Option<PlayerAction>,
+    pub outcome: Outcome,
+    pub mistake: bool, // The move gave away a better result
+}
+
+/// Replays `steps` from `opening` and reviews every move the solver can
+/// settle within `max_nodes`, for post-game analysis. Moves made while the
+/// deck could still matter are left out.
+pub fn review(opening: &GameState, steps: &[ReplayStep], max_nodes: u64) -> Result<Vec<MoveReview>, String> {
+    let mut state = opening.clone();
+    let mut reviews = Vec::new();
+    for (step, s) in steps.iter().enumerate() {
+        let before = solve(&state, &s.player_id, max_nodes);
+        game_logic::apply_action(&mut state, &s.player_id, &s.action)?;
+        let Some(before) = before else { continue };
+        let after = match state.phase {
+            GamePhase::GameEnd => Some(match state.winner.as_deref() {
+                Some(w) if w == s.player_id => Outcome::Win,
+                Some("Draw") => Outcome::Draw,
+                _ => Outcome::Loss,
+            }),
+            _ => solve(&state, &s.player_id, max_nodes).map(|solution| solution.outcome),
+        };
+        if let Some(outcome) = after {
+            reviews.push(MoveReview {
+                step,
+                player_id: s.player_id.clone(),
+                action: s.action.clone(),
+                best_outcome: before.outcome,
+                best_action: before.best_action,
+                outcome,
+                mistake: outcome != before.outcome,
+            });
+        }
+    }
+    Ok(reviews)
+}
+
+impl Solver {
+    fn search(&mut self, state: &GameState, mut alpha: i8, mut beta: i8) -> Option<i8> {
+        self.nodes += 1;
+        if self.nodes > self.max_nodes {
+            return None;
+        }
+
+        if state.phase == GamePhase::GameEnd {
+            return Some(match &state.winner {
+                Some(w) if *w == self.perspective => 1,
+                Some(w) if w == "Draw" => 0,
+                _ => -1,
+            });
+        }
+
+        let hash = self.canonical_hash(state);
+        let mut tt_best = None;
+        if let Some(entry) = self.table.get(&hash) {
+            match entry.bound {
+                Bound::Exact => return Some(entry.value),
+                Bound::Lower => alpha = alpha.max(entry.value),
+                Bound::Upper => beta = beta.min(entry.value),
+            }
+            if alpha >= beta {
+                return Some(entry.value);
+            }
+            tt_best = entry.best.clone();
+        }
+
+        let mover = state.current_turn.clone();
+        let maximizing = mover == self.perspective;
+        let moves = self.ordered_moves(state, &mover, tt_best);
+
+        let (alpha_orig, beta_orig) = (alpha, beta);
+        let mut best_value = if maximizing { -2 } else { 2 };
+        let mut best_key = None;
+
+        for (key, action) in moves {
+            let mut next = state.clone();
+            if game_logic::apply_action(&mut next, &mover, &action).is_err() {
+                continue;
+            }
+            let value = self.search(&next, alpha, beta)?;
+
+            if (maximizing && value > best_value) || (!maximizing && value < best_value) {
+                best_value = value;
+                best_key = Some(key);
+            }
+            if maximizing {
+                alpha = alpha.max(value);
+            } else {
+                beta = beta.min(value);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if best_value <= alpha_orig {
+            Bound::Upper
+        } else if best_value >= beta_orig {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table.insert(hash, Entry { value: best_value, bound, best: best_key });
+
+        Some(best_value)
+    }
+
+    /// One action per distinct effect (two 7s play identically), with the
+    /// transposition table's previous best tried first.
+    fn ordered_moves(&self, state: &GameState, mover: &String, first: Option<String>) -> Vec<(String, PlayerAction)> {
+        let mut moves: Vec<(String, PlayerAction)> = Vec::new();
+        for action in game_logic::legal_actions(state, mover) {
+            let key = action_key(state, mover, &action);
+            if !moves.iter().any(|(k, _)| *k == key) {
+                moves.push((key, action));
+            }
+        }
+        if let Some(first) = first {
+            if let Some(idx) = moves.iter().position(|(k, _)| *k == first) {
+                let m = moves.remove(idx);
+                moves.insert(0, m);
+            }
+        }
+        moves
+    }
+
+    /// Hash of everything that can still affect the result. Card ids, suits
+    /// and the round counter are left out so equivalent positions collide.
+    fn canonical_hash(&self, state: &GameState) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (state.current_turn == self.perspective).hash(&mut hasher);
+
+        let mut seats: Vec<&Player> = state.players.values().collect();
+        seats.sort_by_key(|p| p.id != self.perspective);
+        for player in seats {
+            player.rounds_won.hash(&mut hasher);
+            player.passed.hash(&mut hasher);
+            player.current_score.hash(&mut hasher);
+            for zone in [&player.hand, &player.board, &player.discard_pile] {
+                let mut cards: Vec<String> = zone.iter()
+                    .map(|c| format!("{:?}/{}", c.rank, c.current_power))
+                    .collect();
+                cards.sort_unstable();
+                cards.hash(&mut hasher);
+            }
+        }
+
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_types::{Rank, Suit};
+
+    fn card(id: &str, rank: Rank, power: u8, ability: CardAbility, owner: &str) -> Card {
+        Card { id: id.to_string(), suit: Suit::Spade, rank, base_power: power, current_power: power, ability, owner_id: owner.to_string() }
+    }
+
+    fn final_round(p1_hand: Vec<Card>, p2_hand: Vec<Card>) -> GameState {
+        let ids = vec!["p1".to_string(), "p2".to_string()];
+        let mut game = game_logic::init_game("room_test".to_string(), ids);
+        game.phase = GamePhase::Playing;
+        game.current_turn = "p1".to_string();
+        game.round_count = 3;
+        game.deck.clear();
+        for (pid, hand) in [("p1", p1_hand), ("p2", p2_hand)] {
+            let player = game.players.get_mut(pid).unwrap();
+            player.hand = hand;
+            player.rounds_won = 1;
+        }
+        game
+    }
+
+    #[test]
+    fn test_solver_finds_forced_win() {
+        let game = final_round(
+            vec![card("k", Rank::King, 15, CardAbility::Hero, "p1")],
+            vec![card("9", Rank::Number(9), 9, CardAbility::None, "p2")],
+        );
+
+        let solution = solve(&game, &"p1".to_string(), 10_000).unwrap();
+        assert_eq!(solution.outcome, Outcome::Win);
+        assert_eq!(solution.best_action, Some(PlayerAction::Play { card_id: "k".to_string(), target_id: None }));
+    }
+
+    #[test]
+    fn test_solver_sees_scorch() {
+        // The 9 alone beats the 2, but the opponent answers it with the Ace.
+        let game = final_round(
+            vec![card("9", Rank::Number(9), 9, CardAbility::None, "p1")],
+            vec![
+                card("a", Rank::Ace, 0, CardAbility::Burn, "p2"),
+                card("2", Rank::Number(2), 2, CardAbility::IronGuard, "p2"),
+            ],
+        );
+
+        let solution = solve(&game, &"p1".to_string(), 10_000).unwrap();
+        assert_eq!(solution.outcome, Outcome::Loss);
+    }
+
+    #[test]
+    fn test_solver_skips_positions_with_live_deck() {
+        let mut game = final_round(
+            vec![card("j", Rank::Jack, 10, CardAbility::Intel, "p1")],
+            vec![card("9", Rank::Number(9), 9, CardAbility::None, "p2")],
+        );
+        game.deck.push(card("5", Rank::Number(5), 5, CardAbility::None, "deck"));
+
+        assert!(solve(&game, &"p1".to_string(), 10_000).is_none());
+    }
+
+    #[test]
+    fn test_review_flags_thrown_away_wins() {
+        let game = final_round(
+            vec![card("k", Rank::King, 15, CardAbility::Hero, "p1")],
+            vec![card("9", Rank::Number(9), 9, CardAbility::None, "p2")],
+        );
+        let step = |player: &str, action: PlayerAction| ReplayStep { player_id: player.to_string(), action };
+        let play = |id: &str| PlayerAction::Play { card_id: id.to_string(), target_id: None };
+
+        // Passing first hands the round to the 9.
+        let reviews = review(&game, &[step("p1", PlayerAction::Pass), step("p2", play("9"))], 10_000).unwrap();
+        assert_eq!(reviews.len(), 2);
+        assert_eq!((reviews[0].best_outcome, reviews[0].outcome, reviews[0].mistake), (Outcome::Win, Outcome::Loss, true));
+        assert_eq!(reviews[0].best_action, Some(play("k")));
+        assert!(!reviews[1].mistake);
+
+        let reviews = review(&game, &[step("p1", play("k")), step("p2", play("9"))], 10_000).unwrap();
+        assert!(reviews.iter().all(|r| !r.mistake));
+        assert!(review(&game, &[step("p2", play("9"))], 10_000).is_err());
+    }
+
+    #[test]
+    fn test_decoy_medic_chain_keeps_the_deck_relevant() {
+        // The Decoy takes back the Medic, which revives the Spy.
+        let mut game = final_round(
+            vec![card("jk", Rank::Joker, 0, CardAbility::Decoy, "p1")],
+            vec![card("9", Rank::Number(9), 9, CardAbility::None, "p2")],
+        );
+        let p1 = game.players.get_mut("p1").unwrap();
+        p1.board.push(card("q", Rank::Queen, 10, CardAbility::Medic, "p1"));
+        p1.discard_pile.push(card("j", Rank::Jack, 10, CardAbility::Intel, "p1"));
+        game.deck.push(card("5", Rank::Number(5), 5, CardAbility::None, "deck"));
+        assert!(!deck_is_irrelevant(&game));
+        assert!(solve(&game, &"p1".to_string(), 10_000).is_none());
+
+        // Without the Medic the Spy stays in the discard pile.
+        game.players.get_mut("p1").unwrap().board.clear();
+        assert!(deck_is_irrelevant(&game));
+    }
+}
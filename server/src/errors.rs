@@ -0,0 +1,38 @@
+use crate::metrics::metrics;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+
+pub use poker_tactics_common::errors::{Ack, ErrorCode, Rejection};
+
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    error: String,
+    error_code: ErrorCode,
+}
+
+/// A rejection sent back from a REST handler as an HTTP error with its code.
+pub struct ApiError(pub Rejection);
+
+impl From<Rejection> for ApiError {
+    fn from(rejection: Rejection) -> Self {
+        ApiError(rejection)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let ApiError(rejection) = self;
+        metrics().record_error(rejection.code);
+        let status = match rejection.code {
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::RoomNotFound | ErrorCode::ChallengeNotFound | ErrorCode::TournamentNotFound | ErrorCode::PlayerNotFound => StatusCode::NOT_FOUND,
+            ErrorCode::NotInRoom => StatusCode::FORBIDDEN,
+            ErrorCode::InvalidMessage => StatusCode::BAD_REQUEST,
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            _ => StatusCode::CONFLICT,
+        };
+        (status, Json(ErrorBody { error: rejection.message, error_code: rejection.code })).into_response()
+    }
+}
@@ -0,0 +1,185 @@
+use crate::game_logic;
+use crate::game_types::{Card, CardAbility, GameState, PlayerAction};
+use crate::ismcts::{self, action_key};
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+const SAMPLES_PER_ACTION: u32 = 200;
+const TIME_LIMIT: Duration = Duration::from_millis(800);
+
+/// One suggested action with its estimated chance of winning the match.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Hint {
+    pub action: PlayerAction,
+    pub win_probability: f64,
+    pub reason: HintReason,
+}
+
+/// Machine-readable explanation of what an action does right now. Powers
+/// are listed so clients can phrase the reason in their own language.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum HintReason {
+    ScorchDestroys { own_powers: Vec<u8>, opponent_powers: Vec<u8> },
+    ScorchWasted,
+    SpyDraws { cards_drawn: usize, opponent_gains: u8 },
+    BondActivates { bonus: u8 },
+    MedicRevives { revived_power: u8 },
+    MedicNoTarget,
+    DecoyReturns { returned_power: u8 },
+    AddsPower { power: u8, takes_lead: bool },
+    PassSecuresRound { lead: u32 },
+    PassConcedesRound { deficit: u32 },
+    PassSavesCards { cards_in_hand: usize },
+}
+
+/// Ranks every distinct legal action for `player_id` by simulated win rate.
+/// Each sample re-deals the cards that player cannot see, applies the
+/// action and rolls the match out, so the estimate never peeks at the
+/// opponent's actual hand.
+pub fn rank_actions<R: Rng>(game: &GameState, player_id: &String, rng: &mut R) -> Vec<Hint> {
+    let mut candidates: Vec<(String, PlayerAction)> = Vec::new();
+    for action in game_logic::legal_actions(game, player_id) {
+        let key = action_key(game, player_id, &action);
+        if !candidates.iter().any(|(k, _)| *k == key) {
+            candidates.push((key, action));
+        }
+    }
+    if candidates.is_empty() {
+        return Vec::new();
+    }
+
+    let started = Instant::now();
+    let per_action_time = TIME_LIMIT / candidates.len() as u32;
+
+    let mut hints: Vec<Hint> = candidates.into_iter()
+        .filter_map(|(_, action)| {
+            let reason = explain(game, player_id, &action)?;
+            let deadline = started.elapsed() + per_action_time;
+
+            let mut total = 0.0;
+            let mut samples = 0;
+            while samples < SAMPLES_PER_ACTION && started.elapsed() < deadline {
+                let mut state = ismcts::determinize(game, player_id, rng);
+                if game_logic::apply_action(&mut state, player_id, &action).is_err() {
+                    return None;
+                }
+                ismcts::rollout(&mut state, rng);
+                total += ismcts::outcome(&state, player_id);
+                samples += 1;
+            }
+
+            Some(Hint {
+                action,
+                win_probability: if samples > 0 { total / samples as f64 } else { 0.0 },
+                reason,
+            })
+        })
+        .collect();
+
+    hints.sort_by(|a, b| b.win_probability.total_cmp(&a.win_probability));
+    hints
+}
+
+/// Describes the immediate effect of `action` by applying it to a copy of
+/// the game. Returns `None` if game_logic rejects the action.
+pub fn explain(game: &GameState, player_id: &String, action: &PlayerAction) -> Option<HintReason> {
+    let me = game.players.get(player_id)?;
+    let opponent_id = game_logic::get_opponent_id(game, player_id).ok()?;
+    let opponent = game.players.get(&opponent_id)?;
+
+    let mut next = game.clone();
+    game_logic::apply_action(&mut next, player_id, action).ok()?;
+    let me_after = &next.players[player_id];
+    let opponent_after = &next.players[&opponent_id];
+
+    let (card_id, target_id) = match action {
+        PlayerAction::Pass => {
+            let lead = me.current_score as i64 - opponent.current_score as i64;
+            return Some(if opponent.passed && lead > 0 {
+                HintReason::PassSecuresRound { lead: lead as u32 }
+            } else if lead < 0 {
+                HintReason::PassConcedesRound { deficit: (-lead) as u32 }
+            } else {
+                HintReason::PassSavesCards { cards_in_hand: me.hand.len() }
+            });
+        },
+        PlayerAction::Play { card_id, target_id } => (card_id, target_id),
+        PlayerAction::Resign => return None,
+    };
+
+    let card = me.hand.iter().find(|c| &c.id == card_id)?;
+    let destroyed = |before: &Vec<Card>, after: &Vec<Card>| -> Vec<u8> {
+        before.iter()
+            .filter(|c| !after.iter().any(|a| a.id == c.id))
+            .map(|c| c.current_power)
+            .collect()
+    };
+
+    let reason = match card.ability {
+        CardAbility::Burn => {
+            let own_powers = destroyed(&me.board, &me_after.board);
+            let opponent_powers = destroyed(&opponent.board, &opponent_after.board);
+            if own_powers.is_empty() && opponent_powers.is_empty() {
+                HintReason::ScorchWasted
+            } else {
+                HintReason::ScorchDestroys { own_powers, opponent_powers }
+            }
+        },
+        CardAbility::Intel => HintReason::SpyDraws {
+            cards_drawn: me_after.hand.len() + 1 - me.hand.len(),
+            opponent_gains: card.current_power,
+        },
+        CardAbility::Medic => {
+            let revived = target_id.as_ref().and_then(|tid| me.discard_pile.iter().find(|c| &c.id == tid));
+            match revived {
+                Some(c) => HintReason::MedicRevives { revived_power: c.current_power },
+                None => HintReason::MedicNoTarget,
+            }
+        },
+        CardAbility::Decoy => {
+            let returned = target_id.as_ref().and_then(|tid| me.board.iter().find(|c| &c.id == tid))?;
+            HintReason::DecoyReturns { returned_power: returned.current_power }
+        },
+        CardAbility::IronGuard if me.board.iter().any(|c| c.ability == CardAbility::IronGuard) => {
+            HintReason::BondActivates { bonus: (me_after.current_score - me.current_score) as u8 }
+        },
+        _ => HintReason::AddsPower {
+            power: me_after.current_score.saturating_sub(me.current_score) as u8,
+            takes_lead: me.current_score <= opponent.current_score && me_after.current_score > opponent_after.current_score,
+        },
+    };
+
+    Some(reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_types::{GamePhase, Rank, Suit};
+
+    #[test]
+    fn test_explain_scorch() {
+        let ids = vec!["p1".to_string(), "p2".to_string()];
+        let mut game = game_logic::init_game("room_test".to_string(), ids);
+        game.phase = GamePhase::Playing;
+        game.current_turn = "p1".to_string();
+
+        let nine = Card { id: "p1_9".to_string(), suit: Suit::Club, rank: Rank::Number(9), base_power: 9, current_power: 9, ability: CardAbility::None, owner_id: "p1".to_string() };
+        let ten = Card { id: "p2_10".to_string(), suit: Suit::Club, rank: Rank::Number(10), base_power: 10, current_power: 10, ability: CardAbility::None, owner_id: "p2".to_string() };
+        let ace = Card { id: "ace".to_string(), suit: Suit::Club, rank: Rank::Ace, base_power: 0, current_power: 0, ability: CardAbility::Burn, owner_id: "p1".to_string() };
+        game.players.get_mut("p1").unwrap().board.push(nine);
+        game.players.get_mut("p2").unwrap().board.push(ten);
+        game.players.get_mut("p1").unwrap().hand.push(ace);
+
+        let action = PlayerAction::Play { card_id: "ace".to_string(), target_id: None };
+        let reason = explain(&game, &"p1".to_string(), &action).unwrap();
+        assert_eq!(reason, HintReason::ScorchDestroys { own_powers: vec![], opponent_powers: vec![10] });
+
+        let hints = rank_actions(&game, &"p1".to_string(), &mut rand::rng());
+        assert!(hints.iter().any(|h| h.reason == reason));
+        assert!(hints.windows(2).all(|w| w[0].win_probability >= w[1].win_probability));
+    }
+}
@@ -0,0 +1,300 @@
+use crate::endgame::{self, Outcome};
+use crate::game_logic;
+use crate::game_types::{GamePhase, GameState, PlayerAction};
+use rand::prelude::IndexedRandom;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::time::{Duration, Instant};
+
+const EXPLORATION: f64 = 0.7;
+
+// Determinizations with this few cards left in hand are solved exactly
+// instead of rolled out.
+const SOLVE_AT_CARDS: usize = 6;
+const SOLVE_NODE_LIMIT: u64 = 20_000;
+
+/// How much thinking a search is allowed. The search stops at whichever
+/// limit is hit first.
+#[derive(Debug, Clone, Copy)]
+pub struct SearchBudget {
+    pub iterations: u32,
+    pub time_limit: Duration,
+}
+
+struct Node {
+    key: Option<String>,
+    mover: String,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    visits: f64,
+    availability: f64,
+    reward: f64,
+}
+
+impl Node {
+    fn new(key: Option<String>, mover: String, parent: Option<usize>) -> Self {
+        Node { key, mover, parent, children: Vec::new(), visits: 0.0, availability: 1.0, reward: 0.0 }
+    }
+}
+
+/// Single-observer Information Set MCTS. Each iteration re-deals the cards
+/// `player_id` cannot see (opponent hand and deck), walks the shared tree
+/// using only the actions legal in that deal, and finishes with a fast
+/// rollout through game_logic.
+pub fn search<R: Rng>(game: &GameState, player_id: &String, budget: SearchBudget, rng: &mut R) -> PlayerAction {
+    let root_actions = game_logic::legal_actions(game, player_id);
+    if root_actions.len() <= 1 {
+        return root_actions.into_iter().next().unwrap_or(PlayerAction::Pass);
+    }
+
+    let started = Instant::now();
+    let mut tree = vec![Node::new(None, String::new(), None)];
+
+    for _ in 0..budget.iterations {
+        if started.elapsed() >= budget.time_limit {
+            break;
+        }
+
+        let mut state = determinize(game, player_id, rng);
+        let mut node = 0;
+
+        // Selection and expansion.
+        while state.phase == GamePhase::Playing {
+            let mover = state.current_turn.clone();
+            let legal = game_logic::legal_actions(&state, &mover);
+
+            let untried: Vec<&PlayerAction> = legal.iter()
+                .filter(|a| {
+                    let key = action_key(&state, &mover, a);
+                    !tree[node].children.iter().any(|&c| tree[c].key.as_deref() == Some(key.as_str()))
+                })
+                .collect();
+
+            if let Some(&action) = untried.choose(rng) {
+                let key = action_key(&state, &mover, action);
+                if game_logic::apply_action(&mut state, &mover, action).is_err() {
+                    break;
+                }
+                let child = tree.len();
+                tree.push(Node::new(Some(key), mover, Some(node)));
+                tree[node].children.push(child);
+                node = child;
+                break;
+            }
+
+            let mut best: Option<(usize, &PlayerAction)> = None;
+            let mut best_score = f64::NEG_INFINITY;
+            for &child in &tree[node].children.clone() {
+                let key = tree[child].key.clone().unwrap_or_default();
+                let action = match legal.iter().find(|a| action_key(&state, &mover, a) == key) {
+                    Some(a) => a,
+                    None => continue,
+                };
+                tree[child].availability += 1.0;
+                let n = &tree[child];
+                let score = n.reward / n.visits + EXPLORATION * (n.availability.ln() / n.visits).sqrt();
+                if score > best_score {
+                    best_score = score;
+                    best = Some((child, action));
+                }
+            }
+
+            match best {
+                Some((child, action)) => {
+                    if game_logic::apply_action(&mut state, &mover, action).is_err() {
+                        break;
+                    }
+                    node = child;
+                },
+                None => break,
+            }
+        }
+
+        let solved = solve_leaf(&state, player_id);
+        if solved.is_none() {
+            rollout(&mut state, rng);
+        }
+
+        // Backpropagation: every node is scored for the player who moved into it.
+        let mut current = Some(node);
+        while let Some(idx) = current {
+            let n = &mut tree[idx];
+            n.visits += 1.0;
+            if !n.mover.is_empty() {
+                n.reward += match solved {
+                    Some(result) if &n.mover == player_id => reward(result),
+                    Some(result) => 1.0 - reward(result),
+                    None => outcome(&state, &n.mover),
+                };
+            }
+            current = n.parent;
+        }
+    }
+
+    let best_key = tree[0].children.iter()
+        .max_by(|&&a, &&b| tree[a].visits.total_cmp(&tree[b].visits))
+        .and_then(|&c| tree[c].key.clone());
+
+    best_key
+        .and_then(|key| root_actions.iter().find(|a| action_key(game, player_id, a) == key).cloned())
+        .unwrap_or(PlayerAction::Pass)
+}
+
+/// Exact result for `player_id` when the determinized position is small
+/// enough for the endgame solver.
+fn solve_leaf(state: &GameState, player_id: &String) -> Option<Outcome> {
+    let cards_left: usize = state.players.values().map(|p| p.hand.len()).sum();
+    if cards_left > SOLVE_AT_CARDS {
+        return None;
+    }
+    endgame::solve(state, player_id, SOLVE_NODE_LIMIT).map(|s| s.outcome)
+}
+
+fn reward(result: Outcome) -> f64 {
+    match result {
+        Outcome::Win => 1.0,
+        Outcome::Draw => 0.5,
+        Outcome::Loss => 0.0,
+    }
+}
+
+/// Returns a copy of `game` where everything `player_id` cannot see has been
+/// reshuffled: the opponent's hand and the deck are redrawn from the same
+/// pool of unseen cards.
+pub fn determinize<R: Rng>(game: &GameState, player_id: &String, rng: &mut R) -> GameState {
+    let mut state = game.clone();
+    let opponent_id = match game_logic::get_opponent_id(&state, player_id) {
+        Ok(id) => id,
+        Err(_) => return state,
+    };
+
+    let opponent = state.players.get_mut(&opponent_id).unwrap();
+    let hand_size = opponent.hand.len();
+    let mut pool: Vec<_> = opponent.hand.drain(..).chain(state.deck.drain(..)).collect();
+    pool.shuffle(rng);
+
+    for mut card in pool.drain(..hand_size) {
+        card.owner_id = opponent_id.clone();
+        opponent.hand.push(card);
+    }
+    for mut card in pool {
+        card.owner_id = "deck".to_string();
+        state.deck.push(card);
+    }
+
+    state
+}
+
+/// Plays the match out with a cheap policy: bank a winning lead, otherwise
+/// play a random card, occasionally passing early to save cards.
+pub fn rollout<R: Rng>(state: &mut GameState, rng: &mut R) {
+    while state.phase == GamePhase::Playing {
+        let mover = state.current_turn.clone();
+        let action = rollout_action(state, &mover, rng);
+        if game_logic::apply_action(state, &mover, &action).is_err()
+            && game_logic::pass_turn(state, &mover).is_err()
+        {
+            break;
+        }
+    }
+}
+
+fn rollout_action<R: Rng>(state: &GameState, mover: &String, rng: &mut R) -> PlayerAction {
+    let plays: Vec<PlayerAction> = game_logic::legal_actions(state, mover)
+        .into_iter()
+        .filter(|a| *a != PlayerAction::Pass)
+        .collect();
+    if plays.is_empty() {
+        return PlayerAction::Pass;
+    }
+
+    let me = &state.players[mover];
+    let opponent = state.players.values().find(|p| &p.id != mover);
+    let (opp_score, opp_passed) = opponent.map(|p| (p.current_score, p.passed)).unwrap_or((0, true));
+    let lead = me.current_score as i64 - opp_score as i64;
+
+    if opp_passed && lead > 0 {
+        return PlayerAction::Pass;
+    }
+    if !opp_passed && lead > 10 && rng.random_bool(0.5) {
+        return PlayerAction::Pass;
+    }
+    plays.choose(rng).cloned().unwrap_or(PlayerAction::Pass)
+}
+
+/// 1 for a win, 0.5 for a draw, 0 for a loss or an unfinished match.
+pub fn outcome(state: &GameState, player_id: &String) -> f64 {
+    match &state.winner {
+        Some(w) if w == player_id => 1.0,
+        Some(w) if w == "Draw" => 0.5,
+        _ => 0.0,
+    }
+}
+
+/// Identifies an action by what is played rather than by card id, so
+/// equivalent cards in different determinizations share one tree edge.
+pub fn action_key(state: &GameState, mover: &String, action: &PlayerAction) -> String {
+    match action {
+        PlayerAction::Pass => "pass".to_string(),
+        PlayerAction::Resign => "resign".to_string(),
+        PlayerAction::Play { card_id, target_id } => {
+            let player = &state.players[mover];
+            let card = player.hand.iter().find(|c| &c.id == card_id);
+            let target = target_id.as_ref().and_then(|tid| {
+                player.board.iter().chain(player.discard_pile.iter()).find(|c| &c.id == tid)
+            });
+            match (card, target) {
+                (Some(c), Some(t)) => format!("{:?}>{:?}", c.rank, t.rank),
+                (Some(c), None) => format!("{:?}", c.rank),
+                _ => card_id.clone(),
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_types::{Card, CardAbility, Rank, Suit};
+
+    #[test]
+    fn test_determinize_keeps_visible_cards() {
+        let ids = vec!["p1".to_string(), "p2".to_string()];
+        let mut game = game_logic::init_game("room_test".to_string(), ids);
+        game.phase = GamePhase::Playing;
+        let me = "p1".to_string();
+
+        let state = determinize(&game, &me, &mut rand::rng());
+
+        let my_hand: Vec<_> = game.players["p1"].hand.iter().map(|c| c.id.clone()).collect();
+        let my_new_hand: Vec<_> = state.players["p1"].hand.iter().map(|c| c.id.clone()).collect();
+        assert_eq!(my_hand, my_new_hand);
+        assert_eq!(state.players["p2"].hand.len(), game.players["p2"].hand.len());
+        assert_eq!(state.deck.len(), game.deck.len());
+        assert!(state.players["p2"].hand.iter().all(|c| c.owner_id == "p2"));
+    }
+
+    #[test]
+    fn test_search_banks_the_winning_pass() {
+        let ids = vec!["p1".to_string(), "p2".to_string()];
+        let mut game = game_logic::init_game("room_test".to_string(), ids);
+        game.phase = GamePhase::Playing;
+        game.current_turn = "p1".to_string();
+        game.deck.clear();
+
+        // p1 already won a round and leads this one while p2 has passed.
+        // Their only card is a Spy, which would hand p2 the round.
+        let unit = Card { id: "five".to_string(), suit: Suit::Club, rank: Rank::Number(5), base_power: 5, current_power: 5, ability: CardAbility::None, owner_id: "p1".to_string() };
+        let spy = Card { id: "spy".to_string(), suit: Suit::Club, rank: Rank::Jack, base_power: 10, current_power: 10, ability: CardAbility::Intel, owner_id: "p1".to_string() };
+        let p1 = game.players.get_mut("p1").unwrap();
+        p1.hand = vec![spy];
+        p1.board.push(unit);
+        p1.current_score = 5;
+        p1.rounds_won = 1;
+        game.players.get_mut("p2").unwrap().passed = true;
+
+        let budget = SearchBudget { iterations: 300, time_limit: Duration::from_secs(5) };
+        let action = search(&game, &"p1".to_string(), budget, &mut rand::rng());
+        assert_eq!(action, PlayerAction::Pass);
+    }
+}
@@ -0,0 +1,23 @@
+pub mod achievements;
+pub mod api;
+pub mod bot;
+pub mod bot_api;
+pub mod chat;
+pub mod config;
+pub mod endgame;
+pub mod errors;
+pub mod hints;
+pub mod ismcts;
+pub mod lobby;
+pub mod metrics;
+pub mod profiles;
+pub mod protocol;
+pub mod records;
+pub mod room;
+pub mod scenario;
+pub mod session;
+pub mod simulation;
+pub mod tournament;
+pub mod ws;
+
+pub use poker_tactics_common::{delta, game_logic, game_types, notation};
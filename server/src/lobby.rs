@@ -0,0 +1,143 @@
+use crate::game_types::{GamePhase, GameState, RuleSet, Visibility};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Rooms per page when a request does not say.
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+pub const MAX_PAGE_SIZE: usize = 100;
+
+/// What the lobby shows of a room.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RoomInfo {
+    pub id: String,
+    pub player_count: usize,
+    pub phase: GamePhase,
+    pub creator: Option<String>, // Nickname of the first player to sit
+    pub created_at: u64,
+    pub variant: String, // "standard", "custom" for any rule override, or "scenario"
+    pub rules_summary: String,
+    pub visibility: Visibility,
+    pub rated: bool,
+    pub joinable: bool,
+    pub spectatable: bool,
+}
+
+/// "first to 2 rounds, 10-card hands, 2 jokers"
+fn rules_summary(rules: &RuleSet) -> String {
+    format!("first to {} rounds, {}-card hands, {} jokers", rules.rounds_to_win, rules.hand_size, rules.jokers)
+}
+
+pub fn room_info(game: &GameState) -> RoomInfo {
+    let public = game.options.visibility == Visibility::Public;
+    RoomInfo {
+        id: game.room_id.clone(),
+        player_count: game.players.len(),
+        phase: game.phase.clone(),
+        creator: game.creator.clone(),
+        created_at: game.created_at,
+        variant: match (&game.options.scenario, game.rules == RuleSet::default()) {
+            (Some(_), _) => "scenario",
+            (None, true) => "standard",
+            (None, false) => "custom",
+        }.to_string(),
+        rules_summary: rules_summary(&game.rules),
+        visibility: game.options.visibility,
+        rated: game.options.rated,
+        joinable: public && game.phase == GamePhase::Waiting && game.players.len() < 2 && game.options.seats.is_empty(),
+        spectatable: public && game.phase != GamePhase::Waiting,
+    }
+}
+
+/// Which public rooms a `list_rooms` or `watch_lobby` wants, and which
+/// page of them. Private rooms are never listed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct LobbyFilter {
+    pub joinable: bool, // Only rooms with a free seat
+    pub spectatable: bool, // Only rooms with a match to watch
+    pub variant: Option<String>,
+    pub offset: usize,
+    pub limit: Option<usize>, // DEFAULT_PAGE_SIZE, at most MAX_PAGE_SIZE
+}
+
+impl LobbyFilter {
+    pub fn matches(&self, room: &RoomInfo) -> bool {
+        room.visibility == Visibility::Public
+            && (!self.joinable || room.joinable)
+            && (!self.spectatable || room.spectatable)
+            && self.variant.as_ref().is_none_or(|v| *v == room.variant)
+    }
+
+    /// The matching rooms, newest first, cut to the requested page.
+    pub fn page(&self, games: &[GameState]) -> RoomsPage {
+        let mut rooms: Vec<RoomInfo> = games.iter().map(room_info).filter(|r| self.matches(r)).collect();
+        rooms.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| a.id.cmp(&b.id)));
+        let limit = self.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        let total = rooms.len();
+        let rooms = rooms.into_iter().skip(self.offset).take(limit).collect();
+        RoomsPage { rooms, total, offset: self.offset, limit }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RoomsPage {
+    pub rooms: Vec<RoomInfo>,
+    pub total: usize, // Matching rooms across all pages
+    pub offset: usize,
+    pub limit: usize,
+}
+
+/// A change pushed to lobby watchers. A room that stops matching a
+/// watcher's filter is sent to it as removed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum LobbyChange {
+    Updated { room: RoomInfo },
+    Removed { id: String },
+}
+
+impl LobbyChange {
+    /// This change as a watcher with `filter` should see it.
+    pub fn for_filter(&self, filter: &LobbyFilter) -> LobbyChange {
+        match self {
+            LobbyChange::Updated { room } if !filter.matches(room) => LobbyChange::Removed { id: room.id.clone() },
+            change => change.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic;
+
+    #[test]
+    fn test_filters_and_pages() {
+        let mut games: Vec<GameState> = (0..5).map(|i| {
+            let mut game = game_logic::init_game(format!("r{}", i), vec!["a".to_string(), "b".to_string()]);
+            game.created_at = 100 + i;
+            game
+        }).collect();
+        games[0].phase = GamePhase::Waiting;
+        games[0].players.remove("b");
+        games[1].rules.jokers = 0;
+        games[2].options.visibility = Visibility::Private;
+
+        let all = LobbyFilter::default().page(&games);
+        assert_eq!(all.total, 4);
+        assert_eq!(all.rooms.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), ["r4", "r3", "r1", "r0"]);
+
+        let joinable = LobbyFilter { joinable: true, ..LobbyFilter::default() }.page(&games);
+        assert_eq!(joinable.rooms.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), ["r0"]);
+        let custom = LobbyFilter { variant: Some("custom".to_string()), ..LobbyFilter::default() }.page(&games);
+        assert_eq!(custom.rooms[0].rules_summary, "first to 2 rounds, 10-card hands, 0 jokers");
+        assert_eq!(custom.total, 1);
+
+        let second = LobbyFilter { spectatable: true, offset: 1, limit: Some(1), ..LobbyFilter::default() }.page(&games);
+        assert_eq!((second.total, second.rooms[0].id.as_str()), (3, "r3"));
+
+        let change = LobbyChange::Updated { room: room_info(&games[1]) };
+        let filter = LobbyFilter { joinable: true, ..LobbyFilter::default() };
+        assert_eq!(change.for_filter(&filter), LobbyChange::Removed { id: "r1".to_string() });
+    }
+}
@@ -1,287 +1,156 @@
 use axum::Router;
-use game_types::{GamePhase, GameState, Player};
-use serde::Deserialize;
+use server::bot_api;
+use server::chat::ChatConfig;
+use server::api::{self, ApiState, SharedRecords};
+use server::metrics::metrics;
+use server::protocol::CLIENT_EVENTS;
+use server::profiles::Profiles;
+use server::records::Records;
+use server::room::RoomRegistry;
+use server::scenario;
+use server::session::{Connection, Services, Session};
+use server::tournament::{self, Tournaments};
+use server::ws;
+use serde_json::Value;
 use socketioxide::{
-    extract::{Data, SocketRef},
+    extract::{AckSender, SocketRef, TryData},
     SocketIo,
 };
-use serde::Serialize;
-use std::{collections::HashMap, sync::Arc};
+use std::sync::Arc;
 use tokio::sync::RwLock;
 use tower::ServiceBuilder;
-use tower_http::cors::CorsLayer;
+use axum::http::HeaderValue;
+use clap::Parser;
+use server::config::{ConfigArgs, ServerConfig};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 use tower_http::services::ServeDir;
-use tracing::{info, error, Level};
+use tracing::{info, error, warn, Level};
 
 use tracing_subscriber::FmtSubscriber;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-mod game_logic;
-mod game_types;
+/// Longest a profile change waits before it is written to `profiles_file`.
+const PROFILE_SAVE_INTERVAL: Duration = Duration::from_secs(5);
 
-type Games = Arc<RwLock<HashMap<String, GameState>>>;
+/// Socket.IO side of a `Session`. The socket is released on disconnect, so
+/// the event handlers holding the session do not keep it alive.
+struct SocketIoConnection(std::sync::Mutex<Option<SocketRef>>);
 
-#[derive(Debug, Deserialize)]
-struct JoinGamePayload {
-    room_id: String,
-    player_id: String,
-    nickname: String,
-    avatar: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct MulliganPayload {
-    room_id: String,
-    player_id: String,
-    card_ids: Vec<String>,
-}
-
-#[derive(Debug, Deserialize)]
-struct PlayCardPayload {
-    room_id: String,
-    player_id: String,
-    card_id: String,
-    target_id: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-struct PassPayload {
-    room_id: String,
-    player_id: String,
-}
-
-#[derive(Debug, Serialize, Clone)]
-struct RoomInfo {
-    id: String,
-    player_count: usize,
-    phase: GamePhase,
-}
-
-#[derive(Debug, Deserialize)]
-struct RestartGamePayload {
-    room_id: String,
-    player_id: String,
+impl Connection for SocketIoConnection {
+    fn emit(&self, event: &str, data: &Value) -> bool {
+        match self.0.lock().unwrap().as_ref() {
+            Some(socket) => socket.emit(event, data).is_ok(),
+            None => false,
+        }
+    }
 }
 
-
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let config = match ServerConfig::load(ConfigArgs::parse()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(2);
+        }
+    };
+
     // Initialize tracing
     let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::INFO)
+        .with_max_level(config.log_level().unwrap_or(Level::INFO))
         .finish();
     tracing::subscriber::set_global_default(subscriber)?;
 
     info!("Starting server...");
+    info!("Configuration: {:?}", config);
 
     // Global state
-    let games: Games = Arc::new(RwLock::new(HashMap::new()));
+    let profiles = match Profiles::load(config.profiles_file.clone()) {
+        Ok(profiles) => profiles,
+        Err(e) => {
+            eprintln!("error: profiles_file {}", e);
+            std::process::exit(2);
+        }
+    };
+    let bot_accounts = match bot_api::BotAccounts::load(config.profiles_file.as_deref().map(bot_api::accounts_file)) {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            eprintln!("error: bot accounts {}", e);
+            std::process::exit(2);
+        }
+    };
+    let records: SharedRecords = Arc::new(RwLock::new(Records::with_profiles(profiles)));
+    let chat_config = Arc::new(ChatConfig {
+        banned_words: config.chat_banned_words.clone(),
+        ..ChatConfig::default()
+    });
 
     // Create Socket.IO layer
     let (layer, io) = SocketIo::new_layer();
+    let rooms = RoomRegistry::new(records.clone(), chat_config.clone());
+
+    let services = Services {
+        rooms: rooms.clone(),
+        tournaments: Tournaments::new(rooms.clone(), records.clone(), config.rules.clone()),
+        default_rules: config.rules.clone(),
+        chat: chat_config.clone(),
+        records: records.clone(),
+    };
 
     // Register a handler for the default namespace
-    let games_clone = games.clone();
+    let services_clone = services.clone();
     io.ns("/", move |socket: SocketRef| async move {
         info!("Socket connected: {}", socket.id);
         println!("Socket connected: {}", socket.id);
+        metrics().socket_connected();
         
         if let Err(e) = socket.emit("connected", "Welcome") {
              error!("Failed to send welcome to {}: {}", socket.id, e);
         }
-        
-        let games = games_clone.clone();
-
-        socket.on("ping", |socket: SocketRef| async move {
-            info!("Received ping from {}", socket.id);
-            let _ = socket.emit("pong", "pong");
-        });
 
-        let games_join = games.clone();
-        socket.on("join_game", move |socket: SocketRef, Data::<JoinGamePayload>(data)| async move {
-            info!("Player {} joining room {}", data.player_id, data.room_id);
-            println!("Player {} joining room {}", data.player_id, data.room_id);
-            let _ = socket.leave_all();
-            
-            // socket.join returns () in this version/adapter configuration
-            let _ = socket.join(data.room_id.clone()); 
-            info!("Socket {} joined room {}", socket.id, data.room_id);
+        let conn = Arc::new(SocketIoConnection(std::sync::Mutex::new(Some(socket.clone()))));
+        let session = Arc::new(Session::new(conn.clone(), services_clone.clone()));
 
-            let mut games_guard = games_join.write().await;
-            
-            let game = games_guard.entry(data.room_id.clone()).or_insert_with(|| {
-                info!("Creating new game for room {}", data.room_id);
-                GameState {
-                    room_id: data.room_id.clone(),
-                    phase: GamePhase::Waiting,
-                    players: HashMap::new(),
-                    current_turn: String::new(),
-                    round_count: 0,
-                    deck: Vec::new(),
-                    winner: None,
-                    last_update: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
-                }
+        // Every event goes through the handlers shared with `/ws`; the ack
+        // is only sent if the client asked for one.
+        for event in CLIENT_EVENTS {
+            let session = session.clone();
+            socket.on(event, move |TryData::<Value>(data), ack: AckSender| async move {
+                let reply = session.handle(event, data.unwrap_or(Value::Null)).await;
+                let _ = ack.send(&reply);
             });
+        }
 
-            if !game.players.contains_key(&data.player_id) {
-                if game.players.len() >= 2 {
-                    let _ = socket.emit("error", "Room is full");
-                    return;
-                }
-
-                game.players.insert(data.player_id.clone(), Player {
-                    id: data.player_id.clone(),
-                    nickname: data.nickname.clone(),
-                    avatar: data.avatar.clone(),
-                    hand: Vec::new(),
-                    board: Vec::new(),
-                    discard_pile: Vec::new(),
-                    current_score: 0,
-                    rounds_won: 0,
-                    passed: false,
-                });
-                info!("Added player {} to game", data.player_id);
-                game.last_update = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-            } else {
-                // Update existing player info if reconnecting
-                if let Some(player) = game.players.get_mut(&data.player_id) {
-                    player.nickname = data.nickname.clone();
-                    player.avatar = data.avatar.clone();
-                }
-            }
-
-            if game.players.len() == 2 && game.phase == GamePhase::Waiting {
-                info!("Starting game in room {}", data.room_id);
-                let player_ids: Vec<String> = game.players.keys().cloned().collect();
-                let mut new_game_state = game_logic::init_game(data.room_id.clone(), player_ids);
-
-                // Preserve nicknames/avatars
-                for (pid, player) in &mut new_game_state.players {
-                    if let Some(old_player) = game.players.get(pid) {
-                        player.nickname = old_player.nickname.clone();
-                        player.avatar = old_player.avatar.clone();
-                    }
-                }
-
-                *game = new_game_state;
-            }
-
-            info!("Broadcasting game state update for room {}: {:?}", data.room_id, game.phase);
-            
-            // Broadcast is async
-            match socket.within(data.room_id.clone()).emit("game_state_update", &game.clone()).await {
-                Ok(_) => info!("Broadcast successful"),
-                Err(e) => error!("Broadcast failed: {}", e),
-            }
-        });
-
-        let games_mulligan = games.clone();
-        socket.on("mulligan", move |socket: SocketRef, Data::<MulliganPayload>(data)| async move {
-            let mut games_guard = games_mulligan.write().await;
-            if let Some(game) = games_guard.get_mut(&data.room_id) {
-                match game_logic::handle_mulligan(game, &data.player_id, data.card_ids) {
-                    Ok(_) => {
-                         let player = game.players.get_mut(&data.player_id).unwrap();
-                         player.passed = true;
-
-                         let all_ready = game.players.values().all(|p| p.passed);
-                         if all_ready {
-                             game.phase = GamePhase::Playing;
-                             for p in game.players.values_mut() {
-                                 p.passed = false;
-                             }
-                         }
-
-                         let _ = socket.within(data.room_id.clone()).emit("game_state_update", &game.clone()).await;
-                    },
-                    Err(e) => {
-                        let _ = socket.emit("error", &e);
-                    }
-                }
-            }
-        });
-
-        let games_play = games.clone();
-        socket.on("play_card", move |socket: SocketRef, Data::<PlayCardPayload>(data)| async move {
-            let mut games_guard = games_play.write().await;
-             if let Some(game) = games_guard.get_mut(&data.room_id) {
-                match game_logic::play_card(game, &data.player_id, &data.card_id, data.target_id) {
-                    Ok(_) => {
-                        let _ = socket.within(data.room_id.clone()).emit("game_state_update", &game.clone()).await;
-                    },
-                    Err(e) => {
-                        let _ = socket.emit("error", &e);
-                    }
-                }
-             }
-        });
-
-        let games_pass = games.clone();
-        socket.on("pass", move |socket: SocketRef, Data::<PassPayload>(data)| async move {
-            let mut games_guard = games_pass.write().await;
-             if let Some(game) = games_guard.get_mut(&data.room_id) {
-                match game_logic::pass_turn(game, &data.player_id) {
-                    Ok(_) => {
-                        let _ = socket.within(data.room_id.clone()).emit("game_state_update", &game.clone()).await;
-                    },
-                    Err(e) => {
-                        let _ = socket.emit("error", &e);
-                    }
-                }
-             }
-        });
-
-        let games_restart = games.clone();
-        socket.on("restart_game", move |socket: SocketRef, Data::<RestartGamePayload>(data)| async move {
-            info!("Player {} requesting restart for room {}", data.player_id, data.room_id);
-            let mut games_guard = games_restart.write().await;
-            
-            if let Some(game) = games_guard.get_mut(&data.room_id) {
-                // Only allow restart if game is over
-                if game.phase != GamePhase::GameEnd {
-                     let _ = socket.emit("error", "Cannot restart game while it is in progress");
-                     return;
-                }
-
-                info!("Restarting game in room {}", data.room_id);
-                let player_ids: Vec<String> = game.players.keys().cloned().collect();
-                let mut new_game_state = game_logic::init_game(data.room_id.clone(), player_ids);
-
-                // Preserve nicknames/avatars
-                for (pid, player) in &mut new_game_state.players {
-                    if let Some(old_player) = game.players.get(pid) {
-                        player.nickname = old_player.nickname.clone();
-                        player.avatar = old_player.avatar.clone();
-                    }
-                }
-
-                *game = new_game_state;
-                
-                info!("Broadcasting new game state for room {}", data.room_id);
-                let _ = socket.within(data.room_id.clone()).emit("game_state_update", &game.clone()).await;
+        socket.on_disconnect(move || {
+            let (session, conn) = (session.clone(), conn.clone());
+            async move {
+                metrics().socket_disconnected();
+                session.close();
+                conn.0.lock().unwrap().take();
             }
         });
-
-        let games_list = games.clone();
-        socket.on("list_rooms", move |socket: SocketRef| async move {
-            let games_guard = games_list.read().await;
-            let rooms: Vec<RoomInfo> = games_guard.values().map(|g| RoomInfo {
-                id: g.room_id.clone(),
-                player_count: g.players.len(),
-                phase: g.phase.clone(),
-            }).collect();
-            let _ = socket.emit("rooms_list", &rooms);
-        });
     });
 
 
     // Configure CORS
-    let cors = CorsLayer::permissive();
+    let cors = if config.allows_any_origin() {
+        CorsLayer::permissive()
+    } else {
+        let origins: Vec<HeaderValue> = config.cors_origins.iter().filter_map(|o| o.parse().ok()).collect();
+        CorsLayer::new()
+            .allow_origin(AllowOrigin::list(origins))
+            .allow_methods(Any)
+            .allow_headers(Any)
+    };
 
     // Build the Axum router
     let app = Router::new()
-        .fallback_service(ServeDir::new("dist"))
+        .merge(api::router(ApiState { rooms: rooms.clone(), records: records.clone() }))
+        .merge(ws::router(services.clone()))
+        .merge(tournament::router(services.tournaments.clone()))
+        .merge(scenario::router(rooms.clone()))
+        .merge(bot_api::router(services, bot_accounts, config.bot_registration_key.clone()))
+        .fallback_service(ServeDir::new(&config.static_dir))
         .layer(
             ServiceBuilder::new()
                 .layer(cors)
@@ -291,31 +160,45 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
 
     // Background task for room recycling
-    let games_cleanup = games.clone();
+    let rooms_cleanup = rooms.clone();
+    let cleanup_interval = config.cleanup_interval();
+    let room_timeout = config.room_timeout_secs;
+    let records_cleanup = records.clone();
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_secs(600)); // Check every 10 minutes
+        let mut interval = tokio::time::interval(cleanup_interval);
         loop {
             interval.tick().await;
             info!("Running room cleanup task...");
             
-            let mut games_guard = games_cleanup.write().await;
             let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-            let timeout = 3600; // 1 hour timeout
-            
-            let initial_count = games_guard.len();
-            games_guard.retain(|_, game| {
-                now - game.last_update < timeout
-            });
-            let removed_count = initial_count - games_guard.len();
-            
+            let removed_count = rooms_cleanup.close_inactive(now, room_timeout);
+            records_cleanup.write().await.retain_rooms(|room_id| rooms_cleanup.contains(room_id));
+
+            metrics().rooms_removed(removed_count);
             if removed_count > 0 {
-                info!("Removed {} inactive rooms. Remaining rooms: {}", removed_count, games_guard.len());
+                info!("Removed {} inactive rooms. Remaining rooms: {}", removed_count, rooms_cleanup.len());
+            }
+        }
+    });
+
+    // Background task writing changed profiles out, at most once per
+    // interval and off the async runtime
+    let records_saver = records.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PROFILE_SAVE_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Some((path, text)) = records_saver.write().await.take_unsaved_profiles() else { continue };
+            match tokio::task::spawn_blocking(move || Profiles::write_file(&path, text)).await {
+                Ok(Err(e)) => warn!("Could not save profiles: {}", e),
+                Err(e) => error!("Profile save failed: {}", e),
+                Ok(Ok(())) => {},
             }
         }
     });
 
     // Run the server
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
+    let listener = tokio::net::TcpListener::bind(&config.bind).await?;
     let addr = listener.local_addr()?;
     info!("Server listening on {}", addr);
     println!("Server listening on {}", addr);
@@ -0,0 +1,160 @@
+use crate::errors::ErrorCode;
+use crate::game_types::{GamePhase, GameState};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+/// Upper bounds of the latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 10] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0];
+
+const PHASES: [GamePhase; 5] = [
+    GamePhase::Waiting,
+    GamePhase::Mulligan,
+    GamePhase::Playing,
+    GamePhase::RoundEnd,
+    GamePhase::GameEnd,
+];
+
+#[derive(Debug, Default, Clone)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+/// Process-wide counters exported on `/metrics` in the Prometheus text
+/// format. Room gauges are computed from the live rooms at scrape time.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    connected_sockets: AtomicI64,
+    rooms_removed: AtomicU64,
+    latency: Mutex<BTreeMap<&'static str, Histogram>>, // Also holds the per-event action counts
+    errors: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::default);
+
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+/// Records one handled event and its latency when dropped.
+pub struct ActionTimer {
+    event: &'static str,
+    started: Instant,
+}
+
+impl Drop for ActionTimer {
+    fn drop(&mut self) {
+        metrics().observe_action(self.event, self.started.elapsed());
+    }
+}
+
+impl Metrics {
+    /// Start timing an event handler; keep the guard alive until it returns.
+    pub fn time_action(&self, event: &'static str) -> ActionTimer {
+        ActionTimer { event, started: Instant::now() }
+    }
+
+    pub fn observe_action(&self, event: &'static str, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let mut latency = self.latency.lock().unwrap();
+        let histogram = latency.entry(event).or_default();
+        for (i, bound) in LATENCY_BUCKETS.iter().enumerate() {
+            if seconds <= *bound {
+                histogram.buckets[i] += 1;
+            }
+        }
+        histogram.count += 1;
+        histogram.sum += seconds;
+    }
+
+    pub fn socket_connected(&self) {
+        self.connected_sockets.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn socket_disconnected(&self) {
+        self.connected_sockets.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn record_error(&self, code: ErrorCode) {
+        *self.errors.lock().unwrap().entry(code.as_str()).or_default() += 1;
+    }
+
+    pub fn rooms_removed(&self, count: usize) {
+        self.rooms_removed.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    /// Prometheus text exposition of every metric.
+    pub fn render<'a>(&self, rooms: impl Iterator<Item = &'a GameState>) -> String {
+        let mut out = String::new();
+
+        let mut by_phase = [0u64; PHASES.len()];
+        for game in rooms {
+            if let Some(i) = PHASES.iter().position(|p| *p == game.phase) {
+                by_phase[i] += 1;
+            }
+        }
+        out.push_str("# HELP poker_rooms Active rooms by game phase.\n# TYPE poker_rooms gauge\n");
+        for (phase, count) in PHASES.iter().zip(by_phase) {
+            let _ = writeln!(out, "poker_rooms{{phase=\"{:?}\"}} {}", phase, count);
+        }
+
+        out.push_str("# HELP poker_connected_sockets Currently connected Socket.IO and /ws clients.\n# TYPE poker_connected_sockets gauge\n");
+        let _ = writeln!(out, "poker_connected_sockets {}", self.connected_sockets.load(Ordering::Relaxed));
+
+        let latency = self.latency.lock().unwrap().clone();
+        out.push_str("# HELP poker_actions_total Events handled, by event type.\n# TYPE poker_actions_total counter\n");
+        for (event, histogram) in &latency {
+            let _ = writeln!(out, "poker_actions_total{{event=\"{}\"}} {}", event, histogram.count);
+        }
+
+        out.push_str("# HELP poker_action_duration_seconds Time spent handling an event.\n# TYPE poker_action_duration_seconds histogram\n");
+        for (event, histogram) in &latency {
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                let _ = writeln!(out, "poker_action_duration_seconds_bucket{{event=\"{}\",le=\"{}\"}} {}", event, bound, count);
+            }
+            let _ = writeln!(out, "poker_action_duration_seconds_bucket{{event=\"{}\",le=\"+Inf\"}} {}", event, histogram.count);
+            let _ = writeln!(out, "poker_action_duration_seconds_sum{{event=\"{}\"}} {}", event, histogram.sum);
+            let _ = writeln!(out, "poker_action_duration_seconds_count{{event=\"{}\"}} {}", event, histogram.count);
+        }
+
+        let errors = self.errors.lock().unwrap();
+        out.push_str("# HELP poker_errors_total Rejected requests, by error code.\n# TYPE poker_errors_total counter\n");
+        for code in ErrorCode::ALL {
+            let _ = writeln!(out, "poker_errors_total{{code=\"{}\"}} {}", code.as_str(), errors.get(code.as_str()).unwrap_or(&0));
+        }
+
+        out.push_str("# HELP poker_rooms_removed_total Inactive rooms removed by the cleanup task.\n# TYPE poker_rooms_removed_total counter\n");
+        let _ = writeln!(out, "poker_rooms_removed_total {}", self.rooms_removed.load(Ordering::Relaxed));
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic;
+
+    #[test]
+    fn test_render_prometheus_text() {
+        let metrics = Metrics::default();
+        metrics.observe_action("play_card", Duration::from_millis(3));
+        metrics.observe_action("play_card", Duration::from_millis(300));
+        metrics.record_error(ErrorCode::NotYourTurn);
+        metrics.rooms_removed(2);
+
+        let game = game_logic::init_game("room_test".to_string(), vec!["p1".to_string(), "p2".to_string()]);
+        let text = metrics.render([game].iter());
+
+        assert!(text.contains("poker_rooms{phase=\"Mulligan\"} 1"));
+        assert!(text.contains("poker_actions_total{event=\"play_card\"} 2"));
+        assert!(text.contains("poker_action_duration_seconds_bucket{event=\"play_card\",le=\"0.005\"} 1"));
+        assert!(text.contains("poker_action_duration_seconds_bucket{event=\"play_card\",le=\"+Inf\"} 2"));
+        assert!(text.contains("poker_errors_total{code=\"not_your_turn\"} 1"));
+        assert!(text.contains("poker_rooms_removed_total 2"));
+    }
+}
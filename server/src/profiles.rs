@@ -0,0 +1,258 @@
+use crate::game_types::{CardAbility, GamePhase, GameState, PlayerAction};
+use crate::records::MatchRecord;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Ranks listed in `favourite_cards`.
+const FAVOURITE_CARDS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CardUsage {
+    pub card: String, // Rank label: "2".."10", "J", "Q", "K", "A" or "Joker"
+    pub plays: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct UnlockedAchievement {
+    pub id: String, // See `achievements::ACHIEVEMENTS`
+    pub room_id: String,
+    pub unlocked_at: u64,
+}
+
+/// Lifetime statistics of one account, built from its completed matches.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct PlayerProfile {
+    pub id: String,
+    pub nickname: String,
+    pub avatar: String,
+    pub matches_played: u32,
+    pub matches_won: u32,
+    pub matches_drawn: u32,
+    pub rounds_played: u32,
+    pub rounds_won: u32, // Drawn rounds count for both players, as in the game
+    pub favourite_cards: Vec<CardUsage>, // Most played ranks first
+    pub spy_plays: u32,
+    pub medic_plays: u32,
+    pub scorch_plays: u32,
+    pub average_margin: f64, // Own score minus the opponent's, per round
+    pub current_streak: i32, // Wins in a row, or minus the losses in a row; 0 after a draw
+    pub last_played: u64,
+    pub achievements: Vec<UnlockedAchievement>, // Oldest first
+    pub card_plays: BTreeMap<String, u32>, // Every rank played
+    pub margin_total: i64,
+}
+
+impl PlayerProfile {
+    fn new(id: &str) -> Self {
+        PlayerProfile { id: id.to_string(), ..PlayerProfile::default() }
+    }
+
+    /// Adds one finished match, given its frames, from `self.id`'s seat.
+    fn add_match(&mut self, record: &MatchRecord, frames: &[GameState]) {
+        self.matches_played += 1;
+        self.last_played = record.ended_at.unwrap_or(record.started_at);
+        match record.winner.as_deref() {
+            Some("Draw") => {
+                self.matches_drawn += 1;
+                self.current_streak = 0;
+            },
+            Some(w) if w == self.id => {
+                self.matches_won += 1;
+                self.current_streak = self.current_streak.max(0) + 1;
+            },
+            _ => self.current_streak = self.current_streak.min(0) - 1,
+        }
+
+        for (step, pair) in record.steps.iter().zip(frames.windows(2)) {
+            let (before, after) = (&pair[0], &pair[1]);
+            match &step.action {
+                PlayerAction::Play { card_id, .. } if step.player_id == self.id => {
+                    let Some(card) = before.players.get(&self.id).and_then(|p| p.hand.iter().find(|c| &c.id == card_id)) else {
+                        continue;
+                    };
+                    *self.card_plays.entry(card.rank.label()).or_default() += 1;
+                    match card.ability {
+                        CardAbility::Intel => self.spy_plays += 1,
+                        CardAbility::Medic => self.medic_plays += 1,
+                        CardAbility::Burn => self.scorch_plays += 1,
+                        _ => {},
+                    }
+                },
+                // Only passes resolve rounds, so the scores before the
+                // last pass are the round's final scores.
+                PlayerAction::Pass if after.round_count > before.round_count || after.phase == GamePhase::GameEnd => {
+                    let Some(own) = before.players.get(&self.id) else { continue };
+                    let Some(other) = before.players.values().find(|p| p.id != self.id) else { continue };
+                    self.rounds_played += 1;
+                    if after.players[&self.id].rounds_won > own.rounds_won {
+                        self.rounds_won += 1;
+                    }
+                    self.margin_total += own.current_score as i64 - other.current_score as i64;
+                },
+                _ => {},
+            }
+        }
+
+        if self.rounds_played > 0 {
+            self.average_margin = self.margin_total as f64 / self.rounds_played as f64;
+        }
+        let mut favourites: Vec<CardUsage> = self.card_plays.iter()
+            .map(|(card, plays)| CardUsage { card: card.clone(), plays: *plays })
+            .collect();
+        favourites.sort_by(|a, b| b.plays.cmp(&a.plays).then_with(|| a.card.cmp(&b.card)));
+        favourites.truncate(FAVOURITE_CARDS);
+        self.favourite_cards = favourites;
+    }
+}
+
+/// Profiles of every human account, saved as JSON to `path` when one is
+/// configured. Changes only mark them unsaved; the server writes them out
+/// from time to time with `take_unsaved` and `write_file`, off the runtime.
+#[derive(Debug, Default)]
+pub struct Profiles {
+    profiles: HashMap<String, PlayerProfile>,
+    path: Option<PathBuf>,
+    unsaved: bool,
+}
+
+impl Profiles {
+    /// Reads the profiles saved at `path`; a missing file starts empty.
+    pub fn load(path: Option<PathBuf>) -> Result<Self, String> {
+        let profiles = match &path {
+            Some(p) if p.exists() => {
+                let text = std::fs::read_to_string(p).map_err(|e| format!("{}: {}", p.display(), e))?;
+                let list: Vec<PlayerProfile> = serde_json::from_str(&text).map_err(|e| format!("{}: {}", p.display(), e))?;
+                list.into_iter().map(|profile| (profile.id.clone(), profile)).collect()
+            },
+            _ => HashMap::new(),
+        };
+        Ok(Profiles { profiles, path, unsaved: false })
+    }
+
+    pub fn get(&self, player_id: &str) -> Option<&PlayerProfile> {
+        self.profiles.get(player_id)
+    }
+
+    /// Adds a finished match to the profiles of its human players.
+    pub fn record(&mut self, record: &MatchRecord) {
+        let frames = match record.frames() {
+            Ok(frames) => frames,
+            Err(e) => {
+                warn!("Match {} does not replay, profiles not updated: {}", record.id, e);
+                return;
+            },
+        };
+        for player in record.players.iter().filter(|p| !p.is_bot) {
+            let profile = self.profiles.entry(player.id.clone()).or_insert_with(|| PlayerProfile::new(&player.id));
+            profile.nickname = player.nickname.clone();
+            profile.avatar = player.avatar.clone();
+            profile.add_match(record, &frames);
+        }
+        self.unsaved = true;
+    }
+
+    /// Records that `player_id` has unlocked `achievement_id` in `room_id`;
+    /// false if they already had it.
+    pub fn unlock(&mut self, player_id: &str, nickname: &str, achievement_id: &str, room_id: &str, at: u64) -> bool {
+        let profile = self.profiles.entry(player_id.to_string()).or_insert_with(|| PlayerProfile::new(player_id));
+        if profile.achievements.iter().any(|a| a.id == achievement_id) {
+            return false;
+        }
+        profile.nickname = nickname.to_string();
+        profile.achievements.push(UnlockedAchievement { id: achievement_id.to_string(), room_id: room_id.to_string(), unlocked_at: at });
+        self.unsaved = true;
+        true
+    }
+
+    /// The file and its new contents if profiles changed since the last
+    /// call and are saved to a file at all.
+    pub fn take_unsaved(&mut self) -> Option<(PathBuf, String)> {
+        let path = self.path.clone().filter(|_| self.unsaved)?;
+        self.unsaved = false;
+        let mut list: Vec<&PlayerProfile> = self.profiles.values().collect();
+        list.sort_by(|a, b| a.id.cmp(&b.id));
+        Some((path, serde_json::to_string_pretty(&list).expect("profiles serialize")))
+    }
+
+    /// Writes `text` to `path` through a temporary file, so a crash never
+    /// leaves half a file behind. Blocks; run it with `spawn_blocking`.
+    pub fn write_file(path: &Path, text: String) -> Result<(), String> {
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, text).and_then(|_| std::fs::rename(&tmp, path)).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic;
+    use crate::records::{MatchPlayer, ReplayStep};
+
+    /// p1 plays its highest plain card each round; p2 always passes.
+    fn one_sided_match() -> MatchRecord {
+        let ids = vec!["p1".to_string(), "p2".to_string()];
+        let mut game = game_logic::init_game("room_test".to_string(), ids);
+        for pid in ["p1", "p2"] {
+            game_logic::submit_mulligan(&mut game, &pid.to_string(), Vec::new()).unwrap();
+        }
+        let opening = game.clone();
+        let mut steps = Vec::new();
+        while game.phase == GamePhase::Playing {
+            let mover = game.current_turn.clone();
+            let card = game.players[&mover].hand.iter()
+                .filter(|c| c.ability == CardAbility::None)
+                .max_by_key(|c| c.base_power)
+                .map(|c| c.id.clone());
+            let action = match card {
+                Some(card_id) if mover == "p1" && game.players[&mover].board.is_empty() => PlayerAction::Play { card_id, target_id: None },
+                _ => PlayerAction::Pass,
+            };
+            game_logic::apply_action(&mut game, &mover, &action).unwrap();
+            steps.push(ReplayStep { player_id: mover, action });
+        }
+        let players = ["p1", "p2"].iter()
+            .map(|id| MatchPlayer { id: id.to_string(), nickname: id.to_uppercase(), avatar: String::new(), is_bot: false })
+            .collect();
+        MatchRecord {
+            id: "m1".to_string(),
+            room_id: "room_test".to_string(),
+            players,
+            winner: game.winner.clone(),
+            started_at: 1,
+            ended_at: Some(2),
+            opening,
+            steps,
+        }
+    }
+
+    #[test]
+    fn test_profiles_accumulate_and_persist() {
+        let record = one_sided_match();
+        assert_eq!(record.winner.as_deref(), Some("p1"));
+        let path = std::env::temp_dir().join(format!("profiles-{}.json", uuid::Uuid::new_v4()));
+        let mut profiles = Profiles::load(Some(path.clone())).unwrap();
+        profiles.record(&record);
+        profiles.record(&record);
+
+        let winner = profiles.get("p1").unwrap();
+        assert_eq!((winner.matches_played, winner.matches_won, winner.current_streak), (2, 2, 2));
+        assert_eq!((winner.rounds_played, winner.rounds_won), (4, 4));
+        assert!(winner.average_margin > 0.0);
+        assert!(winner.favourite_cards[0].plays >= winner.favourite_cards.last().unwrap().plays);
+        let loser = profiles.get("p2").unwrap();
+        assert_eq!((loser.rounds_won, loser.current_streak, loser.nickname.as_str()), (0, -2, "P2"));
+        assert_eq!(loser.average_margin, -winner.average_margin);
+        assert!(loser.card_plays.is_empty());
+
+        let (file, text) = profiles.take_unsaved().unwrap();
+        assert!(profiles.take_unsaved().is_none());
+        Profiles::write_file(&file, text).unwrap();
+        let reloaded = Profiles::load(Some(path.clone())).unwrap();
+        assert_eq!(reloaded.get("p1"), profiles.get("p1"));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
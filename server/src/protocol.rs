@@ -0,0 +1,105 @@
+use crate::api::PublicGameState;
+use crate::achievements::AchievementUnlocked;
+use crate::chat::{ChatMessage, EmoteEvent};
+use crate::delta::StateDelta;
+use crate::errors::Ack;
+use crate::hints::Hint;
+use crate::lobby::{LobbyChange, LobbyFilter, RoomInfo, RoomsPage};
+use crate::profiles::PlayerProfile;
+use crate::tournament::TournamentView;
+use schemars::{JsonSchema, SchemaGenerator};
+use serde_json::{json, Value};
+
+pub use poker_tactics_common::protocol::*;
+
+fn of<T: JsonSchema>(generator: &mut SchemaGenerator) -> Value {
+    generator.subschema_for::<T>().to_value()
+}
+
+/// JSON Schema (2020-12) of the `/ws` frame and of every event's payload,
+/// generated from the payload types. Served on `/api/protocol` and checked
+/// in as protocol.schema.json.
+pub fn schema() -> Value {
+    let mut generator = SchemaGenerator::default();
+    let none = json!({ "type": "null" });
+    let text = of::<String>(&mut generator);
+
+    let frame = of::<Frame>(&mut generator);
+    let client_events = json!({
+        "handshake": of::<HandshakePayload>(&mut generator),
+        "ping": none,
+        "join_game": of::<JoinGamePayload>(&mut generator),
+        "spectate": of::<SpectatePayload>(&mut generator),
+        "resync": of::<ResyncPayload>(&mut generator),
+        "mulligan": of::<MulliganPayload>(&mut generator),
+        "play_card": of::<PlayCardPayload>(&mut generator),
+        "pass": of::<PassPayload>(&mut generator),
+        "resign": of::<ResignPayload>(&mut generator),
+        "restart_game": of::<RestartGamePayload>(&mut generator),
+        "add_bot": of::<AddBotPayload>(&mut generator),
+        "request_hint": of::<RequestHintPayload>(&mut generator),
+        "list_rooms": of::<Option<LobbyFilter>>(&mut generator),
+        "watch_lobby": of::<Option<LobbyFilter>>(&mut generator),
+        "chat_message": of::<ChatPayload>(&mut generator),
+        "emote": of::<EmotePayload>(&mut generator),
+        "mute_emotes": of::<MuteEmotesPayload>(&mut generator),
+        "watch_tournament": of::<WatchTournamentPayload>(&mut generator),
+        "get_profile": of::<GetProfilePayload>(&mut generator),
+    });
+    let server_events = json!({
+        "hello": of::<Hello>(&mut generator),
+        "connected": text,
+        "pong": text,
+        "error": text,
+        "ack": of::<Ack>(&mut generator),
+        "game_state_update": of::<PublicGameState>(&mut generator),
+        "game_state_delta": of::<StateDelta>(&mut generator),
+        "chat_history": of::<Vec<ChatMessage>>(&mut generator),
+        "chat_message": of::<ChatMessage>(&mut generator),
+        "emote": of::<EmoteEvent>(&mut generator),
+        "emote_mutes": of::<Vec<String>>(&mut generator),
+        "hints": of::<Vec<Hint>>(&mut generator),
+        "rooms_list": of::<Vec<RoomInfo>>(&mut generator),
+        "rooms_page": of::<RoomsPage>(&mut generator),
+        "lobby_update": of::<LobbyChange>(&mut generator),
+        "tournament_update": of::<TournamentView>(&mut generator),
+        "profile": of::<PlayerProfile>(&mut generator),
+        "achievement_unlocked": of::<AchievementUnlocked>(&mut generator),
+    });
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "Poker Tactics protocol",
+        "protocol_version": PROTOCOL_VERSION,
+        "min_protocol_version": MIN_PROTOCOL_VERSION,
+        "capabilities": CAPABILITIES,
+        "frame": frame,
+        "client_events": client_events,
+        "server_events": server_events,
+        "$defs": generator.take_definitions(true),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_covers_events_and_is_checked_in() {
+        let schema = schema();
+        for event in CLIENT_EVENTS {
+            assert!(schema["client_events"].get(event).is_some(), "no schema for `{}`", event);
+        }
+        assert_eq!(schema["client_events"].as_object().unwrap().len(), CLIENT_EVENTS.len());
+
+        let checked_in: Value = serde_json::from_str(include_str!("../../protocol.schema.json")).unwrap();
+        assert!(checked_in == schema, "protocol.schema.json is out of date; run `cargo run --bin schema > ../protocol.schema.json`");
+    }
+
+    #[test]
+    fn test_supported_versions() {
+        assert!(supports(PROTOCOL_VERSION));
+        assert!(!supports(PROTOCOL_VERSION + 1));
+        assert!(!supports(MIN_PROTOCOL_VERSION - 1));
+    }
+}
@@ -0,0 +1,275 @@
+use crate::game_logic;
+use crate::game_types::{GamePhase, GameState, PlayerAction};
+use crate::profiles::{PlayerProfile, Profiles};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Finished matches kept in memory for replays.
+const MAX_FINISHED_MATCHES: usize = 1000;
+pub const INITIAL_RATING: i32 = 1500;
+const RATING_K: f64 = 32.0;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchPlayer {
+    pub id: String,
+    pub nickname: String,
+    pub avatar: String,
+    pub is_bot: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReplayStep {
+    pub player_id: String,
+    pub action: PlayerAction,
+}
+
+/// One match from the end of the mulligan to the final pass. Game logic is
+/// deterministic once the deck is dealt, so the opening position and the
+/// actions are enough to rebuild every intermediate state.
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchRecord {
+    pub id: String,
+    pub room_id: String,
+    pub players: Vec<MatchPlayer>,
+    pub winner: Option<String>, // Player id, "Draw", or None while in progress
+    pub started_at: u64,
+    pub ended_at: Option<u64>,
+    pub opening: GameState,
+    pub steps: Vec<ReplayStep>,
+}
+
+impl MatchRecord {
+    /// The state after each step, starting with the opening position.
+    pub fn frames(&self) -> Result<Vec<GameState>, String> {
+        let mut state = self.opening.clone();
+        let mut frames = vec![state.clone()];
+        for step in &self.steps {
+            game_logic::apply_action(&mut state, &step.player_id, &step.action)?;
+            frames.push(state.clone());
+        }
+        Ok(frames)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerStats {
+    pub id: String,
+    pub nickname: String,
+    pub matches: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    pub rating: i32, // Elo, only moved by rated matches between two humans
+    pub recent_matches: Vec<String>, // Newest first
+}
+
+/// Match history and per-player results, fed by `observe` and
+/// `record_action` as rooms change.
+#[derive(Debug, Default)]
+pub struct Records {
+    in_progress: HashMap<String, MatchRecord>, // Key: room id
+    finished: HashMap<String, MatchRecord>,
+    finished_order: VecDeque<String>,
+    players: HashMap<String, PlayerStats>,
+    profiles: Profiles,
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+impl Records {
+    pub fn with_profiles(profiles: Profiles) -> Self {
+        Records { profiles, ..Records::default() }
+    }
+
+    /// Starts a record when a room enters play, finishes it when the match
+    /// ends, and drops it if the room goes back to dealing first.
+    pub fn observe(&mut self, game: &GameState) {
+        match game.phase {
+            GamePhase::Waiting | GamePhase::Mulligan => {
+                self.in_progress.remove(&game.room_id);
+            },
+            GamePhase::Playing | GamePhase::RoundEnd => {
+                if !self.in_progress.contains_key(&game.room_id) {
+                    let mut players: Vec<MatchPlayer> = game.players.values()
+                        .map(|p| MatchPlayer { id: p.id.clone(), nickname: p.nickname.clone(), avatar: p.avatar.clone(), is_bot: p.is_bot })
+                        .collect();
+                    players.sort_by(|a, b| a.id.cmp(&b.id));
+                    self.in_progress.insert(game.room_id.clone(), MatchRecord {
+                        id: Uuid::new_v4().to_string(),
+                        room_id: game.room_id.clone(),
+                        players,
+                        winner: None,
+                        started_at: now(),
+                        ended_at: None,
+                        opening: game.clone(),
+                        steps: Vec::new(),
+                    });
+                }
+            },
+            GamePhase::GameEnd => {
+                if let Some(mut record) = self.in_progress.remove(&game.room_id) {
+                    record.winner = game.winner.clone();
+                    record.ended_at = Some(now());
+                    self.finish(record);
+                }
+            },
+        }
+    }
+
+    /// Appends an action that game_logic has just accepted in `room_id`.
+    pub fn record_action(&mut self, room_id: &str, player_id: &str, action: &PlayerAction) {
+        if let Some(record) = self.in_progress.get_mut(room_id) {
+            record.steps.push(ReplayStep { player_id: player_id.to_string(), action: action.clone() });
+        }
+    }
+
+    /// Forgets unfinished matches of rooms that no longer exist.
+    pub fn retain_rooms(&mut self, keep: impl Fn(&str) -> bool) {
+        self.in_progress.retain(|room_id, _| keep(room_id));
+    }
+
+    pub fn get_match(&self, match_id: &str) -> Option<&MatchRecord> {
+        self.finished.get(match_id)
+    }
+
+    pub fn player(&self, player_id: &str) -> Option<&PlayerStats> {
+        self.players.get(player_id)
+    }
+
+    pub fn profile(&self, player_id: &str) -> Option<&PlayerProfile> {
+        self.profiles.get(player_id)
+    }
+
+    /// Profiles to write out, if they changed; see `Profiles::take_unsaved`.
+    pub fn take_unsaved_profiles(&mut self) -> Option<(PathBuf, String)> {
+        self.profiles.take_unsaved()
+    }
+
+    /// Adds an achievement to the profile; false if the player already had it.
+    pub fn unlock(&mut self, player_id: &str, nickname: &str, achievement_id: &str, room_id: &str) -> bool {
+        self.profiles.unlock(player_id, nickname, achievement_id, room_id, now())
+    }
+
+    /// Players who have finished at least one match, best rating first.
+    pub fn leaderboard(&self, limit: usize) -> Vec<PlayerStats> {
+        let mut players: Vec<PlayerStats> = self.players.values().cloned().collect();
+        players.sort_by(|a, b| b.rating.cmp(&a.rating).then(b.wins.cmp(&a.wins)).then(a.id.cmp(&b.id)));
+        players.truncate(limit);
+        players
+    }
+
+    /// Keeps `record` for replays; matches played from a scenario do not
+    /// count towards stats, ratings or profiles.
+    fn finish(&mut self, record: MatchRecord) {
+        if record.opening.options.scenario.is_some() {
+            self.keep(record);
+            return;
+        }
+        let humans: Vec<&MatchPlayer> = record.players.iter().filter(|p| !p.is_bot).collect();
+        for player in &humans {
+            let stats = self.players.entry(player.id.clone()).or_insert_with(|| PlayerStats {
+                id: player.id.clone(),
+                nickname: player.nickname.clone(),
+                matches: 0,
+                wins: 0,
+                losses: 0,
+                draws: 0,
+                rating: INITIAL_RATING,
+                recent_matches: Vec::new(),
+            });
+            stats.nickname = player.nickname.clone();
+            stats.matches += 1;
+            match record.winner.as_deref() {
+                Some("Draw") => stats.draws += 1,
+                Some(w) if w == player.id => stats.wins += 1,
+                _ => stats.losses += 1,
+            }
+            stats.recent_matches.insert(0, record.id.clone());
+            stats.recent_matches.truncate(20);
+        }
+
+        if let (true, [a, b]) = (record.opening.options.rated, &humans[..]) {
+            let score_a = match record.winner.as_deref() {
+                Some("Draw") => 0.5,
+                Some(w) if w == a.id => 1.0,
+                _ => 0.0,
+            };
+            let (ra, rb) = (self.players[&a.id].rating, self.players[&b.id].rating);
+            let (new_a, new_b) = elo(ra, rb, score_a);
+            self.players.get_mut(&a.id).unwrap().rating = new_a;
+            self.players.get_mut(&b.id).unwrap().rating = new_b;
+        }
+
+        self.profiles.record(&record);
+        self.keep(record);
+    }
+
+    fn keep(&mut self, record: MatchRecord) {
+        self.finished_order.push_back(record.id.clone());
+        self.finished.insert(record.id.clone(), record);
+        while self.finished_order.len() > MAX_FINISHED_MATCHES {
+            if let Some(old) = self.finished_order.pop_front() {
+                self.finished.remove(&old);
+            }
+        }
+    }
+}
+
+/// New ratings after a game where `a` scored `score_a` (1, 0.5 or 0).
+fn elo(ra: i32, rb: i32, score_a: f64) -> (i32, i32) {
+    let expected_a = 1.0 / (1.0 + 10f64.powf((rb - ra) as f64 / 400.0));
+    let delta = (RATING_K * (score_a - expected_a)).round() as i32;
+    (ra + delta, rb - delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot;
+
+    #[test]
+    fn test_match_is_recorded_and_replayable() {
+        let ids = vec!["p1".to_string(), "p2".to_string()];
+        let mut game = game_logic::init_game("room_test".to_string(), ids);
+        let mut records = Records::default();
+
+        for pid in ["p1", "p2"] {
+            game_logic::submit_mulligan(&mut game, &pid.to_string(), Vec::new()).unwrap();
+        }
+        records.observe(&game);
+        while game.phase == GamePhase::Playing {
+            let mover = game.current_turn.clone();
+            let action = bot::choose_action(&game, &mover, &mut rand::rng());
+            game_logic::apply_action(&mut game, &mover, &action).unwrap();
+            records.record_action("room_test", &mover, &action);
+            records.observe(&game);
+        }
+
+        let stats = records.leaderboard(10);
+        assert_eq!(stats.len(), 2);
+        let match_id = stats[0].recent_matches[0].clone();
+        let record = records.get_match(&match_id).unwrap();
+        assert_eq!(record.winner, game.winner);
+
+        let frames = record.frames().unwrap();
+        assert_eq!(frames.len(), record.steps.len() + 1);
+        assert_eq!(frames.last().unwrap().winner, game.winner);
+        if game.winner.as_deref() != Some("Draw") {
+            assert!(stats[0].rating > INITIAL_RATING && stats[1].rating < INITIAL_RATING);
+        }
+    }
+
+    #[test]
+    fn test_elo_is_zero_sum() {
+        assert_eq!(elo(1500, 1500, 1.0), (1516, 1484));
+        assert_eq!(elo(1500, 1500, 0.5), (1500, 1500));
+        let (a, b) = elo(1700, 1400, 0.0);
+        assert_eq!(a + b, 3100);
+        assert!(a < 1700);
+    }
+}
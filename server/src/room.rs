@@ -0,0 +1,906 @@
+use crate::achievements::{self, Achievement, AchievementUnlocked};
+use crate::api::{public_state, SharedRecords};
+use crate::chat::{ChatConfig, ChatMessage, EmoteEvent, RoomChat};
+use crate::delta::{self, StateDelta};
+use crate::errors::{ErrorCode, Rejection};
+use crate::game_types::{BotDifficulty, GamePhase, GameState, Player, PlayerAction, RoomOptions, RuleSet, Visibility};
+use crate::lobby::{self, LobbyChange, RoomInfo};
+use crate::metrics::metrics;
+use crate::protocol::ActionGuard;
+use crate::{bot, game_logic};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
+use tracing::{error, info};
+
+/// Commands queued per room before senders have to wait.
+const COMMAND_BUFFER: usize = 64;
+/// Action ids remembered per room for answering retries.
+const ACTION_LOG_SIZE: usize = 256;
+/// Broadcasts buffered per listener; one that falls further behind gets a
+/// fresh snapshot instead.
+const EVENT_BUFFER: usize = 256;
+/// Lobby changes buffered per watcher.
+const LOBBY_BUFFER: usize = 256;
+/// Records updates queued for the writer before rooms have to wait.
+const RECORDS_BUFFER: usize = 1024;
+
+type Reply<T> = oneshot::Sender<Result<T, Rejection>>;
+
+enum Command {
+    Join { player_id: String, nickname: String, avatar: String, reply: Reply<Welcome> },
+    Spectate { reply: Reply<Welcome> },
+    Guarded { player_id: String, guard: ActionGuard, request: Request, reply: Reply<u64> },
+    AddBot { difficulty: BotDifficulty, reply: Reply<()> },
+    Chat { player_id: String, spectator: Option<String>, text: String, reply: Reply<()> },
+    Emote { player_id: String, emote: String, reply: Reply<()> },
+    MuteEmotes { player_id: String, target_id: String, muted: bool, reply: Reply<Vec<String>> },
+    BotMove { bot_id: String, action: Option<PlayerAction> },
+    Unlocked(Vec<AchievementUnlocked>),
+    Rematch { reply: Reply<u64> },
+    Close,
+}
+
+/// State-changing requests that take an `ActionGuard`.
+enum Request {
+    Mulligan(Vec<String>),
+    Act(PlayerAction),
+    Restart,
+}
+
+/// Answers to recent guarded requests, keyed by player and action id.
+#[derive(Default)]
+struct ActionLog {
+    results: HashMap<(String, String), Result<u64, Rejection>>,
+    order: VecDeque<(String, String)>,
+}
+
+impl ActionLog {
+    fn get(&self, player_id: &str, action_id: &str) -> Option<Result<u64, Rejection>> {
+        self.results.get(&(player_id.to_string(), action_id.to_string())).cloned()
+    }
+
+    fn insert(&mut self, player_id: &str, action_id: &str, result: Result<u64, Rejection>) {
+        let key = (player_id.to_string(), action_id.to_string());
+        if self.results.insert(key.clone(), result).is_none() {
+            self.order.push_back(key);
+        }
+        while self.order.len() > ACTION_LOG_SIZE {
+            if let Some(old) = self.order.pop_front() {
+                self.results.remove(&old);
+            }
+        }
+    }
+}
+
+/// What a socket needs when it enters a room, besides the state broadcast.
+#[derive(Debug, Clone)]
+pub struct Welcome {
+    pub state: GameState,
+    pub chat_history: Vec<ChatMessage>,
+    pub emote_mutes: Vec<String>, // Players the joining player has muted
+}
+
+/// Who a room broadcast is for, by seated player id. Spectators only get
+/// `Everyone`, `Except` and `Spectators` events.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Audience {
+    Everyone,
+    Except(Vec<String>),
+    Player(String),
+    Spectators,
+}
+
+impl Audience {
+    pub fn includes(&self, player_id: Option<&str>) -> bool {
+        match self {
+            Audience::Everyone => true,
+            Audience::Except(ids) => player_id.is_none_or(|id| !ids.iter().any(|x| x == id)),
+            Audience::Player(id) => player_id == Some(id.as_str()),
+            Audience::Spectators => player_id.is_none(),
+        }
+    }
+}
+
+/// The state as `viewer` may see it, as sent in `game_state_update` and
+/// diffed for `game_state_delta`: only the viewer's own hand, and never
+/// the deck order. Spectators (`None`) see no hand at all.
+pub fn state_view(game: &GameState, viewer: Option<&str>) -> Value {
+    serde_json::to_value(public_state(game, viewer)).expect("game state serializes")
+}
+
+/// An event a room sends to its listeners, named and shaped like the
+/// Socket.IO event a connection should emit for it.
+#[derive(Debug, Clone)]
+pub struct RoomEvent {
+    pub event: &'static str,
+    pub data: Value,
+    pub audience: Audience,
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+fn empty_game(room_id: &str, options: RoomOptions, rules: RuleSet) -> GameState {
+    GameState {
+        room_id: room_id.to_string(),
+        phase: GamePhase::Waiting,
+        players: HashMap::new(),
+        current_turn: String::new(),
+        round_count: 0,
+        deck: Vec::new(),
+        winner: None,
+        last_update: now(),
+        version: 0,
+        options,
+        rules,
+        created_at: now(),
+        creator: None,
+    }
+}
+
+fn seat(id: String, nickname: String, avatar: String, is_bot: bool, bot_difficulty: BotDifficulty) -> Player {
+    Player {
+        id,
+        nickname,
+        avatar,
+        hand: Vec::new(),
+        board: Vec::new(),
+        discard_pile: Vec::new(),
+        current_score: 0,
+        rounds_won: 0,
+        passed: false,
+        is_bot,
+        bot_difficulty,
+    }
+}
+
+/// Deals a fresh match for the players already seated in `game`, keeping
+/// their nicknames, avatars and bot flags, and lets bots mulligan.
+fn start_new_match(game: &mut GameState) {
+    let player_ids: Vec<String> = game.players.keys().cloned().collect();
+    let mut new_game_state = game_logic::init_game_with(game.room_id.clone(), player_ids, game.rules.clone(), &mut rand::rng());
+    new_game_state.options = game.options.clone();
+    new_game_state.version = game.version;
+    new_game_state.created_at = game.created_at;
+    new_game_state.creator = game.creator.clone();
+
+    for (pid, player) in &mut new_game_state.players {
+        if let Some(old_player) = game.players.get(pid) {
+            player.nickname = old_player.nickname.clone();
+            player.avatar = old_player.avatar.clone();
+            player.is_bot = old_player.is_bot;
+            player.bot_difficulty = old_player.bot_difficulty;
+        }
+    }
+
+    *game = new_game_state;
+    bot::play_pending_mulligans(game);
+}
+
+/// What rooms hand the records writer, applied in the order sent.
+enum RecordsUpdate {
+    Observe(GameState),
+    Action { room_id: String, player_id: String, action: PlayerAction },
+    /// Achievements met in `room_id` as `(player id, nickname, achievement)`;
+    /// the new ones go back to the room as `Command::Unlocked`.
+    Unlock { room_id: String, met: Vec<(String, String, &'static Achievement)>, room: mpsc::WeakSender<Command> },
+    Settled(oneshot::Sender<()>),
+}
+
+/// Applies every room's records updates in one task, taking the records
+/// lock once per batch, so rooms never wait on the lock or on each other.
+fn spawn_records_writer(records: SharedRecords) -> mpsc::Sender<RecordsUpdate> {
+    let (sender, mut updates) = mpsc::channel(RECORDS_BUFFER);
+    tokio::spawn(async move {
+        let mut batch = Vec::new();
+        while updates.recv_many(&mut batch, RECORDS_BUFFER).await > 0 {
+            let mut records = records.write().await;
+            for update in batch.drain(..) {
+                match update {
+                    RecordsUpdate::Observe(game) => records.observe(&game),
+                    RecordsUpdate::Action { room_id, player_id, action } => records.record_action(&room_id, &player_id, &action),
+                    RecordsUpdate::Unlock { room_id, met, room } => {
+                        let unlocked: Vec<AchievementUnlocked> = met.into_iter()
+                            .filter(|(id, nickname, achievement)| records.unlock(id, nickname, achievement.id, &room_id))
+                            .map(|(id, nickname, achievement)| AchievementUnlocked::new(&id, &nickname, achievement))
+                            .collect();
+                        if let (false, Some(room)) = (unlocked.is_empty(), room.upgrade()) {
+                            tokio::spawn(async move { room.send(Command::Unlocked(unlocked)).await });
+                        }
+                    },
+                    RecordsUpdate::Settled(reply) => {
+                        let _ = reply.send(());
+                    },
+                }
+            }
+        }
+    });
+    sender
+}
+
+/// Shared services every room task uses.
+#[derive(Clone)]
+struct RoomContext {
+    records: mpsc::Sender<RecordsUpdate>,
+    chat: Arc<ChatConfig>,
+    lobby: broadcast::Sender<LobbyChange>,
+}
+
+/// One room's task. It owns the game and the chat, applies commands one at
+/// a time and does its own broadcasting, so rooms never wait on each other.
+struct RoomActor {
+    game: GameState,
+    chat: RoomChat,
+    ctx: RoomContext,
+    commands: mpsc::WeakSender<Command>,
+    published: watch::Sender<GameState>,
+    events: broadcast::Sender<RoomEvent>,
+    sent: GameState, // The published state, to diff the next one against
+    actions: ActionLog,
+    bot_thinking: bool,
+    listed: Option<RoomInfo>, // What the lobby was last told, if anything
+    opening: Option<GameState>, // The position a scenario room started from
+}
+
+impl RoomActor {
+    async fn run(mut self, mut commands: mpsc::Receiver<Command>) {
+        // A room opened mid-match is recorded, listed and handed to its
+        // bots like any other change.
+        if self.game.phase != GamePhase::Waiting {
+            self.opening = Some(self.game.clone());
+            self.changed().await;
+        }
+        while let Some(command) = commands.recv().await {
+            match command {
+                Command::Join { player_id, nickname, avatar, reply } => {
+                    let result = self.join(player_id, nickname, avatar).await;
+                    let _ = reply.send(result);
+                },
+                Command::Spectate { reply } => {
+                    let _ = reply.send(Ok(self.welcome(None)));
+                },
+                Command::Guarded { player_id, guard, request, reply } => {
+                    let result = self.guarded(player_id, guard, request).await;
+                    let _ = reply.send(result);
+                },
+                Command::AddBot { difficulty, reply } => {
+                    let result = self.add_bot(difficulty).await;
+                    let _ = reply.send(result);
+                },
+                Command::Chat { player_id, spectator, text, reply } => {
+                    let result = self.chat(player_id, spectator, text).await;
+                    let _ = reply.send(result);
+                },
+                Command::Emote { player_id, emote, reply } => {
+                    let result = self.emote(player_id, emote).await;
+                    let _ = reply.send(result);
+                },
+                Command::MuteEmotes { player_id, target_id, muted, reply } => {
+                    let result = if self.game.players.contains_key(&player_id) {
+                        self.chat.set_emotes_muted(&player_id, &target_id, muted);
+                        let muted = self.chat.muted_by(&player_id);
+                        self.broadcast("emote_mutes", &muted, Audience::Player(player_id));
+                        Ok(muted)
+                    } else {
+                        Err(Rejection::new(ErrorCode::NotInRoom, "You are not in this room"))
+                    };
+                    let _ = reply.send(result);
+                },
+                Command::BotMove { bot_id, action } => {
+                    self.bot_thinking = false;
+                    match action {
+                        Some(action) => self.bot_move(bot_id, action).await,
+                        None => error!("Bot {} gave up in room {}", bot_id, self.game.room_id),
+                    }
+                },
+                Command::Unlocked(unlocked) => {
+                    for event in unlocked {
+                        info!("{} unlocked {} in room {}", event.nickname, event.id, self.game.room_id);
+                        self.broadcast("achievement_unlocked", &event, Audience::Everyone);
+                    }
+                },
+                Command::Rematch { reply } => {
+                    let result = self.rematch().await;
+                    let _ = reply.send(result);
+                },
+                Command::Close => break,
+            }
+        }
+
+        if self.listed.is_some() {
+            let _ = self.ctx.lobby.send(LobbyChange::Removed { id: self.game.room_id.clone() });
+        }
+        info!("Room {} closed", self.game.room_id);
+    }
+
+    fn welcome(&self, player_id: Option<&str>) -> Welcome {
+        Welcome {
+            state: self.game.clone(),
+            chat_history: self.chat.history.messages(),
+            emote_mutes: player_id.map(|id| self.chat.muted_by(id)).unwrap_or_default(),
+        }
+    }
+
+    /// Publishes the new state under the next version, records it and
+    /// broadcasts the delta to the room, then hands the turn to a bot if one
+    /// is to move.
+    async fn changed(&mut self) {
+        self.game.last_update = now();
+        self.game.version += 1;
+        let _ = self.ctx.records.send(RecordsUpdate::Observe(self.game.clone())).await;
+        self.published.send_replace(self.game.clone());
+
+        // Every seat and the spectators get the change to their own view.
+        let previous = std::mem::replace(&mut self.sent, self.game.clone());
+        let mut viewers: Vec<Option<&str>> = self.game.players.keys().map(|id| Some(id.as_str())).collect();
+        viewers.push(None);
+        for viewer in viewers {
+            let update = StateDelta {
+                room_id: self.game.room_id.clone(),
+                from_version: self.game.version - 1,
+                version: self.game.version,
+                patch: delta::diff(&state_view(&previous, viewer), &state_view(&self.game, viewer)),
+            };
+            let audience = viewer.map_or(Audience::Spectators, |id| Audience::Player(id.to_string()));
+            self.broadcast("game_state_delta", &update, audience);
+        }
+        self.update_lobby();
+        self.schedule_bot();
+    }
+
+    /// Tells lobby watchers about a public room whose listing changed.
+    fn update_lobby(&mut self) {
+        if self.game.options.visibility == Visibility::Private {
+            return;
+        }
+        let info = lobby::room_info(&self.game);
+        if self.listed.as_ref() != Some(&info) {
+            let _ = self.ctx.lobby.send(LobbyChange::Updated { room: info.clone() });
+            self.listed = Some(info);
+        }
+    }
+
+    /// Sends an event to every connection listening to the room. Nobody
+    /// listening is not an error.
+    fn broadcast(&self, event: &'static str, data: &impl Serialize, audience: Audience) {
+        let data = serde_json::to_value(data).expect("room events serialize");
+        let _ = self.events.send(RoomEvent { event, data, audience });
+    }
+
+    /// Whether `player_id` may take a seat: anyone, unless the room
+    /// reserves its seats for a scheduled match.
+    fn reserved_for(&self, player_id: &str) -> bool {
+        let seats = &self.game.options.seats;
+        seats.is_empty() || seats.iter().any(|seat| seat == player_id)
+    }
+
+    async fn join(&mut self, player_id: String, nickname: String, avatar: String) -> Result<Welcome, Rejection> {
+        let mut changed = true;
+        match self.game.players.get_mut(&player_id) {
+            Some(player) => {
+                // Reconnecting: refresh the profile. An unchanged one keeps
+                // the version, so in-flight `expected_version`s still hold.
+                changed = player.nickname != nickname || player.avatar != avatar;
+                player.nickname = nickname;
+                player.avatar = avatar;
+            },
+            None => {
+                if self.game.players.len() >= 2 {
+                    return Err(Rejection::new(ErrorCode::RoomFull, "Room is full"));
+                }
+                if !self.reserved_for(&player_id) {
+                    return Err(Rejection::new(ErrorCode::RoomFull, "The seats in this room are reserved"));
+                }
+                info!("Added player {} to room {}", player_id, self.game.room_id);
+                self.game.creator.get_or_insert_with(|| nickname.clone());
+                self.game.players.insert(player_id.clone(), seat(player_id.clone(), nickname, avatar, false, BotDifficulty::default()));
+            },
+        }
+
+        if self.game.players.len() == 2 && self.game.phase == GamePhase::Waiting {
+            info!("Starting game in room {}", self.game.room_id);
+            start_new_match(&mut self.game);
+            changed = true;
+        }
+        if changed {
+            self.changed().await;
+        }
+        Ok(self.welcome(Some(&player_id)))
+    }
+
+    /// Runs `request` unless it repeats an action id or expects another
+    /// version; returns the version the request left the room at.
+    async fn guarded(&mut self, player_id: String, guard: ActionGuard, request: Request) -> Result<u64, Rejection> {
+        if let Some(previous) = guard.action_id.as_deref().and_then(|id| self.actions.get(&player_id, id)) {
+            return previous;
+        }
+
+        let result = match guard.expected_version {
+            Some(expected) if expected != self.game.version => Err(Rejection::new(
+                ErrorCode::StaleVersion,
+                format!("The game has moved on (version {}, expected {})", self.game.version, expected),
+            )),
+            _ => self.handle(&player_id, request).await.map(|_| self.game.version),
+        };
+
+        if let Some(action_id) = &guard.action_id {
+            self.actions.insert(&player_id, action_id, result.clone());
+        }
+        result
+    }
+
+    async fn handle(&mut self, player_id: &str, request: Request) -> Result<(), Rejection> {
+        match request {
+            Request::Mulligan(card_ids) => {
+                game_logic::submit_mulligan(&mut self.game, &player_id.to_string(), card_ids).map_err(Rejection::from_game_error)?;
+                bot::play_pending_mulligans(&mut self.game);
+                self.changed().await;
+                Ok(())
+            },
+            Request::Act(action) => self.act(player_id, action).await,
+            Request::Restart => {
+                if self.game.phase != GamePhase::GameEnd {
+                    return Err(Rejection::new(ErrorCode::GameInProgress, "Cannot restart game while it is in progress"));
+                }
+                if let Some(opening) = &self.opening {
+                    info!("Reloading the opening position in room {}", self.game.room_id);
+                    let version = self.game.version;
+                    let players = std::mem::take(&mut self.game.players);
+                    self.game = GameState { version, last_update: now(), ..opening.clone() };
+                    for (id, player) in &mut self.game.players {
+                        if let Some(old) = players.get(id) {
+                            player.nickname = old.nickname.clone();
+                            player.avatar = old.avatar.clone();
+                        }
+                    }
+                    self.changed().await;
+                    return Ok(());
+                }
+                if !self.game.options.seats.is_empty() {
+                    return Err(Rejection::new(ErrorCode::WrongPhase, "A scheduled match cannot be restarted"));
+                }
+                info!("Restarting game in room {}", self.game.room_id);
+                start_new_match(&mut self.game);
+                self.changed().await;
+                Ok(())
+            },
+        }
+    }
+
+    async fn rematch(&mut self) -> Result<u64, Rejection> {
+        if self.game.phase != GamePhase::GameEnd {
+            return Err(Rejection::new(ErrorCode::GameInProgress, "The match is still in progress"));
+        }
+        info!("Dealing the next match of the series in room {}", self.game.room_id);
+        start_new_match(&mut self.game);
+        self.changed().await;
+        Ok(self.game.version)
+    }
+
+    async fn add_bot(&mut self, difficulty: BotDifficulty) -> Result<(), Rejection> {
+        if self.game.phase != GamePhase::Waiting || self.game.players.len() >= 2 || !self.game.options.seats.is_empty() {
+            return Err(Rejection::new(ErrorCode::RoomFull, "Room is full"));
+        }
+
+        let bot_id = bot::bot_player_id();
+        info!("Adding {:?} bot {} to room {}", difficulty, bot_id, self.game.room_id);
+        self.game.players.insert(bot_id.clone(), seat(bot_id, "Bot".to_string(), "🤖".to_string(), true, difficulty));
+
+        if self.game.players.len() == 2 {
+            info!("Starting game in room {}", self.game.room_id);
+            start_new_match(&mut self.game);
+        }
+        self.changed().await;
+        Ok(())
+    }
+
+    async fn act(&mut self, player_id: &str, action: PlayerAction) -> Result<(), Rejection> {
+        let before = self.game.clone();
+        game_logic::apply_action(&mut self.game, &player_id.to_string(), &action).map_err(Rejection::from_game_error)?;
+        let room_id = self.game.room_id.clone();
+        let recorded = RecordsUpdate::Action { room_id: room_id.clone(), player_id: player_id.to_string(), action: action.clone() };
+        let _ = self.ctx.records.send(recorded).await;
+        // Scenario positions are set up by hand, so they earn nothing.
+        let met: Vec<_> = match self.game.options.scenario {
+            None => achievements::evaluate(&before, &self.game, player_id, &action),
+            Some(_) => Vec::new(),
+        }
+        .into_iter()
+        .map(|(id, achievement)| (id.clone(), self.game.players[&id].nickname.clone(), achievement))
+        .collect();
+        self.changed().await;
+        // Whether an achievement is new is up to the profiles; the writer
+        // sends back the ones that are as `Command::Unlocked`.
+        if !met.is_empty() {
+            let _ = self.ctx.records.send(RecordsUpdate::Unlock { room_id, met, room: self.commands.clone() }).await;
+        }
+        Ok(())
+    }
+
+    /// Starts a search for the bot whose turn it is, unless one is running.
+    /// The result comes back as a `BotMove` command, so the room keeps
+    /// serving other commands while the bot thinks.
+    fn schedule_bot(&mut self) {
+        if self.bot_thinking || self.game.phase != GamePhase::Playing {
+            return;
+        }
+        let bot_id = match self.game.players.get(&self.game.current_turn) {
+            Some(p) if p.is_bot => p.id.clone(),
+            _ => return,
+        };
+        let commands = match self.commands.upgrade() {
+            Some(commands) => commands,
+            None => return,
+        };
+
+        self.bot_thinking = true;
+        let snapshot = self.game.clone();
+        tokio::spawn(async move {
+            let bot_turn = bot_id.clone();
+            let action = match tokio::task::spawn_blocking(move || bot::choose_action(&snapshot, &bot_turn, &mut rand::rng())).await {
+                Ok(action) => Some(action),
+                Err(e) => {
+                    error!("Bot search failed: {}", e);
+                    None
+                }
+            };
+            let _ = commands.send(Command::BotMove { bot_id, action }).await;
+        });
+    }
+
+    async fn bot_move(&mut self, bot_id: String, action: PlayerAction) {
+        // The room may have moved on while the bot was thinking.
+        if self.game.current_turn != bot_id || !game_logic::legal_actions(&self.game, &bot_id).contains(&action) {
+            self.schedule_bot();
+            return;
+        }
+
+        let _timer = metrics().time_action("bot_action");
+        if let Err(e) = self.act(&bot_id, action).await {
+            error!("Bot {} made an illegal move: {}", bot_id, e.message);
+            let _ = self.act(&bot_id, PlayerAction::Pass).await;
+        }
+    }
+
+    async fn chat(&mut self, player_id: String, spectator: Option<String>, text: String) -> Result<(), Rejection> {
+        let (nickname, spectator) = match self.game.players.get(&player_id) {
+            Some(player) => (player.nickname.clone(), false),
+            None => match spectator {
+                Some(nickname) if self.game.options.spectator_chat => (nickname, true),
+                _ => return Err(Rejection::new(ErrorCode::NotInRoom, "You cannot chat in this room")),
+            },
+        };
+
+        let message = ChatMessage { sender_id: player_id, nickname, spectator, text, timestamp: now() };
+        self.chat.history.push(message.clone(), self.ctx.chat.history_size);
+        self.broadcast("chat_message", &message, Audience::Everyone);
+        Ok(())
+    }
+
+    async fn emote(&mut self, player_id: String, emote: String) -> Result<(), Rejection> {
+        if !self.game.players.contains_key(&player_id) {
+            return Err(Rejection::new(ErrorCode::NotInRoom, "Only players can send emotes"));
+        }
+
+        let muting = self.chat.muting(&player_id);
+        let event = EmoteEvent { sender_id: player_id, emote };
+        self.broadcast("emote", &event, Audience::Except(muting));
+        Ok(())
+    }
+}
+
+/// Cheap, cloneable way to reach one room's task.
+#[derive(Clone)]
+pub struct RoomHandle {
+    commands: mpsc::Sender<Command>,
+    state: watch::Receiver<GameState>,
+    events: broadcast::Sender<RoomEvent>,
+}
+
+fn room_closed() -> Rejection {
+    Rejection::new(ErrorCode::RoomNotFound, "Room not found")
+}
+
+impl RoomHandle {
+    /// The state as of the last change the room published.
+    pub fn snapshot(&self) -> GameState {
+        self.state.borrow().clone()
+    }
+
+    /// Receiver that wakes on every published change.
+    pub fn watch(&self) -> watch::Receiver<GameState> {
+        self.state.clone()
+    }
+
+    /// Receiver of every broadcast from now on. Subscribe before sending
+    /// the request whose effects should be seen.
+    pub fn subscribe(&self) -> broadcast::Receiver<RoomEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.commands.is_closed()
+    }
+
+    async fn request<T>(&self, command: impl FnOnce(Reply<T>) -> Command) -> Result<T, Rejection> {
+        let (reply, response) = oneshot::channel();
+        self.commands.send(command(reply)).await.map_err(|_| room_closed())?;
+        response.await.map_err(|_| room_closed())?
+    }
+
+    /// Seats a player, or refreshes their profile if already seated; the
+    /// match starts once two players are in.
+    pub async fn join(&self, player_id: String, nickname: String, avatar: String) -> Result<Welcome, Rejection> {
+        self.request(|reply| Command::Join { player_id, nickname, avatar, reply }).await
+    }
+
+    pub async fn spectate(&self) -> Result<Welcome, Rejection> {
+        self.request(|reply| Command::Spectate { reply }).await
+    }
+
+    async fn guarded(&self, player_id: String, guard: ActionGuard, request: Request) -> Result<u64, Rejection> {
+        self.request(|reply| Command::Guarded { player_id, guard, request, reply }).await
+    }
+
+    /// The mutating calls below return the state version they left the
+    /// room at.
+    pub async fn mulligan(&self, player_id: String, card_ids: Vec<String>, guard: ActionGuard) -> Result<u64, Rejection> {
+        self.guarded(player_id, guard, Request::Mulligan(card_ids)).await
+    }
+
+    pub async fn act(&self, player_id: String, action: PlayerAction, guard: ActionGuard) -> Result<u64, Rejection> {
+        self.guarded(player_id, guard, Request::Act(action)).await
+    }
+
+    /// Deals a new match once the current one is over.
+    pub async fn restart(&self, player_id: String, guard: ActionGuard) -> Result<u64, Rejection> {
+        self.guarded(player_id, guard, Request::Restart).await
+    }
+
+    /// Deals the next match of a series once the current one is over. Unlike
+    /// `restart`, this also works in rooms with reserved seats.
+    pub async fn rematch(&self) -> Result<u64, Rejection> {
+        self.request(|reply| Command::Rematch { reply }).await
+    }
+
+    pub async fn add_bot(&self, difficulty: BotDifficulty) -> Result<(), Rejection> {
+        self.request(|reply| Command::AddBot { difficulty, reply }).await
+    }
+
+    /// Posts already filtered `text`. `spectator` is the nickname of a
+    /// watching socket, used when `player_id` has no seat.
+    pub async fn chat(&self, player_id: String, spectator: Option<String>, text: String) -> Result<(), Rejection> {
+        self.request(|reply| Command::Chat { player_id, spectator, text, reply }).await
+    }
+
+    pub async fn emote(&self, player_id: String, emote: String) -> Result<(), Rejection> {
+        self.request(|reply| Command::Emote { player_id, emote, reply }).await
+    }
+
+    /// Returns the players `player_id` has muted after the change.
+    pub async fn mute_emotes(&self, player_id: String, target_id: String, muted: bool) -> Result<Vec<String>, Rejection> {
+        self.request(|reply| Command::MuteEmotes { player_id, target_id, muted, reply }).await
+    }
+}
+
+/// Room id to handle lookup. The lock is only held to find, add or drop a
+/// handle, never while a room is working.
+#[derive(Clone)]
+pub struct RoomRegistry {
+    rooms: Arc<RwLock<HashMap<String, RoomHandle>>>,
+    ctx: RoomContext,
+}
+
+impl RoomRegistry {
+    pub fn new(records: SharedRecords, chat: Arc<ChatConfig>) -> Self {
+        let ctx = RoomContext { records: spawn_records_writer(records), chat, lobby: broadcast::channel(LOBBY_BUFFER).0 };
+        RoomRegistry { rooms: Arc::new(RwLock::new(HashMap::new())), ctx }
+    }
+
+    /// Waits until the records reflect every room change made so far.
+    pub async fn records_settled(&self) {
+        let (reply, settled) = oneshot::channel();
+        if self.ctx.records.send(RecordsUpdate::Settled(reply)).await.is_ok() {
+            let _ = settled.await;
+        }
+    }
+
+    pub fn get(&self, room_id: &str) -> Result<RoomHandle, Rejection> {
+        self.rooms.read().unwrap().get(room_id).cloned().ok_or_else(room_closed)
+    }
+
+    /// Finds the room, or starts a task for a new one with `options` and
+    /// `rules`.
+    pub fn get_or_create(&self, room_id: &str, options: RoomOptions, rules: RuleSet) -> RoomHandle {
+        let mut rooms = self.rooms.write().unwrap();
+        if let Some(handle) = rooms.get(room_id).filter(|h| !h.is_closed()) {
+            return handle.clone();
+        }
+
+        info!("Creating new game for room {}", room_id);
+        let handle = self.spawn(empty_game(room_id, options, rules));
+        rooms.insert(room_id.to_string(), handle.clone());
+        handle
+    }
+
+    /// Starts a room already in `game`'s position, such as a loaded
+    /// scenario. Restarting the room goes back to that position.
+    pub fn open(&self, game: GameState) -> Result<RoomHandle, Rejection> {
+        let mut rooms = self.rooms.write().unwrap();
+        if rooms.get(&game.room_id).is_some_and(|h| !h.is_closed()) {
+            return Err(Rejection::new(ErrorCode::GameInProgress, "A room with this id already exists"));
+        }
+        let room_id = game.room_id.clone();
+        let handle = self.spawn(game);
+        rooms.insert(room_id, handle.clone());
+        Ok(handle)
+    }
+
+    fn spawn(&self, game: GameState) -> RoomHandle {
+        let (commands, receiver) = mpsc::channel(COMMAND_BUFFER);
+        let (published, state) = watch::channel(game.clone());
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        let actor = RoomActor {
+            events: events.clone(),
+            sent: game.clone(),
+            actions: ActionLog::default(),
+            game,
+            chat: RoomChat::default(),
+            ctx: self.ctx.clone(),
+            commands: commands.downgrade(),
+            published,
+            bot_thinking: false,
+            listed: None,
+            opening: None,
+        };
+        tokio::spawn(actor.run(receiver));
+        RoomHandle { commands, state, events }
+    }
+
+    pub fn contains(&self, room_id: &str) -> bool {
+        self.rooms.read().unwrap().contains_key(room_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.rooms.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Changes to the public rooms' listings.
+    pub fn lobby(&self) -> broadcast::Receiver<LobbyChange> {
+        self.ctx.lobby.subscribe()
+    }
+
+    /// Latest published state of every room.
+    pub fn snapshots(&self) -> Vec<GameState> {
+        self.rooms.read().unwrap().values().map(|h| h.snapshot()).collect()
+    }
+
+    /// Closes rooms idle for `timeout` seconds as of `now` and returns how
+    /// many were removed.
+    pub fn close_inactive(&self, now: u64, timeout: u64) -> usize {
+        let mut rooms = self.rooms.write().unwrap();
+        let before = rooms.len();
+        rooms.retain(|_, handle| {
+            let keep = !handle.is_closed() && now.saturating_sub(handle.state.borrow().last_update) < timeout;
+            if !keep {
+                let _ = handle.commands.try_send(Command::Close);
+            }
+            keep
+        });
+        before - rooms.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::records::Records;
+    use std::time::Duration;
+
+    fn registry() -> (RoomRegistry, SharedRecords) {
+        let records = Arc::new(tokio::sync::RwLock::new(Records::default()));
+        (RoomRegistry::new(records.clone(), Arc::new(ChatConfig::default())), records)
+    }
+
+    #[tokio::test]
+    async fn test_room_task_plays_bot_turns() {
+        let (rooms, records) = registry();
+        let room = rooms.get_or_create("r1", RoomOptions::default(), RuleSet::default());
+        room.join("p1".to_string(), "Alice".to_string(), "🙂".to_string()).await.unwrap();
+        room.add_bot(BotDifficulty::Easy).await.unwrap();
+        room.mulligan("p1".to_string(), Vec::new(), ActionGuard::default()).await.unwrap();
+
+        let mut state = room.watch();
+        tokio::time::timeout(Duration::from_secs(60), async {
+            loop {
+                let game = state.borrow_and_update().clone();
+                if game.phase == GamePhase::GameEnd {
+                    break;
+                }
+                if game.phase == GamePhase::Playing && game.current_turn == "p1" {
+                    room.act("p1".to_string(), PlayerAction::Pass, ActionGuard::default()).await.unwrap();
+                } else {
+                    state.changed().await.unwrap();
+                }
+            }
+        })
+        .await
+        .expect("bot never finished the match");
+
+        assert!(room.snapshot().winner.is_some());
+        assert!(room.snapshot().version > 3);
+        rooms.records_settled().await;
+        assert_eq!(records.read().await.player("p1").unwrap().matches, 1);
+    }
+
+    #[tokio::test]
+    async fn test_full_rooms_and_idle_cleanup() {
+        let (rooms, _) = registry();
+        let room = rooms.get_or_create("r1", RoomOptions::default(), RuleSet::default());
+        for pid in ["p1", "p2"] {
+            room.join(pid.to_string(), pid.to_string(), String::new()).await.unwrap();
+        }
+        let rejected = room.join("p3".to_string(), "p3".to_string(), String::new()).await.unwrap_err();
+        assert_eq!(rejected.code, ErrorCode::RoomFull);
+        assert_eq!(room.snapshot().phase, GamePhase::Mulligan);
+
+        assert_eq!(rooms.close_inactive(now(), 3600), 0);
+        assert_eq!(rooms.close_inactive(now() + 3600, 3600), 1);
+        assert!(rooms.is_empty());
+        assert!(rooms.get("r1").is_err());
+        assert_eq!(room.restart("p1".to_string(), ActionGuard::default()).await.unwrap_err().code, ErrorCode::RoomNotFound);
+    }
+
+    #[tokio::test]
+    async fn test_retries_and_stale_versions() {
+        let (rooms, _) = registry();
+        let room = rooms.get_or_create("r1", RoomOptions::default(), RuleSet::default());
+        for pid in ["p1", "p2"] {
+            room.join(pid.to_string(), pid.to_string(), String::new()).await.unwrap();
+        }
+        let dealt = room.snapshot().version;
+        // Reconnecting as before publishes nothing; a new nickname does.
+        room.join("p1".to_string(), "p1".to_string(), String::new()).await.unwrap();
+        assert_eq!(room.snapshot().version, dealt);
+        room.join("p2".to_string(), "P2".to_string(), String::new()).await.unwrap();
+        assert_eq!(room.snapshot().version, dealt + 1);
+        let dealt = dealt + 1;
+
+        let guard = ActionGuard { action_id: Some("a1".to_string()), expected_version: Some(dealt) };
+        let applied = room.mulligan("p1".to_string(), Vec::new(), guard.clone()).await.unwrap();
+        assert_eq!(applied, dealt + 1);
+        assert_eq!(room.mulligan("p1".to_string(), Vec::new(), guard).await, Ok(applied));
+        assert_eq!(room.snapshot().version, applied);
+
+        let stale = ActionGuard { action_id: Some("a1".to_string()), expected_version: Some(dealt) };
+        let rejected = room.mulligan("p2".to_string(), Vec::new(), stale).await.unwrap_err();
+        assert_eq!(rejected.code, ErrorCode::StaleVersion);
+        assert_eq!(room.snapshot().phase, GamePhase::Mulligan);
+    }
+
+    #[tokio::test]
+    async fn test_scenario_rooms_restart_from_their_position() {
+        let (rooms, records) = registry();
+        let text = r#"{"name": "t", "current_turn": "p1", "players": [{"id": "p1", "hand": ["H5"], "rounds_won": 1}, {"id": "p2", "board": ["S9"]}]}"#;
+        let game = crate::scenario::Scenario::parse(text, false).unwrap().to_game("r1", &mut rand::rng()).unwrap();
+        let room = rooms.open(game).unwrap();
+        assert_eq!(rooms.open(room.snapshot()).err().map(|e| e.code), Some(ErrorCode::GameInProgress));
+
+        room.act("p2".to_string(), PlayerAction::Resign, ActionGuard::default()).await.unwrap();
+        assert_eq!(room.snapshot().winner.as_deref(), Some("p1"));
+        room.restart("p1".to_string(), ActionGuard::default()).await.unwrap();
+        let restarted = room.snapshot();
+        assert_eq!((restarted.phase, restarted.players["p1"].hand.len(), restarted.players["p1"].rounds_won), (GamePhase::Playing, 1, 1));
+        rooms.records_settled().await;
+        assert!(records.read().await.player("p1").is_none());
+    }
+}
@@ -0,0 +1,278 @@
+use crate::api::public_state;
+use crate::errors::{ApiError, ErrorCode, Rejection};
+use crate::game_logic::{self, create_deck};
+use crate::game_types::{BotDifficulty, Card, GamePhase, GameState, Player, RoomOptions, RuleSet};
+use crate::notation::parse_card_code;
+use crate::room::RoomRegistry;
+use axum::extract::{Path as UrlPath, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+/// An exact position to start playing from, for lessons, puzzles and bug
+/// reports. Cards are written as card codes (`H7`, `SQ`, `JK`).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub rules: RuleSet,
+    #[serde(default = "first_round")]
+    pub round_count: u32,
+    pub current_turn: String, // Player id
+    pub players: Vec<ScenarioPlayer>, // Exactly two
+    #[serde(default)]
+    pub deck: Option<Vec<String>>, // Top card first; every card left over, shuffled, when absent
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioPlayer {
+    pub id: String,
+    #[serde(default)]
+    pub nickname: Option<String>, // The id when absent
+    #[serde(default)]
+    pub avatar: String,
+    #[serde(default)]
+    pub hand: Vec<String>,
+    #[serde(default)]
+    pub board: Vec<String>, // Spies count for the board they are on
+    #[serde(default)]
+    pub discard_pile: Vec<String>,
+    #[serde(default)]
+    pub rounds_won: u8,
+    #[serde(default)]
+    pub passed: bool,
+    #[serde(default)]
+    pub bot: Option<BotDifficulty>, // Played by the server when set
+}
+
+fn first_round() -> u32 {
+    1
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+/// Removes the first card matching `code` from `pool`.
+fn take(pool: &mut Vec<Card>, code: &str, owner: &str, place: &str) -> Result<Card, String> {
+    let (suit, rank) = parse_card_code(code).map_err(|e| format!("{}: {}", place, e))?;
+    let index = pool.iter().position(|c| c.suit == suit && c.rank == rank)
+        .ok_or_else(|| format!("{}: no {} left; the deck has fewer copies", place, code.to_uppercase()))?;
+    let mut card = pool.remove(index);
+    card.owner_id = owner.to_string();
+    Ok(card)
+}
+
+impl Scenario {
+    /// Reads a TOML file, or JSON for any other extension.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let toml = path.extension().is_some_and(|ext| ext == "toml");
+        Scenario::parse(&text, toml).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str, toml: bool) -> Result<Self, String> {
+        if toml {
+            toml::from_str(text).map_err(|e| e.to_string())
+        } else {
+            serde_json::from_str(text).map_err(|e| e.to_string())
+        }
+    }
+
+    /// The position as a game in `room_id`, in the Playing phase. Fails
+    /// unless it is one game_logic could have reached: two players, a
+    /// legal score and turn, and every card of the deck exactly once.
+    pub fn to_game<R: Rng>(&self, room_id: &str, rng: &mut R) -> Result<GameState, String> {
+        self.rules.validate()?;
+        let [first, second] = &self.players[..] else {
+            return Err(format!("A scenario needs exactly two players, not {}", self.players.len()));
+        };
+        if first.id == second.id {
+            return Err(format!("Both players are called `{}`", first.id));
+        }
+        let Some(mover) = self.players.iter().find(|p| p.id == self.current_turn) else {
+            return Err(format!("current_turn `{}` is not one of the players", self.current_turn));
+        };
+        if mover.passed {
+            return Err(format!("{} has passed and cannot be on turn", mover.id));
+        }
+        if self.round_count == 0 {
+            return Err("round_count starts at 1".to_string());
+        }
+        if let Some(p) = self.players.iter().find(|p| p.rounds_won >= self.rules.rounds_to_win) {
+            return Err(format!("{} has already won the match", p.id));
+        }
+
+        let mut pool = create_deck(&self.rules, rng);
+        let mut players = HashMap::new();
+        for p in &self.players {
+            let mut cards = |codes: &[String], place: &str| -> Result<Vec<Card>, String> {
+                codes.iter().map(|code| take(&mut pool, code, &p.id, &format!("{}'s {}", p.id, place))).collect()
+            };
+            let player = Player {
+                id: p.id.clone(),
+                nickname: p.nickname.clone().unwrap_or_else(|| p.id.clone()),
+                avatar: p.avatar.clone(),
+                hand: cards(&p.hand, "hand")?,
+                board: cards(&p.board, "board")?,
+                discard_pile: cards(&p.discard_pile, "discard pile")?,
+                current_score: 0,
+                rounds_won: p.rounds_won,
+                passed: p.passed,
+                is_bot: p.bot.is_some(),
+                bot_difficulty: p.bot.unwrap_or_default(),
+            };
+            players.insert(p.id.clone(), player);
+        }
+
+        let deck = match &self.deck {
+            Some(codes) => {
+                let deck = codes.iter().map(|code| take(&mut pool, code, "deck", "deck")).collect::<Result<Vec<_>, _>>()?;
+                if !pool.is_empty() {
+                    let mut missing: Vec<String> = pool.iter().map(crate::notation::card_code).collect();
+                    missing.sort();
+                    return Err(format!("Cards not placed anywhere: {}", missing.join(", ")));
+                }
+                deck
+            },
+            None => {
+                pool.shuffle(rng);
+                pool.into_iter().map(|c| Card { owner_id: "deck".to_string(), ..c }).collect()
+            },
+        };
+
+        let mut game = GameState {
+            room_id: room_id.to_string(),
+            phase: GamePhase::Playing,
+            players,
+            current_turn: self.current_turn.clone(),
+            round_count: self.round_count,
+            deck,
+            winner: None,
+            last_update: now(),
+            version: 0,
+            options: RoomOptions { scenario: Some(self.name.clone()), ..RoomOptions::default() },
+            rules: self.rules.clone(),
+            created_at: now(),
+            creator: None,
+        };
+        game_logic::update_scores(&mut game);
+        game_logic::check_conservation(&game)?;
+        Ok(game)
+    }
+}
+
+/// `POST /api/rooms/:id/scenario`: opens a room at the posted position.
+pub fn router(rooms: RoomRegistry) -> Router {
+    Router::new()
+        .route("/api/rooms/:id/scenario", post(open))
+        .with_state(rooms)
+}
+
+/// Takes the scenario as JSON, or as TOML with a TOML content type. Every
+/// player id gets a reserved seat, so people sit by joining with those ids.
+async fn open(State(rooms): State<RoomRegistry>, UrlPath(room_id): UrlPath<String>, headers: HeaderMap, body: String) -> Result<Response, ApiError> {
+    let toml = headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).is_some_and(|v| v.contains("toml"));
+    let invalid = |e: String| Rejection::new(ErrorCode::InvalidMessage, e);
+    let scenario = Scenario::parse(&body, toml).map_err(invalid)?;
+    let mut game = scenario.to_game(&room_id, &mut rand::rng()).map_err(invalid)?;
+    game.options = RoomOptions {
+        seats: scenario.players.iter().map(|p| p.id.clone()).collect(),
+        rated: false,
+        ..game.options
+    };
+
+    let room = rooms.open(game)?;
+    info!("Loaded scenario `{}` into room {}", scenario.name, room_id);
+    Ok((StatusCode::CREATED, Json(public_state(&room.snapshot(), None))).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_types::Rank;
+
+    const LESSON: &str = r#"
+name = "Scorch the pair"
+current_turn = "teacher"
+round_count = 2
+deck = []
+
+[[players]]
+id = "teacher"
+hand = ["SA", "H3"]
+board = ["D9"]
+discard_pile = ["CQ"]
+rounds_won = 1
+
+[[players]]
+id = "student"
+hand = ["C2", "S2"]
+board = ["C9", "H9", "S9"]
+rounds_won = 1
+"#;
+
+    /// LESSON with every other card in `student`'s discard pile.
+    fn lesson() -> Scenario {
+        let mut scenario = Scenario::parse(LESSON, true).unwrap();
+        let mut rest = create_deck(&scenario.rules, &mut rand::rng());
+        for p in &scenario.players {
+            for code in p.hand.iter().chain(&p.board).chain(&p.discard_pile) {
+                take(&mut rest, code, "", "").unwrap();
+            }
+        }
+        scenario.players[1].discard_pile = rest.iter().map(crate::notation::card_code).collect();
+        scenario
+    }
+
+    #[test]
+    fn test_scenario_loads_and_plays() {
+        let game = lesson().to_game("lesson", &mut rand::rng()).unwrap();
+        assert_eq!((game.phase.clone(), game.round_count, game.deck.len()), (GamePhase::Playing, 2, 0));
+        assert_eq!(game.players["student"].current_score, 27);
+
+        let mut game = game;
+        let ace = game.players["teacher"].hand.iter().find(|c| c.rank == Rank::Ace).unwrap().id.clone();
+        let action = crate::game_types::PlayerAction::Play { card_id: ace, target_id: None };
+        game_logic::apply_action(&mut game, &"teacher".to_string(), &action).unwrap();
+        assert_eq!(game.players["student"].current_score, 0);
+        game_logic::check_conservation(&game).unwrap();
+
+        let example = Scenario::parse(include_str!("../scenarios/scorch-lesson.toml"), true).unwrap();
+        example.to_game("example", &mut rand::rng()).unwrap();
+    }
+
+    #[test]
+    fn test_scenario_must_conserve_cards() {
+        let mut missing = lesson();
+        missing.players[1].discard_pile.pop();
+        assert!(missing.to_game("r", &mut rand::rng()).unwrap_err().starts_with("Cards not placed anywhere"));
+
+        let mut extra = lesson();
+        extra.players[0].hand.push("SA".to_string());
+        assert!(extra.to_game("r", &mut rand::rng()).unwrap_err().contains("no SA left"));
+
+        let mut shuffled = Scenario::parse(LESSON, true).unwrap();
+        shuffled.deck = None;
+        assert_eq!(shuffled.to_game("r", &mut rand::rng()).unwrap().deck.len(), 54 - 9);
+
+        let mut finished = lesson();
+        finished.players[0].rounds_won = 2;
+        assert!(finished.to_game("r", &mut rand::rng()).is_err());
+        assert!(Scenario::parse(r#"{"current_turn": "a", "players": [], "colour": 1}"#, false).is_err());
+    }
+}
@@ -0,0 +1,660 @@
+use crate::bot_api::BOT_PREFIX;
+use crate::chat::{self, ChatConfig, RateLimiter};
+use crate::errors::{Ack, ErrorCode, Rejection};
+use crate::api::SharedRecords;
+use crate::game_types::{PlayerAction, RuleSet};
+use crate::hints;
+use crate::lobby::{self, LobbyFilter};
+use crate::metrics::metrics;
+use crate::protocol::*;
+use crate::room::{state_view, RoomEvent, RoomHandle, RoomRegistry};
+use crate::tournament::Tournaments;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::AbortHandle;
+use tracing::{error, info};
+
+/// One client connection as the shared handlers see it.
+pub trait Connection: Send + Sync + 'static {
+    /// Sends an event to this connection only; false once it is gone.
+    fn emit(&self, event: &str, data: &Value) -> bool;
+}
+
+/// What every session needs from the server.
+#[derive(Clone)]
+pub struct Services {
+    pub rooms: RoomRegistry,
+    pub default_rules: RuleSet, // Rule set of rooms created by a join
+    pub chat: Arc<ChatConfig>,
+    pub tournaments: Tournaments,
+    pub records: SharedRecords,
+}
+
+/// Per-connection state and the event handlers shared by the Socket.IO and
+/// WebSocket transports: a transport only decodes `(event, data)` pairs,
+/// calls `handle`, and delivers what the session emits.
+pub struct Session<C: Connection> {
+    conn: Arc<C>,
+    services: Services,
+    chat_limiter: Mutex<RateLimiter>,
+    emote_limiter: Mutex<RateLimiter>,
+    spectating: Mutex<Option<(String, String)>>, // Room id and nickname when watching
+    seated: Mutex<Option<(String, String)>>, // Room id and player id after a join
+    listening: Mutex<Option<AbortHandle>>, // Task forwarding the current room's events
+    following: Mutex<Option<AbortHandle>>, // Task forwarding a tournament's updates
+    watching_lobby: Mutex<Option<AbortHandle>>, // Task forwarding lobby changes
+    deltas: Arc<AtomicBool>, // False if the handshake left out `state_deltas`
+}
+
+fn parse<T: DeserializeOwned>(data: Value) -> Result<T, Rejection> {
+    serde_json::from_value(data).map_err(|e| Rejection::new(ErrorCode::InvalidMessage, format!("Invalid payload: {}", e)))
+}
+
+fn to_value(data: &impl Serialize) -> Value {
+    serde_json::to_value(data).expect("payloads serialize")
+}
+
+impl<C: Connection> Session<C> {
+    pub fn new(conn: Arc<C>, services: Services) -> Self {
+        Session {
+            conn,
+            services,
+            chat_limiter: Mutex::new(RateLimiter::default()),
+            emote_limiter: Mutex::new(RateLimiter::default()),
+            spectating: Mutex::new(None),
+            seated: Mutex::new(None),
+            listening: Mutex::new(None),
+            following: Mutex::new(None),
+            watching_lobby: Mutex::new(None),
+            deltas: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    fn emit(&self, event: &str, data: &impl Serialize) {
+        self.conn.emit(event, &to_value(data));
+    }
+
+    /// Runs one client event. Failures are also sent as an `error` event
+    /// with the human-readable message.
+    pub async fn handle(&self, event: &str, data: Value) -> Ack {
+        let event = match CLIENT_EVENTS.iter().find(|e| **e == event) {
+            Some(event) => *event,
+            None => {
+                let rejection = Rejection::new(ErrorCode::InvalidMessage, format!("Unknown event `{}`", event));
+                return self.reject(rejection, None);
+            }
+        };
+
+        let _timer = metrics().time_action(event);
+        let room_id = data.get("room_id").and_then(Value::as_str).map(str::to_string);
+        match self.dispatch(event, data).await {
+            Ok(version) => Ack::ok(version),
+            Err(e) => self.reject(e, room_id.as_deref()),
+        }
+    }
+
+    fn reject(&self, rejection: Rejection, room_id: Option<&str>) -> Ack {
+        metrics().record_error(rejection.code);
+        self.emit("error", &rejection.message);
+        let version = room_id.and_then(|id| self.services.rooms.get(id).ok()).map(|room| room.snapshot().version);
+        Ack::rejected(rejection.code, version)
+    }
+
+    /// Stops forwarding room and tournament events; call when the
+    /// connection closes.
+    pub fn close(&self) {
+        for task in [&self.listening, &self.following, &self.watching_lobby] {
+            if let Some(task) = task.lock().unwrap().take() {
+                task.abort();
+            }
+        }
+    }
+
+    /// Forwards `room`'s broadcasts from `events` meant for `player_id` (or
+    /// for a spectator) to this connection, replacing any previous room.
+    fn listen(&self, room: &RoomHandle, mut events: broadcast::Receiver<RoomEvent>, player_id: Option<String>) {
+        let room = room.clone();
+        let conn = self.conn.clone();
+        let deltas = self.deltas.clone();
+        let task = tokio::spawn(async move {
+            let snapshot = || state_view(&room.snapshot(), player_id.as_deref());
+            loop {
+                let sent = match events.recv().await {
+                    Ok(event) if !event.audience.includes(player_id.as_deref()) => continue,
+                    // Clients without delta support get the whole state.
+                    Ok(event) if event.event == "game_state_delta" && !deltas.load(Ordering::Relaxed) => conn.emit("game_state_update", &snapshot()),
+                    Ok(event) => conn.emit(event.event, &event.data),
+                    // Too far behind for deltas to help: start over.
+                    Err(RecvError::Lagged(_)) => conn.emit("game_state_update", &snapshot()),
+                    Err(RecvError::Closed) => break,
+                };
+                if !sent {
+                    break;
+                }
+            }
+        });
+        if let Some(previous) = self.listening.lock().unwrap().replace(task.abort_handle()) {
+            previous.abort();
+        }
+    }
+
+    /// Sends tournament `id` as it stands and after every change,
+    /// replacing any tournament followed before.
+    fn follow_tournament(&self, id: String) -> Result<(), Rejection> {
+        let mut updates = self.services.tournaments.subscribe();
+        self.emit("tournament_update", &self.services.tournaments.get(&id)?);
+
+        let tournaments = self.services.tournaments.clone();
+        let conn = self.conn.clone();
+        let task = tokio::spawn(async move {
+            loop {
+                let update = match updates.recv().await {
+                    Ok(update) if update.tournament.id == id => update,
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(_)) => match tournaments.get(&id) {
+                        Ok(update) => update,
+                        Err(_) => break,
+                    },
+                    Err(RecvError::Closed) => break,
+                };
+                if !conn.emit("tournament_update", &to_value(&update)) {
+                    break;
+                }
+            }
+        });
+        if let Some(previous) = self.following.lock().unwrap().replace(task.abort_handle()) {
+            previous.abort();
+        }
+        Ok(())
+    }
+
+    /// Sends the first page of rooms matching `filter`, then every change
+    /// to them, replacing any filter watched before.
+    fn watch_lobby(&self, filter: LobbyFilter) {
+        let rooms = self.services.rooms.clone();
+        let mut changes = rooms.lobby();
+        self.emit("rooms_page", &filter.page(&rooms.snapshots()));
+
+        let conn = self.conn.clone();
+        let task = tokio::spawn(async move {
+            loop {
+                let sent = match changes.recv().await {
+                    Ok(change) => conn.emit("lobby_update", &to_value(&change.for_filter(&filter))),
+                    Err(RecvError::Lagged(_)) => conn.emit("rooms_page", &to_value(&filter.page(&rooms.snapshots()))),
+                    Err(RecvError::Closed) => break,
+                };
+                if !sent {
+                    break;
+                }
+            }
+        });
+        if let Some(previous) = self.watching_lobby.lock().unwrap().replace(task.abort_handle()) {
+            previous.abort();
+        }
+    }
+
+    fn room(&self, room_id: &str) -> Result<RoomHandle, Rejection> {
+        self.services.rooms.get(room_id)
+    }
+
+    /// Returns the room's state version for acknowledgements where one
+    /// applies.
+    async fn dispatch(&self, event: &'static str, data: Value) -> Result<Option<u64>, Rejection> {
+        let player_id = data.get("player_id").and_then(Value::as_str);
+        if event != "get_profile" && player_id.is_some_and(|id| id.starts_with(BOT_PREFIX)) {
+            return Err(Rejection::new(ErrorCode::Unauthorized, "Bot account seats are only played through /api/bot"));
+        }
+
+        match event {
+            "handshake" => self.handshake(parse(data)?).map(|_| None),
+            "ping" => {
+                self.emit("pong", &"pong");
+                Ok(None)
+            },
+            "join_game" => self.join(parse(data)?).await.map(Some),
+            "spectate" => self.spectate(parse(data)?).await.map(Some),
+            "resync" => {
+                let payload: ResyncPayload = parse(data)?;
+                let state = self.room(&payload.room_id)?.snapshot();
+                let viewer = match self.seated.lock().unwrap().as_ref() {
+                    Some((room_id, player_id)) if *room_id == payload.room_id => Some(player_id.clone()),
+                    _ => None,
+                };
+                self.emit("game_state_update", &state_view(&state, viewer.as_deref()));
+                Ok(Some(state.version))
+            },
+            "mulligan" => {
+                let payload: MulliganPayload = parse(data)?;
+                let room = self.room(&payload.room_id)?;
+                room.mulligan(payload.player_id, payload.card_ids, payload.guard).await.map(Some)
+            },
+            "play_card" => {
+                let payload: PlayCardPayload = parse(data)?;
+                let room = self.room(&payload.room_id)?;
+                let action = PlayerAction::Play { card_id: payload.card_id, target_id: payload.target_id };
+                room.act(payload.player_id, action, payload.guard).await.map(Some)
+            },
+            "pass" => {
+                let payload: PassPayload = parse(data)?;
+                let room = self.room(&payload.room_id)?;
+                room.act(payload.player_id, PlayerAction::Pass, payload.guard).await.map(Some)
+            },
+            "resign" => {
+                let payload: ResignPayload = parse(data)?;
+                info!("Player {} resigning in room {}", payload.player_id, payload.room_id);
+                let room = self.room(&payload.room_id)?;
+                room.act(payload.player_id, PlayerAction::Resign, payload.guard).await.map(Some)
+            },
+            "restart_game" => {
+                let payload: RestartGamePayload = parse(data)?;
+                info!("Player {} requesting restart for room {}", payload.player_id, payload.room_id);
+                let room = self.room(&payload.room_id)?;
+                room.restart(payload.player_id, payload.guard).await.map(Some)
+            },
+            "add_bot" => {
+                let payload: AddBotPayload = parse(data)?;
+                let room = self.room(&payload.room_id)?;
+                room.add_bot(payload.difficulty).await?;
+                Ok(Some(room.snapshot().version))
+            },
+            "request_hint" => self.request_hint(parse(data)?).await,
+            "list_rooms" => {
+                // Without a filter, answer as protocol 1 did: every public
+                // room as a bare list.
+                let games = self.services.rooms.snapshots();
+                match parse::<Option<LobbyFilter>>(data)? {
+                    Some(filter) => self.emit("rooms_page", &filter.page(&games)),
+                    None => {
+                        let all = LobbyFilter::default();
+                        let rooms: Vec<lobby::RoomInfo> = games.iter().map(lobby::room_info).filter(|r| all.matches(r)).collect();
+                        self.emit("rooms_list", &rooms);
+                    },
+                }
+                Ok(None)
+            },
+            "watch_lobby" => {
+                self.watch_lobby(parse::<Option<LobbyFilter>>(data)?.unwrap_or_default());
+                Ok(None)
+            },
+            "chat_message" => self.chat(parse(data)?).await.map(|_| None),
+            "emote" => self.emote(parse(data)?).await.map(|_| None),
+            "mute_emotes" => {
+                let payload: MuteEmotesPayload = parse(data)?;
+                let room = self.room(&payload.room_id)?;
+                room.mute_emotes(payload.player_id, payload.target_id, payload.muted).await?;
+                Ok(None)
+            },
+            "watch_tournament" => {
+                let payload: WatchTournamentPayload = parse(data)?;
+                self.follow_tournament(payload.tournament_id).map(|_| None)
+            },
+            "get_profile" => {
+                let payload: GetProfilePayload = parse(data)?;
+                self.services.rooms.records_settled().await;
+                let profile = self.services.records.read().await.profile(&payload.player_id).cloned()
+                    .ok_or_else(|| Rejection::new(ErrorCode::PlayerNotFound, "No completed matches for this player"))?;
+                self.emit("profile", &profile);
+                Ok(None)
+            },
+            _ => unreachable!("`handle` only dispatches CLIENT_EVENTS"),
+        }
+    }
+
+    /// Checks the client's protocol version and settles on the
+    /// capabilities both sides know, answering with `hello`.
+    fn handshake(&self, data: HandshakePayload) -> Result<(), Rejection> {
+        if !supports(data.protocol_version) {
+            return Err(Rejection::new(ErrorCode::IncompatibleClient, format!(
+                "This client speaks protocol {} but the server supports {} to {}; please update the client",
+                data.protocol_version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+            )));
+        }
+
+        let shared: Vec<String> = CAPABILITIES.iter()
+            .filter(|c| data.capabilities.iter().any(|d| d == *c))
+            .map(|c| c.to_string())
+            .collect();
+        info!("Client {} on protocol {} with {:?}", data.client.as_deref().unwrap_or("(unnamed)"), data.protocol_version, shared);
+        self.deltas.store(shared.iter().any(|c| c == "state_deltas"), Ordering::Relaxed);
+        self.emit("hello", &Hello::new(shared));
+        Ok(())
+    }
+
+    async fn join(&self, data: JoinGamePayload) -> Result<u64, Rejection> {
+        info!("Player {} joining room {}", data.player_id, data.room_id);
+        *self.spectating.lock().unwrap() = None;
+
+        let options = data.options.unwrap_or_default().requested();
+        let room = self.services.rooms.get_or_create(&data.room_id, options, self.services.default_rules.clone());
+        // Subscribe before joining so no change after the welcome snapshot
+        // is missed, but forward only once the join has succeeded.
+        let events = room.subscribe();
+        let player_id = data.player_id.clone();
+        let welcome = room.join(data.player_id, data.nickname, data.avatar).await?;
+        // The room sends deltas from here on; start from a full snapshot
+        // and catch up on the conversation.
+        self.emit("game_state_update", &state_view(&welcome.state, Some(&player_id)));
+        self.emit("chat_history", &welcome.chat_history);
+        self.emit("emote_mutes", &welcome.emote_mutes);
+        *self.seated.lock().unwrap() = Some((data.room_id, player_id.clone()));
+        self.listen(&room, events, Some(player_id));
+        Ok(welcome.state.version)
+    }
+
+    async fn spectate(&self, data: SpectatePayload) -> Result<u64, Rejection> {
+        let room = self.room(&data.room_id)?;
+        info!("Spectating room {}", data.room_id);
+        self.listen(&room, room.subscribe(), None);
+        *self.spectating.lock().unwrap() = Some((data.room_id, data.nickname));
+        *self.seated.lock().unwrap() = None;
+
+        let welcome = room.spectate().await?;
+        self.emit("game_state_update", &state_view(&welcome.state, None));
+        self.emit("chat_history", &welcome.chat_history);
+        Ok(welcome.state.version)
+    }
+
+    async fn chat(&self, data: ChatPayload) -> Result<(), Rejection> {
+        let config = &self.services.chat;
+        let room = self.room(&data.room_id)?;
+        let text = chat::prepare_text(&data.text, config).map_err(|e| Rejection::new(ErrorCode::InvalidMessage, e))?;
+        if !self.chat_limiter.lock().unwrap().try_send(config.rate_limit, config.rate_window, Instant::now()) {
+            return Err(Rejection::new(ErrorCode::RateLimited, "You are sending messages too quickly"));
+        }
+
+        let spectator = match self.spectating.lock().unwrap().as_ref() {
+            Some((room_id, nickname)) if *room_id == data.room_id => Some(nickname.clone()),
+            _ => None,
+        };
+        room.chat(data.player_id, spectator, text).await
+    }
+
+    async fn emote(&self, data: EmotePayload) -> Result<(), Rejection> {
+        chat::validate_emote(&data.emote).map_err(|e| Rejection::new(ErrorCode::UnknownEmote, e))?;
+        let room = self.room(&data.room_id)?;
+        if !self.emote_limiter.lock().unwrap().try_send(1, self.services.chat.emote_cooldown, Instant::now()) {
+            return Err(Rejection::new(ErrorCode::RateLimited, "Emote is on cooldown"));
+        }
+        room.emote(data.player_id, data.emote).await
+    }
+
+    async fn request_hint(&self, data: RequestHintPayload) -> Result<Option<u64>, Rejection> {
+        let snapshot = self.room(&data.room_id)?.snapshot();
+        if !snapshot.options.hints_enabled {
+            return Err(Rejection::new(ErrorCode::HintsDisabled, "Hints are disabled in this room"));
+        }
+        // Hints come from the mover's hand, so only the mover's own seat
+        // may ask for them.
+        let seated = self.seated.lock().unwrap().as_ref().is_some_and(|(room_id, player_id)| *room_id == data.room_id && *player_id == data.player_id);
+        if !seated {
+            return Err(Rejection::new(ErrorCode::NotInRoom, "You are not seated as this player"));
+        }
+        if snapshot.current_turn != data.player_id {
+            return Err(Rejection::new(ErrorCode::NotYourTurn, "Not your turn"));
+        }
+
+        let version = snapshot.version;
+        let player_id = data.player_id.clone();
+        match tokio::task::spawn_blocking(move || hints::rank_actions(&snapshot, &player_id, &mut rand::rng())).await {
+            Ok(hints) => self.emit("hints", &hints),
+            Err(e) => error!("Hint search failed in room {}: {}", data.room_id, e),
+        }
+        Ok(Some(version))
+    }
+}
+
+impl<C: Connection> Drop for Session<C> {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::records::Records;
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<(String, Value)>>);
+
+    impl Connection for Recorder {
+        fn emit(&self, event: &str, data: &Value) -> bool {
+            self.0.lock().unwrap().push((event.to_string(), data.clone()));
+            true
+        }
+    }
+
+    impl Recorder {
+        fn events(&self) -> Vec<String> {
+            self.0.lock().unwrap().iter().map(|(event, _)| event.clone()).collect()
+        }
+    }
+
+    fn services() -> Services {
+        let records = Arc::new(tokio::sync::RwLock::new(Records::default()));
+        let chat = Arc::new(ChatConfig::default());
+        let rooms = RoomRegistry::new(records.clone(), chat.clone());
+        Services { tournaments: Tournaments::new(rooms.clone(), records.clone(), RuleSet::default()), rooms, default_rules: RuleSet::default(), chat, records }
+    }
+
+    fn join(player_id: &str) -> Value {
+        json!({ "room_id": "r1", "player_id": player_id, "nickname": player_id, "avatar": "a" })
+    }
+
+    #[tokio::test]
+    async fn test_sessions_share_room_events() {
+        let services = services();
+        let (alice, bob) = (Arc::new(Recorder::default()), Arc::new(Recorder::default()));
+        let alice_session = Session::new(alice.clone(), services.clone());
+        let bob_session = Session::new(bob.clone(), services.clone());
+
+        let ack = alice_session.handle("join_game", join("alice")).await;
+        assert!(ack.ok);
+        // The join's own delta follows the snapshot; clients drop deltas
+        // it covers.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(alice.events()[..3], ["game_state_update", "chat_history", "emote_mutes"]);
+        assert!(bob_session.handle("join_game", join("bob")).await.ok);
+
+        // Alice hears about Bob's join as a delta; only Alice sees her own
+        // mute list change.
+        assert!(alice_session.handle("mute_emotes", json!({ "room_id": "r1", "player_id": "alice", "target_id": "bob", "muted": true })).await.ok);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(alice.events().contains(&"game_state_delta".to_string()));
+        assert_eq!(alice.events().iter().filter(|e| *e == "emote_mutes").count(), 2);
+        assert_eq!(bob.events().iter().filter(|e| *e == "emote_mutes").count(), 1);
+
+        let ack = bob_session.handle("pass", json!({ "room_id": "nowhere", "player_id": "bob" })).await;
+        assert_eq!(ack.error_code, Some(ErrorCode::RoomNotFound));
+        assert_eq!(bob.events().last().unwrap(), "error");
+        // A rejected join leaves Carol's connection out of the room.
+        let carol = Arc::new(Recorder::default());
+        let carol_session = Session::new(carol.clone(), services.clone());
+        assert_eq!(carol_session.handle("join_game", join("carol")).await.error_code, Some(ErrorCode::RoomFull));
+        assert!(carol_session.listening.lock().unwrap().is_none());
+        let ack = bob_session.handle("teleport", Value::Null).await;
+        assert_eq!(ack.error_code, Some(ErrorCode::InvalidMessage));
+        let ack = bob_session.handle("pass", json!({ "room_id": "r1", "player_id": "bot:alpha" })).await;
+        assert_eq!(ack.error_code, Some(ErrorCode::Unauthorized));
+    }
+
+    #[tokio::test]
+    async fn test_handshake_negotiates_capabilities() {
+        let services = services();
+        let old = Arc::new(Recorder::default());
+        let session = Session::new(old.clone(), services.clone());
+
+        let ack = session.handle("handshake", json!({ "protocol_version": PROTOCOL_VERSION + 1 })).await;
+        assert_eq!(ack.error_code, Some(ErrorCode::IncompatibleClient));
+
+        // A client without `state_deltas` gets whole states instead.
+        assert!(session.handle("handshake", json!({ "protocol_version": PROTOCOL_VERSION, "capabilities": ["acks", "teleport"] })).await.ok);
+        let (event, hello) = old.0.lock().unwrap().last().cloned().unwrap();
+        assert_eq!(event, "hello");
+        assert_eq!(hello["capabilities"], json!(["acks"]));
+        assert!(session.handle("join_game", join("alice")).await.ok);
+        let bob = Session::new(Arc::new(Recorder::default()), services);
+        assert!(bob.handle("join_game", join("bob")).await.ok);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!old.events().contains(&"game_state_delta".to_string()));
+        assert!(old.events().iter().filter(|e| *e == "game_state_update").count() >= 2);
+    }
+
+    #[tokio::test]
+    async fn test_lobby_watchers_see_rooms_fill_up() {
+        let services = services();
+        let watcher = Arc::new(Recorder::default());
+        let lobby = Session::new(watcher.clone(), services.clone());
+        assert!(lobby.handle("watch_lobby", json!({ "joinable": true })).await.ok);
+        assert_eq!(watcher.events(), ["rooms_page"]);
+
+        let alice = Session::new(Arc::new(Recorder::default()), services.clone());
+        assert!(alice.handle("join_game", join("alice")).await.ok);
+        let bob = Session::new(Arc::new(Recorder::default()), services.clone());
+        assert!(bob.handle("join_game", join("bob")).await.ok);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // The room shows up with a free seat, then drops out once full.
+        let updates: Vec<Value> = watcher.0.lock().unwrap().iter().filter(|(e, _)| e == "lobby_update").map(|(_, d)| d.clone()).collect();
+        assert_eq!(updates[0]["change"], "updated");
+        assert_eq!(updates[0]["room"]["creator"], "alice");
+        assert_eq!(updates.last().unwrap(), &json!({ "change": "removed", "id": "r1" }));
+
+        let private = json!({ "room_id": "hidden", "player_id": "carol", "nickname": "carol", "avatar": "a", "options": { "hints_enabled": true, "visibility": "private" } });
+        assert!(alice.handle("join_game", private).await.ok);
+        assert!(lobby.handle("list_rooms", Value::Null).await.ok);
+        let (_, rooms) = watcher.0.lock().unwrap().last().cloned().unwrap();
+        assert_eq!(rooms.as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_profiles_after_a_resignation() {
+        let services = services();
+        let (alice, bob) = (Arc::new(Recorder::default()), Arc::new(Recorder::default()));
+        let alice_session = Session::new(alice.clone(), services.clone());
+        let bob_session = Session::new(bob.clone(), services);
+        assert!(alice_session.handle("join_game", join("alice")).await.ok);
+        assert!(bob_session.handle("join_game", join("bob")).await.ok);
+        let ack = alice_session.handle("get_profile", json!({ "player_id": "alice" })).await;
+        assert_eq!(ack.error_code, Some(ErrorCode::PlayerNotFound));
+
+        for (session, id) in [(&alice_session, "alice"), (&bob_session, "bob")] {
+            assert!(session.handle("mulligan", json!({ "room_id": "r1", "player_id": id, "card_ids": [] })).await.ok);
+        }
+        assert!(bob_session.handle("resign", json!({ "room_id": "r1", "player_id": "bob" })).await.ok);
+        assert!(alice_session.handle("get_profile", json!({ "player_id": "alice" })).await.ok);
+        let (event, profile) = alice.0.lock().unwrap().last().cloned().unwrap();
+        assert_eq!(event, "profile");
+        assert_eq!((profile["matches_won"].as_u64(), profile["current_streak"].as_i64()), (Some(1), Some(1)));
+    }
+
+    #[tokio::test]
+    async fn test_hints_follow_hints_enabled() {
+        let services = services();
+        // The first room takes the default options, which are rated.
+        for (room_id, options, allowed) in [("default", Value::Null, true), ("no-hints", json!({ "hints_enabled": false }), false)] {
+            let alice = Session::new(Arc::new(Recorder::default()), services.clone());
+            let bob = Session::new(Arc::new(Recorder::default()), services.clone());
+            for (session, id) in [(&alice, "alice"), (&bob, "bob")] {
+                let join = json!({ "room_id": room_id, "player_id": id, "nickname": id, "avatar": "a", "options": options });
+                assert!(session.handle("join_game", join).await.ok);
+            }
+            for (session, id) in [(&alice, "alice"), (&bob, "bob")] {
+                assert!(session.handle("mulligan", json!({ "room_id": room_id, "player_id": id, "card_ids": [] })).await.ok);
+            }
+
+            let snapshot = services.rooms.get(room_id).unwrap().snapshot();
+            assert!(snapshot.options.rated);
+            let mover = if snapshot.current_turn == "alice" { &alice } else { &bob };
+            let ack = mover.handle("request_hint", json!({ "room_id": room_id, "player_id": snapshot.current_turn })).await;
+            assert_eq!(ack.ok, allowed);
+            if !allowed {
+                assert_eq!(ack.error_code, Some(ErrorCode::HintsDisabled));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_clients_cannot_reserve_seats_or_claim_a_scenario() {
+        let services = services();
+        let alice = Session::new(Arc::new(Recorder::default()), services.clone());
+        let join = json!({ "room_id": "r1", "player_id": "alice", "nickname": "alice", "avatar": "a", "options": { "seats": ["alice", "mallory"], "scenario": "unrecorded" } });
+        assert!(alice.handle("join_game", join).await.ok);
+
+        let options = services.rooms.get("r1").unwrap().snapshot().options;
+        assert!(options.seats.is_empty() && options.scenario.is_none());
+        let bob = Session::new(Arc::new(Recorder::default()), services.clone());
+        assert!(bob.handle("join_game", json!({ "room_id": "r1", "player_id": "bob", "nickname": "bob", "avatar": "a" })).await.ok);
+    }
+
+    #[tokio::test]
+    async fn test_hints_only_for_the_seated_mover() {
+        let services = services();
+        let alice = Session::new(Arc::new(Recorder::default()), services.clone());
+        let bob = Session::new(Arc::new(Recorder::default()), services.clone());
+        for (session, id) in [(&alice, "alice"), (&bob, "bob")] {
+            assert!(session.handle("join_game", join(id)).await.ok);
+        }
+        for (session, id) in [(&alice, "alice"), (&bob, "bob")] {
+            assert!(session.handle("mulligan", json!({ "room_id": "r1", "player_id": id, "card_ids": [] })).await.ok);
+        }
+        let spectator = Session::new(Arc::new(Recorder::default()), services.clone());
+        assert!(spectator.handle("spectate", json!({ "room_id": "r1", "nickname": "eve" })).await.ok);
+
+        let mover = services.rooms.get("r1").unwrap().snapshot().current_turn;
+        let (mover_session, other) = if mover == "alice" { (&alice, &bob) } else { (&bob, &alice) };
+        let hint = json!({ "room_id": "r1", "player_id": mover });
+        assert_eq!(spectator.handle("request_hint", hint.clone()).await.error_code, Some(ErrorCode::NotInRoom));
+        assert_eq!(other.handle("request_hint", hint.clone()).await.error_code, Some(ErrorCode::NotInRoom));
+        assert!(mover_session.handle("request_hint", hint).await.ok);
+    }
+
+    #[tokio::test]
+    async fn test_views_hide_other_hands() {
+        let services = services();
+        let alice_seen = Arc::new(Recorder::default());
+        let alice = Session::new(alice_seen.clone(), services.clone());
+        let bob = Session::new(Arc::new(Recorder::default()), services.clone());
+        assert!(alice.handle("join_game", join("alice")).await.ok);
+        assert!(bob.handle("join_game", join("bob")).await.ok);
+        let watcher = Arc::new(Recorder::default());
+        let spectator = Session::new(watcher.clone(), services.clone());
+        assert!(spectator.handle("spectate", json!({ "room_id": "r1", "nickname": "eve" })).await.ok);
+        assert!(alice.handle("mulligan", json!({ "room_id": "r1", "player_id": "alice", "card_ids": [] })).await.ok);
+        assert!(spectator.handle("resync", json!({ "room_id": "r1" })).await.ok);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // The snapshot and its deltas add up to the resynced view, and
+        // none of them holds a hand or the deck.
+        let seen = watcher.0.lock().unwrap().clone();
+        let states: Vec<&Value> = seen.iter().filter(|(e, _)| e == "game_state_update").map(|(_, d)| d).collect();
+        assert_eq!(states.len(), 2);
+        let mut view = states[0].clone();
+        for (_, delta) in seen.iter().filter(|(e, _)| e == "game_state_delta") {
+            crate::delta::apply(&mut view, &parse::<crate::delta::StateDelta>(delta.clone()).unwrap().patch).unwrap();
+        }
+        assert_eq!(&view, states[1]);
+        assert!(view.get("deck").is_none());
+        assert!(view["players"]["alice"]["hand"].is_null());
+        assert_eq!(view["players"]["alice"]["hand_size"], 10);
+
+        // Players keep their own hand, patched by their own deltas.
+        let seen = alice_seen.0.lock().unwrap().clone();
+        let (_, snapshot) = seen.iter().find(|(e, _)| e == "game_state_update").unwrap();
+        let mut view = snapshot.clone();
+        for (_, delta) in seen.iter().filter(|(e, _)| e == "game_state_delta") {
+            crate::delta::apply(&mut view, &parse::<crate::delta::StateDelta>(delta.clone()).unwrap().patch).unwrap();
+        }
+        let room = services.rooms.get("r1").unwrap().snapshot();
+        assert_eq!(view, state_view(&room, Some("alice")));
+        assert_eq!(view["players"]["alice"]["hand"].as_array().unwrap().len(), 10);
+        assert!(view["players"]["bob"]["hand"].is_null());
+    }
+}
@@ -0,0 +1,326 @@
+use crate::bot;
+use crate::game_logic;
+use crate::game_types::{BotDifficulty, GamePhase, GameState, PlayerAction, RuleSet};
+use crate::ismcts::SearchBudget;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+const SEATS: [&str; 2] = ["bot-a", "bot-b"];
+
+/// Everything needed to reproduce a batch of bot-vs-bot matches.
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    pub matches: u32,
+    pub seed: u64,
+    pub rules: RuleSet,
+    pub bots: [BotDifficulty; 2],
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RoundPlayStats {
+    pub round: u32,
+    pub played: u32,
+    pub round_wins: u32,
+    pub round_win_rate: f64,
+}
+
+/// Per-card results. A "win" counts a drawn match as half, and "held" /
+/// "played" are counted at most once per player per match.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CardStats {
+    pub card: String,
+    pub held: u32,
+    pub held_win_rate: f64,
+    pub played: u32,
+    pub played_win_rate: f64,
+    pub times_played: u32,
+    pub by_round: Vec<RoundPlayStats>,
+    #[serde(skip)]
+    held_score: f64,
+    #[serde(skip)]
+    played_score: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulationReport {
+    pub matches: u32,
+    pub seed: u64,
+    pub rules: RuleSet,
+    pub bots: [BotDifficulty; 2],
+    pub seat_wins: [u32; 2],
+    pub draws: u32,
+    pub first_player_wins: u32,
+    pub first_player_win_rate: f64,
+    pub average_rounds: f64,
+    pub average_actions: f64,
+    pub cards: Vec<CardStats>,
+}
+
+/// What happened in one match, as far as the statistics care.
+#[derive(Debug, Clone)]
+pub struct MatchLog {
+    pub winner: Option<usize>, // Seat index, None for a draw
+    pub first_player: usize,
+    pub rounds: u32,
+    pub actions: u32,
+    held: BTreeSet<(usize, String)>,
+    played: BTreeSet<(usize, String)>,
+    plays: Vec<(usize, String, u32, bool)>, // Seat, card, round, won that round
+}
+
+/// Search bots in simulations are bounded by iterations only, so a seed
+/// reproduces the same matches regardless of machine speed.
+fn simulation_budget(difficulty: BotDifficulty) -> Option<SearchBudget> {
+    bot::search_budget(difficulty).map(|b| SearchBudget { time_limit: Duration::MAX, ..b })
+}
+
+pub fn run_match(rules: &RuleSet, bots: [BotDifficulty; 2], seed: u64) -> MatchLog {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let ids: Vec<String> = SEATS.iter().map(|s| s.to_string()).collect();
+    let mut game = game_logic::init_game_with("simulation".to_string(), ids, rules.clone(), &mut rng);
+    for (seat, id) in SEATS.iter().enumerate() {
+        let player = game.players.get_mut(*id).unwrap();
+        player.is_bot = true;
+        player.bot_difficulty = bots[seat];
+    }
+
+    bot::play_pending_mulligans(&mut game);
+
+    let mut log = MatchLog {
+        winner: None,
+        first_player: seat_of(&game.current_turn),
+        rounds: 0,
+        actions: 0,
+        held: BTreeSet::new(),
+        played: BTreeSet::new(),
+        plays: Vec::new(),
+    };
+    record_hands(&game, &mut log.held);
+
+    let mut round_plays: Vec<(usize, String)> = Vec::new();
+    while game.phase == GamePhase::Playing {
+        let mover = game.current_turn.clone();
+        let seat = seat_of(&mover);
+        let action = bot::choose_action_with_budget(&game, &mover, simulation_budget(bots[seat]), &mut rng);
+
+        if let PlayerAction::Play { card_id, .. } = &action {
+            if let Some(card) = game.players[&mover].hand.iter().find(|c| &c.id == card_id) {
+                round_plays.push((seat, card.rank.label()));
+                log.played.insert((seat, card.rank.label()));
+            }
+        }
+
+        let round = game.round_count;
+        let wins_before = rounds_won(&game);
+        if game_logic::apply_action(&mut game, &mover, &action).is_err() {
+            let _ = game_logic::pass_turn(&mut game, &mover);
+        }
+        log.actions += 1;
+        record_hands(&game, &mut log.held);
+
+        let wins_after = rounds_won(&game);
+        if wins_after != wins_before {
+            for (seat, card) in round_plays.drain(..) {
+                log.plays.push((seat, card, round, wins_after[seat] > wins_before[seat]));
+            }
+            log.rounds += 1;
+        }
+    }
+
+    log.winner = match game.winner.as_deref() {
+        Some("Draw") | None => None,
+        Some(id) => Some(seat_of(id)),
+    };
+    log
+}
+
+pub fn run(config: &SimulationConfig) -> SimulationReport {
+    let logs: Vec<MatchLog> = (0..config.matches)
+        .map(|i| run_match(&config.rules, config.bots, config.seed.wrapping_add(i as u64)))
+        .collect();
+    summarize(config, &logs)
+}
+
+pub fn summarize(config: &SimulationConfig, logs: &[MatchLog]) -> SimulationReport {
+    let mut cards: BTreeMap<String, CardStats> = BTreeMap::new();
+    let mut seat_wins = [0; 2];
+    let mut draws = 0;
+    let mut first_player_wins = 0;
+    let mut first_player_score = 0.0;
+    let (mut total_rounds, mut total_actions) = (0u64, 0u64);
+
+    for log in logs {
+        let score = |seat: usize| match log.winner {
+            Some(w) if w == seat => 1.0,
+            Some(_) => 0.0,
+            None => 0.5,
+        };
+
+        match log.winner {
+            Some(seat) => seat_wins[seat] += 1,
+            None => draws += 1,
+        }
+        if log.winner == Some(log.first_player) {
+            first_player_wins += 1;
+        }
+        first_player_score += score(log.first_player);
+        total_rounds += log.rounds as u64;
+        total_actions += log.actions as u64;
+
+        for (seat, card) in &log.held {
+            let stats = card_entry(&mut cards, card);
+            stats.held += 1;
+            stats.held_score += score(*seat);
+        }
+        for (seat, card) in &log.played {
+            let stats = card_entry(&mut cards, card);
+            stats.played += 1;
+            stats.played_score += score(*seat);
+        }
+        for (_, card, round, won) in &log.plays {
+            let stats = card_entry(&mut cards, card);
+            stats.times_played += 1;
+            let idx = (*round as usize).saturating_sub(1);
+            while stats.by_round.len() <= idx {
+                let round = stats.by_round.len() as u32 + 1;
+                stats.by_round.push(RoundPlayStats { round, ..Default::default() });
+            }
+            stats.by_round[idx].played += 1;
+            if *won {
+                stats.by_round[idx].round_wins += 1;
+            }
+        }
+    }
+
+    let matches = logs.len().max(1) as f64;
+    let cards = cards.into_values()
+        .map(|mut c| {
+            c.held_win_rate = ratio(c.held_score, c.held);
+            c.played_win_rate = ratio(c.played_score, c.played);
+            for r in &mut c.by_round {
+                r.round_win_rate = ratio(r.round_wins as f64, r.played);
+            }
+            c
+        })
+        .collect();
+
+    SimulationReport {
+        matches: logs.len() as u32,
+        seed: config.seed,
+        rules: config.rules.clone(),
+        bots: config.bots,
+        seat_wins,
+        draws,
+        first_player_wins,
+        first_player_win_rate: first_player_score / matches,
+        average_rounds: total_rounds as f64 / matches,
+        average_actions: total_actions as f64 / matches,
+        cards,
+    }
+}
+
+impl SimulationReport {
+    /// The whole-batch figures as a one-row CSV table.
+    pub fn summary_csv(&self) -> String {
+        format!(
+            "matches,seed,bot_a,bot_b,seat_a_wins,seat_b_wins,draws,first_player_wins,first_player_win_rate,average_rounds,average_actions\n\
+             {},{},{:?},{:?},{},{},{},{},{:.4},{:.4},{:.4}\n",
+            self.matches, self.seed, self.bots[0], self.bots[1], self.seat_wins[0], self.seat_wins[1], self.draws,
+            self.first_player_wins, self.first_player_win_rate, self.average_rounds, self.average_actions,
+        )
+    }
+
+    /// The summary and the per-card table, separated by a blank line.
+    pub fn to_csv(&self) -> String {
+        format!("{}\n{}", self.summary_csv(), self.cards_csv())
+    }
+
+    /// Per-card table as CSV, one row per card and round column group.
+    pub fn cards_csv(&self) -> String {
+        let rounds = self.cards.iter().map(|c| c.by_round.len()).max().unwrap_or(0);
+        let mut out = String::from("card,held,held_win_rate,played,played_win_rate,times_played");
+        for r in 1..=rounds {
+            out.push_str(&format!(",played_r{r},round_win_rate_r{r}"));
+        }
+        out.push('\n');
+
+        for c in &self.cards {
+            out.push_str(&format!(
+                "{},{},{:.4},{},{:.4},{}",
+                c.card, c.held, c.held_win_rate, c.played, c.played_win_rate, c.times_played
+            ));
+            for r in 0..rounds {
+                match c.by_round.get(r) {
+                    Some(stats) => out.push_str(&format!(",{},{:.4}", stats.played, stats.round_win_rate)),
+                    None => out.push_str(",0,0.0000"),
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn card_entry<'a>(cards: &'a mut BTreeMap<String, CardStats>, card: &str) -> &'a mut CardStats {
+    cards.entry(card.to_string()).or_insert_with(|| CardStats { card: card.to_string(), ..Default::default() })
+}
+
+fn ratio(score: f64, count: u32) -> f64 {
+    if count == 0 { 0.0 } else { score / count as f64 }
+}
+
+fn seat_of(player_id: &str) -> usize {
+    SEATS.iter().position(|s| *s == player_id).unwrap_or(0)
+}
+
+fn rounds_won(game: &GameState) -> [u8; 2] {
+    [game.players[SEATS[0]].rounds_won, game.players[SEATS[1]].rounds_won]
+}
+
+fn record_hands(game: &GameState, held: &mut BTreeSet<(usize, String)>) {
+    for (seat, id) in SEATS.iter().enumerate() {
+        for card in &game.players[*id].hand {
+            held.insert((seat, card.rank.label()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(matches: u32) -> SimulationConfig {
+        SimulationConfig {
+            matches,
+            seed: 7,
+            rules: RuleSet::default(),
+            bots: [BotDifficulty::Easy, BotDifficulty::Easy],
+        }
+    }
+
+    #[test]
+    fn test_seeded_runs_are_reproducible() {
+        let a = run(&config(10));
+        let b = run(&config(10));
+        assert_eq!(a.seat_wins, b.seat_wins);
+        assert_eq!(a.average_actions, b.average_actions);
+        assert_eq!(a.cards_csv(), b.cards_csv());
+    }
+
+    #[test]
+    fn test_report_counts_every_match() {
+        let report = run(&config(10));
+        assert_eq!(report.seat_wins[0] + report.seat_wins[1] + report.draws, 10);
+        assert!(report.average_rounds >= 2.0);
+        assert!(report.cards.iter().any(|c| c.card == "K" && c.held > 0));
+
+        let csv = report.to_csv();
+        let summary: Vec<&str> = csv.lines().take(2).collect();
+        assert!(summary[0].contains("first_player_win_rate,average_rounds,average_actions"));
+        assert!(summary[1].starts_with("10,7,Easy,Easy,"));
+        assert!(csv.contains("\n\ncard,held,"));
+    }
+}
@@ -0,0 +1,1042 @@
+use crate::api::SharedRecords;
+use crate::bot_api::{bearer_token, new_token};
+use crate::errors::{ApiError, ErrorCode, Rejection};
+use crate::game_types::{GamePhase, RoomOptions, RuleSet};
+use crate::records::INITIAL_RATING;
+use crate::room::{RoomHandle, RoomRegistry};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+const MAX_ENTRANTS: usize = 256;
+const MAX_BEST_OF: u32 = 9;
+/// Steps the Swiss pairing search may take before it allows rematches.
+const PAIRING_BUDGET: usize = 100_000;
+const UPDATE_BUFFER: usize = 64;
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TournamentFormat {
+    Swiss,
+    RoundRobin,
+    SingleElimination,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TournamentStatus {
+    Registration,
+    Running,
+    Finished,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Entrant {
+    pub player_id: String,
+    pub nickname: String,
+}
+
+/// One table of a round. A bye has a single player, no room, and scores
+/// as a win. In an elimination series every match is played in the same
+/// room until a player has won a majority of `best_of`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Pairing {
+    pub room_id: Option<String>,
+    pub players: Vec<String>,
+    pub result: Option<String>, // Winner's id or "Draw", as in `GameState.winner`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub games: Vec<String>, // Winner of each match played so far
+}
+
+impl Pairing {
+    fn is_bye(&self) -> bool {
+        self.players.len() == 1
+    }
+
+    /// Points `player_id` took from this table, once it has a result.
+    fn score(&self, player_id: &str) -> Option<f64> {
+        match self.result.as_deref()? {
+            "Draw" => Some(0.5),
+            winner if winner == player_id => Some(1.0),
+            _ => Some(0.0),
+        }
+    }
+
+    fn opponent(&self, player_id: &str) -> Option<&str> {
+        self.players.iter().map(String::as_str).find(|p| *p != player_id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Round {
+    pub number: u32,
+    pub pairings: Vec<Pairing>,
+}
+
+/// A tournament's entrants and every round paired so far. Matches are
+/// played in rooms whose seats are reserved for the pairing.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Tournament {
+    pub id: String,
+    pub name: String,
+    pub format: TournamentFormat,
+    pub status: TournamentStatus,
+    pub total_rounds: u32, // Chosen at the start; 0 during registration
+    pub best_of: u32, // Matches per elimination series; 1 otherwise
+    pub options: RoomOptions,
+    pub entrants: Vec<Entrant>, // In registration order, which is the seeding
+    pub rounds: Vec<Round>,
+    pub created_at: u64,
+    #[serde(skip)]
+    requested_rounds: Option<u32>,
+}
+
+/// One seat of a bracket series.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct BracketSeat {
+    pub player_id: String,
+    pub nickname: String,
+    pub seed: usize,
+    pub wins: u32, // Matches won in this series
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Series {
+    pub room_id: Option<String>, // None for a bye or a series not yet paired
+    pub players: Vec<BracketSeat>, // The players known so far
+    pub winner: Option<String>,
+    pub games: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct BracketRound {
+    pub number: u32,
+    pub name: String, // "Final", "Semifinals", "Round of 16"...
+    pub series: Vec<Series>,
+}
+
+/// A single-elimination bracket, including the rounds still to come.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Bracket {
+    pub tournament_id: String,
+    pub best_of: u32,
+    pub rounds: Vec<BracketRound>,
+    pub champion: Option<String>,
+}
+
+/// A line of the standings. Ties on points are broken by Buchholz (the
+/// opponents' points), then Sonneborn-Berger (the points of the opponents
+/// beaten, plus half of those drawn), then wins, then seeding.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Standing {
+    pub rank: usize,
+    pub player_id: String,
+    pub nickname: String,
+    pub points: f64,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    pub byes: u32,
+    pub buchholz: f64,
+    pub sonneborn_berger: f64,
+}
+
+/// Pairs `players` in order, each with the highest one left they have not
+/// met yet, backtracking when that strands someone.
+fn pair_without_rematches(players: &[String], met: &HashSet<(String, String)>, budget: &mut usize) -> Option<Vec<Vec<String>>> {
+    let Some((first, rest)) = players.split_first() else {
+        return Some(Vec::new());
+    };
+    for i in 0..rest.len() {
+        if *budget == 0 {
+            return None;
+        }
+        *budget -= 1;
+        if met.contains(&(first.clone(), rest[i].clone())) {
+            continue;
+        }
+        let mut remaining = rest.to_vec();
+        let opponent = remaining.remove(i);
+        if let Some(mut pairs) = pair_without_rematches(&remaining, met, budget) {
+            pairs.insert(0, vec![first.clone(), opponent]);
+            return Some(pairs);
+        }
+    }
+    None
+}
+
+/// Orders `players` (standings order, with their points) so that pairing
+/// each with the next one left folds every score group: the top half meets
+/// the bottom half, 1 against n/2+1. The lowest of an odd group drops into
+/// the next one.
+fn fold_order(players: &[(String, f64)]) -> Vec<String> {
+    let mut order = Vec::new();
+    let mut group: Vec<String> = Vec::new();
+    let mut rest = players.iter().peekable();
+    while let Some((id, points)) = rest.next() {
+        group.push(id.clone());
+        if rest.peek().is_some_and(|(_, next)| next == points) {
+            continue;
+        }
+        let floater = if group.len() % 2 == 1 && rest.peek().is_some() { group.pop() } else { None };
+        let half = group.len() / 2;
+        for i in 0..half {
+            order.push(group[i].clone());
+            order.push(group[half + i].clone());
+        }
+        order.extend(group.drain(..).skip(half * 2));
+        group.extend(floater);
+    }
+    order
+}
+
+/// Seeds in bracket order for a bracket of `size` (a power of two), so the
+/// top seeds can only meet in the late rounds: 1, 4, 2, 3 for four.
+fn bracket_order(size: usize) -> Vec<usize> {
+    let mut order = vec![1];
+    while order.len() < size {
+        let sum = order.len() * 2 + 1;
+        order = order.iter().flat_map(|&seed| [seed, sum - seed]).collect();
+    }
+    order
+}
+
+fn bracket_round_name(series: usize) -> String {
+    match series {
+        1 => "Final".to_string(),
+        2 => "Semifinals".to_string(),
+        4 => "Quarterfinals".to_string(),
+        _ => format!("Round of {}", series * 2),
+    }
+}
+
+/// Round `round` (from 0) of a round robin between `n` seeds by the circle
+/// method; `None` is the bye when `n` is odd.
+fn round_robin_round(n: usize, round: usize) -> Vec<(Option<usize>, Option<usize>)> {
+    let size = n + n % 2;
+    let at = |k: usize| {
+        let seed = if k == 0 { 0 } else { (k - 1 + round) % (size - 1) + 1 };
+        Some(seed).filter(|s| *s < n)
+    };
+    (0..size / 2).map(|i| (at(i), at(size - 1 - i))).collect()
+}
+
+impl Tournament {
+    pub fn new(id: String, name: String, format: TournamentFormat, rounds: Option<u32>, best_of: u32, options: RoomOptions) -> Self {
+        Tournament {
+            id,
+            name,
+            format,
+            status: TournamentStatus::Registration,
+            total_rounds: 0,
+            best_of,
+            options,
+            entrants: Vec::new(),
+            rounds: Vec::new(),
+            created_at: now(),
+            requested_rounds: rounds,
+        }
+    }
+
+    pub fn register(&mut self, player_id: String, nickname: String) -> Result<(), Rejection> {
+        if self.status != TournamentStatus::Registration {
+            return Err(Rejection::new(ErrorCode::GameInProgress, "Registration is closed"));
+        }
+        if self.entrants.iter().any(|e| e.player_id == player_id) {
+            return Err(Rejection::new(ErrorCode::InvalidMessage, "Already registered"));
+        }
+        if self.entrants.len() >= MAX_ENTRANTS {
+            return Err(Rejection::new(ErrorCode::RoomFull, "The tournament is full"));
+        }
+        self.entrants.push(Entrant { player_id, nickname });
+        Ok(())
+    }
+
+    /// Orders the entrants by rating, best first, keeping registration
+    /// order between equals. Unrated players count as new ones.
+    pub fn seed_by_rating(&mut self, ratings: &HashMap<String, i32>) {
+        self.entrants.sort_by_key(|e| std::cmp::Reverse(ratings.get(&e.player_id).copied().unwrap_or(INITIAL_RATING)));
+    }
+
+    /// Closes registration and pairs the first round.
+    pub fn start(&mut self) -> Result<(), Rejection> {
+        if self.status != TournamentStatus::Registration {
+            return Err(Rejection::new(ErrorCode::GameInProgress, "The tournament has already started"));
+        }
+        let n = self.entrants.len();
+        if n < 2 {
+            return Err(Rejection::new(ErrorCode::WrongPhase, "A tournament needs at least two players"));
+        }
+
+        // Swiss defaults to enough rounds to separate a single winner, and
+        // never plays more rounds than a round robin would.
+        let most = (n + n % 2 - 1) as u32;
+        self.total_rounds = match self.format {
+            TournamentFormat::RoundRobin => most,
+            TournamentFormat::SingleElimination => n.next_power_of_two().trailing_zeros(),
+            TournamentFormat::Swiss => self.requested_rounds.unwrap_or(n.next_power_of_two().trailing_zeros()).clamp(1, most),
+        };
+        self.status = TournamentStatus::Running;
+        self.pair_next_round();
+        Ok(())
+    }
+
+    pub fn current_round(&self) -> Option<&Round> {
+        self.rounds.last()
+    }
+
+    /// The pairing played in `room_id`, in any round.
+    pub fn pairing(&self, room_id: &str) -> Option<&Pairing> {
+        self.rounds.iter().flat_map(|r| &r.pairings).find(|p| p.room_id.as_deref() == Some(room_id))
+    }
+
+    /// The undecided pairing of the current round played in `room_id`,
+    /// after checking `winner` (or a draw, if allowed) could end it.
+    fn open_pairing(&mut self, room_id: &str, winner: &str, draw_allowed: bool) -> Result<&mut Pairing, Rejection> {
+        let pairing = self.rounds.last_mut()
+            .and_then(|round| round.pairings.iter_mut().find(|p| p.room_id.as_deref() == Some(room_id)))
+            .ok_or_else(|| Rejection::new(ErrorCode::RoomNotFound, "No match of the current round is played in that room"))?;
+        if pairing.result.is_some() {
+            return Err(Rejection::new(ErrorCode::WrongPhase, "That match already has a result"));
+        }
+        let possible = pairing.players.iter().any(|p| p == winner) || (draw_allowed && winner == "Draw");
+        if !possible {
+            return Err(Rejection::new(ErrorCode::InvalidMessage, "The winner must be one of the players or \"Draw\""));
+        }
+        Ok(pairing)
+    }
+
+    /// Records a finished match in `room_id`. It decides the pairing,
+    /// except in an elimination series, which goes on until a player has
+    /// won a majority of `best_of`; drawn matches there are replayed.
+    pub fn record(&mut self, room_id: &str, winner: &str) -> Result<(), Rejection> {
+        let elimination = self.format == TournamentFormat::SingleElimination;
+        let needed = self.best_of / 2 + 1;
+        let pairing = self.open_pairing(room_id, winner, true)?;
+        pairing.games.push(winner.to_string());
+        let wins = pairing.games.iter().filter(|w| *w == winner).count() as u32;
+        if !elimination || (winner != "Draw" && wins >= needed) {
+            pairing.result = Some(winner.to_string());
+        }
+        self.advance();
+        Ok(())
+    }
+
+    /// Decides the pairing in `room_id` outright, as an organizer does for
+    /// a no-show.
+    pub fn decide(&mut self, room_id: &str, winner: &str) -> Result<(), Rejection> {
+        let draw_allowed = self.format != TournamentFormat::SingleElimination;
+        self.open_pairing(room_id, winner, draw_allowed)?.result = Some(winner.to_string());
+        self.advance();
+        Ok(())
+    }
+
+    /// Pairs the next round, or finishes, once every table has a result.
+    fn advance(&mut self) {
+        if self.rounds.last().is_some_and(|round| round.pairings.iter().all(|p| p.result.is_some())) {
+            if self.rounds.len() as u32 >= self.total_rounds {
+                self.status = TournamentStatus::Finished;
+            } else {
+                self.pair_next_round();
+            }
+        }
+    }
+
+    fn pair_next_round(&mut self) {
+        let number = self.rounds.len() as u32 + 1;
+        let mut tables: Vec<Vec<String>> = match self.format {
+            TournamentFormat::RoundRobin => {
+                let seeds = round_robin_round(self.entrants.len(), self.rounds.len());
+                seeds.into_iter()
+                    .map(|pair| [pair.0, pair.1].into_iter().flatten().map(|i| self.entrants[i].player_id.clone()).collect())
+                    .collect()
+            },
+            TournamentFormat::Swiss => self.swiss_tables(),
+            TournamentFormat::SingleElimination => self.elimination_tables(),
+        };
+
+        // Byes go last, except in a bracket, where the position matters.
+        // Every other table gets a room.
+        if self.format != TournamentFormat::SingleElimination {
+            tables.sort_by_key(|players| players.len() == 1);
+        }
+        let pairings = tables.into_iter().enumerate().map(|(i, players)| {
+            if players.len() == 1 {
+                Pairing { room_id: None, result: Some(players[0].clone()), players, games: Vec::new() }
+            } else {
+                let room_id = Some(format!("tournament-{}-r{}-t{}", self.id, number, i + 1));
+                Pairing { room_id, players, result: None, games: Vec::new() }
+            }
+        }).collect();
+        self.rounds.push(Round { number, pairings });
+    }
+
+    /// The first round places the seeds in bracket order, giving the top
+    /// seeds a bye when the field is short of a power of two; after that,
+    /// the winners of neighbouring series meet.
+    fn elimination_tables(&self) -> Vec<Vec<String>> {
+        let winners: Vec<String> = match self.rounds.last() {
+            Some(round) => round.pairings.iter().filter_map(|p| p.result.clone()).collect(),
+            None => {
+                let order = bracket_order(self.entrants.len().next_power_of_two());
+                return order.chunks(2)
+                    .map(|seeds| seeds.iter().filter_map(|s| self.entrants.get(s - 1)).map(|e| e.player_id.clone()).collect())
+                    .collect();
+            },
+        };
+        winners.chunks(2).map(<[String]>::to_vec).collect()
+    }
+
+    /// The bracket of a single-elimination tournament that has started.
+    pub fn bracket(&self) -> Option<Bracket> {
+        if self.format != TournamentFormat::SingleElimination || self.rounds.is_empty() {
+            return None;
+        }
+        let seat = |player_id: &str, games: &[String]| {
+            let seed = self.entrants.iter().position(|e| e.player_id == player_id)?;
+            Some(BracketSeat {
+                player_id: player_id.to_string(),
+                nickname: self.entrants[seed].nickname.clone(),
+                seed: seed + 1,
+                wins: games.iter().filter(|w| *w == player_id).count() as u32,
+            })
+        };
+
+        let mut rounds: Vec<BracketRound> = self.rounds.iter().map(|round| BracketRound {
+            number: round.number,
+            name: bracket_round_name(round.pairings.len()),
+            series: round.pairings.iter().map(|p| Series {
+                room_id: p.room_id.clone(),
+                players: p.players.iter().filter_map(|id| seat(id, &p.games)).collect(),
+                winner: p.result.clone(),
+                games: p.games.clone(),
+            }).collect(),
+        }).collect();
+
+        // Rounds still to come hold whoever has already advanced.
+        while (rounds.len() as u32) < self.total_rounds {
+            let previous = rounds.last().expect("the first round is paired");
+            let series = previous.series.chunks(2).map(|feeders| Series {
+                room_id: None,
+                players: feeders.iter().filter_map(|f| f.winner.as_deref()).filter_map(|id| seat(id, &[])).collect(),
+                winner: None,
+                games: Vec::new(),
+            }).collect::<Vec<_>>();
+            rounds.push(BracketRound { number: previous.number + 1, name: bracket_round_name(series.len()), series });
+        }
+
+        let champion = match self.status {
+            TournamentStatus::Finished => rounds.last().and_then(|r| r.series.first()).and_then(|s| s.winner.clone()),
+            _ => None,
+        };
+        Some(Bracket { tournament_id: self.id.clone(), best_of: self.best_of, rounds, champion })
+    }
+
+    /// Swiss tables for the next round: players in standings order, the
+    /// lowest ranked without a bye sits out if the count is odd, and the
+    /// rest are folded within their score group, meeting someone new where
+    /// possible.
+    fn swiss_tables(&self) -> Vec<Vec<String>> {
+        let mut order: Vec<(String, f64)> = self.standings().into_iter().map(|s| (s.player_id, s.points)).collect();
+        let mut met = HashSet::new();
+        let mut had_bye = HashSet::new();
+        for pairing in self.rounds.iter().flat_map(|r| &r.pairings) {
+            match pairing.players.as_slice() {
+                [a, b] => {
+                    met.insert((a.clone(), b.clone()));
+                    met.insert((b.clone(), a.clone()));
+                },
+                [a] => {
+                    had_bye.insert(a.clone());
+                },
+                _ => {},
+            }
+        }
+
+        let mut tables = Vec::new();
+        if order.len() % 2 == 1 {
+            let sits_out = order.iter().rposition(|(p, _)| !had_bye.contains(p)).unwrap_or(order.len() - 1);
+            tables.push(vec![order.remove(sits_out).0]);
+        }
+        let order = fold_order(&order);
+        let mut budget = PAIRING_BUDGET;
+        let pairs = pair_without_rematches(&order, &met, &mut budget)
+            .unwrap_or_else(|| order.chunks(2).map(<[String]>::to_vec).collect());
+        pairs.into_iter().chain(tables).collect()
+    }
+
+    pub fn standings(&self) -> Vec<Standing> {
+        let finished: Vec<&Pairing> = self.rounds.iter().flat_map(|r| &r.pairings).filter(|p| p.result.is_some()).collect();
+        let mut points: HashMap<&str, f64> = HashMap::new();
+        for pairing in &finished {
+            for player in &pairing.players {
+                *points.entry(player).or_default() += pairing.score(player).unwrap_or(0.0);
+            }
+        }
+
+        let mut standings: Vec<(usize, Standing)> = self.entrants.iter().enumerate().map(|(seed, entrant)| {
+            let id = entrant.player_id.as_str();
+            let mut standing = Standing {
+                rank: 0,
+                player_id: entrant.player_id.clone(),
+                nickname: entrant.nickname.clone(),
+                points: points.get(id).copied().unwrap_or(0.0),
+                wins: 0,
+                draws: 0,
+                losses: 0,
+                byes: 0,
+                buchholz: 0.0,
+                sonneborn_berger: 0.0,
+            };
+            for pairing in finished.iter().filter(|p| p.players.iter().any(|q| q == id)) {
+                if pairing.is_bye() {
+                    standing.byes += 1;
+                    continue;
+                }
+                let score = pairing.score(id).unwrap_or(0.0);
+                let opponent_points = pairing.opponent(id).and_then(|o| points.get(o)).copied().unwrap_or(0.0);
+                match score {
+                    s if s >= 1.0 => standing.wins += 1,
+                    s if s > 0.0 => standing.draws += 1,
+                    _ => standing.losses += 1,
+                }
+                standing.buchholz += opponent_points;
+                standing.sonneborn_berger += score * opponent_points;
+            }
+            (seed, standing)
+        }).collect();
+
+        standings.sort_by(|(seed_a, a), (seed_b, b)| {
+            b.points.total_cmp(&a.points)
+                .then(b.buchholz.total_cmp(&a.buchholz))
+                .then(b.sonneborn_berger.total_cmp(&a.sonneborn_berger))
+                .then(b.wins.cmp(&a.wins))
+                .then(seed_a.cmp(seed_b))
+        });
+        standings.into_iter().enumerate().map(|(i, (_, standing))| Standing { rank: i + 1, ..standing }).collect()
+    }
+
+    /// Standings as CSV, one row per player.
+    pub fn standings_csv(&self) -> String {
+        let mut out = String::from("rank,player_id,nickname,points,wins,draws,losses,byes,buchholz,sonneborn_berger\n");
+        for s in self.standings() {
+            out.push_str(&format!(
+                "{},{},{},{:.1},{},{},{},{},{:.1},{:.2}\n",
+                s.rank, csv_field(&s.player_id), csv_field(&s.nickname), s.points, s.wins, s.draws, s.losses, s.byes, s.buchholz, s.sonneborn_berger
+            ));
+        }
+        out
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// A tournament with its standings, as served and broadcast on every
+/// change.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TournamentView {
+    #[serde(flatten)]
+    pub tournament: Tournament,
+    pub standings: Vec<Standing>,
+}
+
+impl From<&Tournament> for TournamentView {
+    fn from(tournament: &Tournament) -> Self {
+        TournamentView { tournament: tournament.clone(), standings: tournament.standings() }
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    tournaments: HashMap<String, Tournament>,
+    organizers: HashMap<String, String>, // Tournament id to organizer token
+}
+
+/// Every tournament, kept in memory. Opens a room for each table of a new
+/// round and records the winner when its match ends.
+#[derive(Clone)]
+pub struct Tournaments {
+    registry: Arc<Mutex<Registry>>,
+    updates: broadcast::Sender<TournamentView>,
+    rooms: RoomRegistry,
+    records: SharedRecords, // Ratings for seeding brackets
+    rules: RuleSet,
+}
+
+impl Tournaments {
+    pub fn new(rooms: RoomRegistry, records: SharedRecords, rules: RuleSet) -> Self {
+        Tournaments { registry: Arc::default(), updates: broadcast::channel(UPDATE_BUFFER).0, rooms, records, rules }
+    }
+
+    /// Every change to any tournament.
+    pub fn subscribe(&self) -> broadcast::Receiver<TournamentView> {
+        self.updates.subscribe()
+    }
+
+    pub fn get(&self, id: &str) -> Result<TournamentView, Rejection> {
+        self.registry.lock().unwrap().tournaments.get(id).map(TournamentView::from)
+            .ok_or_else(|| Rejection::new(ErrorCode::TournamentNotFound, "Tournament not found"))
+    }
+
+    /// Every tournament, newest first.
+    pub fn list(&self) -> Vec<Tournament> {
+        let mut tournaments: Vec<Tournament> = self.registry.lock().unwrap().tournaments.values().cloned().collect();
+        tournaments.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| a.id.cmp(&b.id)));
+        tournaments
+    }
+
+    /// Creates a tournament and returns it with the organizer's token,
+    /// which is not shown again.
+    pub fn create(&self, name: &str, format: TournamentFormat, rounds: Option<u32>, best_of: u32, options: RoomOptions) -> Result<(Tournament, String), Rejection> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > 60 {
+            return Err(Rejection::new(ErrorCode::InvalidMessage, "Names are 1 to 60 characters"));
+        }
+        if best_of.is_multiple_of(2) || best_of > MAX_BEST_OF {
+            return Err(Rejection::new(ErrorCode::InvalidMessage, format!("best_of is an odd number up to {}", MAX_BEST_OF)));
+        }
+        if best_of > 1 && format != TournamentFormat::SingleElimination {
+            return Err(Rejection::new(ErrorCode::InvalidMessage, "Only single-elimination pairings are played as series"));
+        }
+        let id = Uuid::new_v4().simple().to_string()[..12].to_string();
+        let tournament = Tournament::new(id.clone(), name.to_string(), format, rounds, best_of, options);
+        let token = new_token();
+        let mut registry = self.registry.lock().unwrap();
+        registry.tournaments.insert(id.clone(), tournament.clone());
+        registry.organizers.insert(id, token.clone());
+        Ok((tournament, token))
+    }
+
+    pub fn register(&self, id: &str, player_id: String, nickname: String) -> Result<TournamentView, Rejection> {
+        self.update(id, |t| t.register(player_id, nickname))
+    }
+
+    /// Closes registration and pairs round 1; brackets are seeded by
+    /// rating first.
+    pub async fn start(&self, id: &str, token: &str) -> Result<TournamentView, Rejection> {
+        self.authorize(id, token)?;
+        let entrants = self.get(id)?.tournament.entrants;
+        let ratings: HashMap<String, i32> = {
+            let records = self.records.read().await;
+            entrants.iter().filter_map(|e| Some((e.player_id.clone(), records.player(&e.player_id)?.rating))).collect()
+        };
+        self.update(id, |t| {
+            if t.format == TournamentFormat::SingleElimination && t.status == TournamentStatus::Registration {
+                t.seed_by_rating(&ratings);
+            }
+            t.start()
+        })
+    }
+
+    /// An organizer's result for a pairing, e.g. for a player who never
+    /// showed up. It decides an elimination series outright.
+    pub fn report(&self, id: &str, token: &str, room_id: &str, winner: &str) -> Result<TournamentView, Rejection> {
+        self.authorize(id, token)?;
+        self.update(id, |t| t.decide(room_id, winner))
+    }
+
+    fn authorize(&self, id: &str, token: &str) -> Result<(), Rejection> {
+        match self.registry.lock().unwrap().organizers.get(id) {
+            Some(organizer) if organizer == token => Ok(()),
+            Some(_) => Err(Rejection::new(ErrorCode::Unauthorized, "Only the organizer can do that")),
+            None => Err(Rejection::new(ErrorCode::TournamentNotFound, "Tournament not found")),
+        }
+    }
+
+    /// Applies `change` to tournament `id`, opens rooms for a newly paired
+    /// round and broadcasts the result.
+    fn update(&self, id: &str, change: impl FnOnce(&mut Tournament) -> Result<(), Rejection>) -> Result<TournamentView, Rejection> {
+        let view = {
+            let mut registry = self.registry.lock().unwrap();
+            let tournament = registry.tournaments.get_mut(id)
+                .ok_or_else(|| Rejection::new(ErrorCode::TournamentNotFound, "Tournament not found"))?;
+            let round = tournament.rounds.len();
+            change(tournament)?;
+            if tournament.rounds.len() > round {
+                self.open_round(tournament);
+            }
+            TournamentView::from(&*tournament)
+        };
+        let _ = self.updates.send(view.clone());
+        Ok(view)
+    }
+
+    fn open_round(&self, tournament: &Tournament) {
+        let Some(round) = tournament.current_round() else { return };
+        info!("Pairing round {} of tournament {}", round.number, tournament.id);
+        for pairing in &round.pairings {
+            let Some(room_id) = &pairing.room_id else { continue };
+            let options = RoomOptions { seats: pairing.players.clone(), ..tournament.options.clone() };
+            let room = self.rooms.get_or_create(room_id, options, self.rules.clone());
+            self.watch_match(tournament.id.clone(), room_id.clone(), room);
+        }
+    }
+
+    /// Records the winner of each match in `room` as it ends, dealing the
+    /// next match while a series is undecided. If the room closes first,
+    /// the organizer has to report the result.
+    fn watch_match(&self, tournament_id: String, room_id: String, room: RoomHandle) {
+        let tournaments = self.clone();
+        tokio::spawn(async move {
+            let mut changes = room.watch();
+            let mut recorded = 0; // Version at which the last match ended
+            loop {
+                let ended = changes.wait_for(|game| game.phase == GamePhase::GameEnd && game.winner.is_some() && game.version > recorded).await
+                    .map(|game| (game.version, game.winner.clone().unwrap_or_default()));
+                let Ok((version, winner)) = ended else {
+                    warn!("Room {} of tournament {} closed without a result", room_id, tournament_id);
+                    return;
+                };
+                recorded = version;
+
+                match tournaments.update(&tournament_id, |t| t.record(&room_id, &winner)) {
+                    Ok(view) if view.tournament.pairing(&room_id).is_some_and(|p| p.result.is_none()) => {
+                        if let Err(e) = room.rematch().await {
+                            warn!("Could not deal the next match in {}: {}", room_id, e.message);
+                            return;
+                        }
+                    },
+                    Ok(_) => return,
+                    Err(e) => {
+                        warn!("Could not record {} in tournament {}: {}", room_id, tournament_id, e.message);
+                        return;
+                    },
+                }
+            }
+        });
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateRequest {
+    name: String,
+    format: TournamentFormat,
+    rounds: Option<u32>, // Swiss only; defaults to log2 of the field
+    #[serde(default = "one")]
+    best_of: u32, // Single elimination only
+    #[serde(default)]
+    options: RoomOptions,
+}
+
+fn one() -> u32 {
+    1
+}
+
+#[derive(Debug, Serialize)]
+struct Created {
+    tournament: Tournament,
+    organizer_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegisterRequest {
+    player_id: String,
+    nickname: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResultRequest {
+    room_id: String,
+    winner: String, // Player id or "Draw"
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportQuery {
+    format: Option<String>, // "json" (default) or "csv"
+}
+
+/// Tournament management under `/api/tournaments`. See protocol.md.
+pub fn router(tournaments: Tournaments) -> Router {
+    Router::new()
+        .route("/api/tournaments", get(list).post(create))
+        .route("/api/tournaments/:id", get(get_tournament))
+        .route("/api/tournaments/:id/players", post(register))
+        .route("/api/tournaments/:id/start", post(start))
+        .route("/api/tournaments/:id/results", post(report))
+        .route("/api/tournaments/:id/bracket", get(bracket))
+        .route("/api/tournaments/:id/export", get(export))
+        .with_state(tournaments)
+}
+
+/// The organizer token a request carries, or "" (which never matches).
+fn organizer(headers: &HeaderMap) -> &str {
+    bearer_token(headers).unwrap_or("")
+}
+
+async fn list(State(tournaments): State<Tournaments>) -> Json<Vec<Tournament>> {
+    Json(tournaments.list())
+}
+
+async fn create(State(tournaments): State<Tournaments>, Json(request): Json<CreateRequest>) -> Result<Response, ApiError> {
+    let (tournament, organizer_token) = tournaments.create(&request.name, request.format, request.rounds, request.best_of, request.options.requested())?;
+    info!("Created tournament {} ({})", tournament.id, tournament.name);
+    Ok((StatusCode::CREATED, Json(Created { tournament, organizer_token })).into_response())
+}
+
+async fn get_tournament(State(tournaments): State<Tournaments>, Path(id): Path<String>) -> Result<Json<TournamentView>, ApiError> {
+    Ok(Json(tournaments.get(&id)?))
+}
+
+async fn register(State(tournaments): State<Tournaments>, Path(id): Path<String>, Json(request): Json<RegisterRequest>) -> Result<Json<TournamentView>, ApiError> {
+    Ok(Json(tournaments.register(&id, request.player_id, request.nickname)?))
+}
+
+async fn start(State(tournaments): State<Tournaments>, headers: HeaderMap, Path(id): Path<String>) -> Result<Json<TournamentView>, ApiError> {
+    Ok(Json(tournaments.start(&id, organizer(&headers)).await?))
+}
+
+async fn report(State(tournaments): State<Tournaments>, headers: HeaderMap, Path(id): Path<String>, Json(request): Json<ResultRequest>) -> Result<Json<TournamentView>, ApiError> {
+    Ok(Json(tournaments.report(&id, organizer(&headers), &request.room_id, &request.winner)?))
+}
+
+async fn bracket(State(tournaments): State<Tournaments>, Path(id): Path<String>) -> Result<Json<Bracket>, ApiError> {
+    let view = tournaments.get(&id)?;
+    match view.tournament.bracket() {
+        Some(bracket) => Ok(Json(bracket)),
+        None if view.tournament.format != TournamentFormat::SingleElimination => {
+            Err(Rejection::new(ErrorCode::InvalidMessage, "Only single-elimination tournaments have a bracket").into())
+        },
+        None => Err(Rejection::new(ErrorCode::WrongPhase, "The bracket is drawn when the tournament starts").into()),
+    }
+}
+
+async fn export(State(tournaments): State<Tournaments>, Path(id): Path<String>, Query(query): Query<ExportQuery>) -> Result<Response, ApiError> {
+    let view = tournaments.get(&id)?;
+    match query.format.as_deref() {
+        None | Some("json") => Ok(Json(view).into_response()),
+        Some("csv") => Ok(([(header::CONTENT_TYPE, "text/csv")], view.tournament.standings_csv()).into_response()),
+        Some(other) => Err(Rejection::new(ErrorCode::InvalidMessage, format!("Unknown export format `{}`", other)).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tournament(format: TournamentFormat, players: usize) -> Tournament {
+        let mut t = Tournament::new("t1".to_string(), "Monthly".to_string(), format, None, 1, RoomOptions::default());
+        for i in 1..=players {
+            t.register(format!("p{}", i), format!("Player {}", i)).unwrap();
+        }
+        t
+    }
+
+    /// Plays out the current round: the lower seed wins, except `draws`.
+    fn play_round(t: &mut Tournament, draws: &[&str]) {
+        let tables: Vec<(String, Vec<String>)> = t.current_round().unwrap().pairings.iter()
+            .filter_map(|p| Some((p.room_id.clone()?, p.players.clone())))
+            .collect();
+        for (room_id, players) in tables {
+            let winner = if draws.contains(&room_id.as_str()) {
+                "Draw".to_string()
+            } else {
+                players.iter().min_by_key(|p| p[1..].parse::<u32>().unwrap()).unwrap().clone()
+            };
+            t.record(&room_id, &winner).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_round_robin_meets_everyone_once() {
+        let mut t = tournament(TournamentFormat::RoundRobin, 5);
+        t.start().unwrap();
+        assert_eq!(t.total_rounds, 5);
+        assert!(t.register("late".to_string(), "Late".to_string()).is_err());
+        while t.status == TournamentStatus::Running {
+            play_round(&mut t, &[]);
+        }
+
+        let mut met = HashSet::new();
+        for pairing in t.rounds.iter().flat_map(|r| &r.pairings) {
+            if let [a, b] = pairing.players.as_slice() {
+                assert!(met.insert((a.min(b).clone(), a.max(b).clone())), "rematch {} {}", a, b);
+            }
+        }
+        assert_eq!(met.len(), 10);
+        let standings = t.standings();
+        assert_eq!(standings[0].player_id, "p1");
+        assert_eq!((standings[0].points, standings[0].wins, standings[0].byes), (5.0, 4, 1));
+        assert_eq!(standings[4].player_id, "p5");
+        assert!(t.record("tournament-t1-r1-t1", "p1").is_err());
+    }
+
+    #[test]
+    fn test_swiss_pairs_by_score_with_tiebreaks() {
+        let mut t = tournament(TournamentFormat::Swiss, 7);
+        t.start().unwrap();
+        assert_eq!(t.total_rounds, 3);
+        play_round(&mut t, &["tournament-t1-r1-t1"]);
+
+        // Round 2 pairs players on equal scores and gives the bye to someone
+        // who has not had one.
+        let round = t.current_round().unwrap();
+        assert_eq!(round.number, 2);
+        let first_bye = &t.rounds[0].pairings.last().unwrap().players;
+        let second_bye = &round.pairings.last().unwrap().players;
+        assert_eq!((first_bye.len(), second_bye.len()), (1, 1));
+        assert_ne!(first_bye, second_bye);
+        assert!(t.record("tournament-t1-r1-t2", "p1").is_err(), "round 1 is over");
+        assert!(t.record("tournament-t1-r2-t1", "nobody").is_err());
+
+        play_round(&mut t, &[]);
+        play_round(&mut t, &[]);
+        assert_eq!(t.status, TournamentStatus::Finished);
+        let standings = t.standings();
+        for pair in standings.windows(2) {
+            assert!(pair[0].points >= pair[1].points);
+            if pair[0].points == pair[1].points {
+                assert!(pair[0].buchholz >= pair[1].buchholz);
+            }
+        }
+        assert_eq!(standings.iter().map(|s| s.wins + s.draws + s.losses + s.byes).sum::<u32>(), 21);
+        assert!(t.standings_csv().starts_with("rank,player_id,nickname,points"));
+        assert_eq!(t.standings_csv().lines().count(), 8);
+    }
+
+    #[test]
+    fn test_swiss_folds_score_groups() {
+        let pairs = |t: &Tournament| -> Vec<Vec<String>> { t.current_round().unwrap().pairings.iter().map(|p| p.players.clone()).collect() };
+        let tables = |names: &[[&str; 2]]| -> Vec<Vec<String>> { names.iter().map(|t| t.iter().map(|p| p.to_string()).collect()).collect() };
+
+        let mut t = tournament(TournamentFormat::Swiss, 8);
+        t.start().unwrap();
+        assert_eq!(pairs(&t), tables(&[["p1", "p5"], ["p2", "p6"], ["p3", "p7"], ["p4", "p8"]]));
+        play_round(&mut t, &[]);
+        assert_eq!(pairs(&t), tables(&[["p1", "p3"], ["p2", "p4"], ["p5", "p7"], ["p6", "p8"]]));
+
+        // An odd group sends its lowest player down to the next one.
+        let scored = |ids: &[(&str, f64)]| -> Vec<(String, f64)> { ids.iter().map(|(p, s)| (p.to_string(), *s)).collect() };
+        let order = fold_order(&scored(&[("a", 2.0), ("b", 2.0), ("c", 2.0), ("d", 1.0), ("e", 1.0), ("f", 1.0)]));
+        assert_eq!(order, ["a", "b", "c", "e", "d", "f"]);
+    }
+
+    #[test]
+    fn test_bracket_seeds_byes_and_series() {
+        let mut t = Tournament::new("t1".to_string(), "Finals".to_string(), TournamentFormat::SingleElimination, None, 3, RoomOptions::default());
+        for i in 1..=6 {
+            t.register(format!("p{}", i), format!("Player {}", i)).unwrap();
+        }
+        let ratings = HashMap::from([("p6".to_string(), 1700), ("p5".to_string(), 1600), ("p1".to_string(), 1400)]);
+        t.seed_by_rating(&ratings);
+        let seeds: Vec<&str> = t.entrants.iter().map(|e| e.player_id.as_str()).collect();
+        assert_eq!(seeds, ["p6", "p5", "p2", "p3", "p4", "p1"]);
+        t.start().unwrap();
+        assert_eq!(t.total_rounds, 3);
+
+        // Seeds 1 and 2 get the byes of an eight-player bracket.
+        let round: Vec<Vec<String>> = t.rounds[0].pairings.iter().map(|p| p.players.clone()).collect();
+        assert_eq!(round, [vec!["p6"], vec!["p3", "p4"], vec!["p5"], vec!["p2", "p1"]]);
+
+        // A drawn match is replayed; two wins take the series.
+        t.record("tournament-t1-r1-t2", "Draw").unwrap();
+        assert!(t.record("tournament-t1-r1-t2", "p6").is_err());
+        t.record("tournament-t1-r1-t2", "p4").unwrap();
+        t.record("tournament-t1-r1-t2", "p4").unwrap();
+        assert_eq!(t.rounds[0].pairings[1].result.as_deref(), Some("p4"));
+        assert!(t.decide("tournament-t1-r1-t4", "Draw").is_err());
+        t.decide("tournament-t1-r1-t4", "p2").unwrap();
+
+        let bracket = t.bracket().unwrap();
+        assert_eq!(bracket.rounds.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), ["Quarterfinals", "Semifinals", "Final"]);
+        let semi = &bracket.rounds[1].series[0];
+        assert_eq!(semi.players.iter().map(|s| (s.player_id.as_str(), s.seed)).collect::<Vec<_>>(), [("p6", 1), ("p4", 5)]);
+        assert!(bracket.rounds[2].series[0].players.is_empty());
+        assert_eq!(bracket.rounds[0].series[1].players[1].wins, 2);
+
+        t.decide("tournament-t1-r2-t1", "p6").unwrap();
+        t.decide("tournament-t1-r2-t2", "p2").unwrap();
+        t.record("tournament-t1-r3-t1", "p2").unwrap();
+        t.record("tournament-t1-r3-t1", "p6").unwrap();
+        t.record("tournament-t1-r3-t1", "p6").unwrap();
+        assert_eq!(t.status, TournamentStatus::Finished);
+        assert_eq!(t.bracket().unwrap().champion.as_deref(), Some("p6"));
+        assert_eq!(t.standings()[0].player_id, "p6");
+    }
+
+    #[tokio::test]
+    async fn test_results_come_from_rooms() {
+        use crate::chat::ChatConfig;
+        use crate::game_types::PlayerAction;
+        use crate::protocol::ActionGuard;
+        use crate::records::Records;
+
+        let records = Arc::new(tokio::sync::RwLock::new(Records::default()));
+        let rooms = RoomRegistry::new(records.clone(), Arc::new(ChatConfig::default()));
+        let tournaments = Tournaments::new(rooms.clone(), records, RuleSet::default());
+        let (t, token) = tournaments.create("Cup", TournamentFormat::Swiss, Some(1), 1, RoomOptions::default()).unwrap();
+        for pid in ["p1", "p2"] {
+            tournaments.register(&t.id, pid.to_string(), pid.to_string()).unwrap();
+        }
+        assert_eq!(tournaments.start(&t.id, "guess").await.unwrap_err().code, ErrorCode::Unauthorized);
+        let mut updates = tournaments.subscribe();
+        tournaments.start(&t.id, &token).await.unwrap();
+        assert_eq!(updates.recv().await.unwrap().tournament.status, TournamentStatus::Running);
+
+        let room_id = format!("tournament-{}-r1-t1", t.id);
+        let room = rooms.get(&room_id).unwrap();
+        assert_eq!(room.join("p3".to_string(), "p3".to_string(), String::new()).await.unwrap_err().code, ErrorCode::RoomFull);
+        for pid in ["p1", "p2"] {
+            room.join(pid.to_string(), pid.to_string(), String::new()).await.unwrap();
+        }
+        room.act("p2".to_string(), PlayerAction::Resign, ActionGuard::default()).await.unwrap();
+
+        let finished = updates.recv().await.unwrap();
+        assert_eq!(finished.tournament.status, TournamentStatus::Finished);
+        assert_eq!(finished.standings[0].player_id, "p1");
+        assert_eq!(room.restart("p1".to_string(), ActionGuard::default()).await.unwrap_err().code, ErrorCode::WrongPhase);
+    }
+
+    #[tokio::test]
+    async fn test_series_rematch_in_the_same_room() {
+        use crate::chat::ChatConfig;
+        use crate::game_types::PlayerAction;
+        use crate::protocol::ActionGuard;
+        use crate::records::Records;
+
+        let records = Arc::new(tokio::sync::RwLock::new(Records::default()));
+        let rooms = RoomRegistry::new(records.clone(), Arc::new(ChatConfig::default()));
+        let tournaments = Tournaments::new(rooms.clone(), records, RuleSet::default());
+        assert!(tournaments.create("Final", TournamentFormat::Swiss, None, 3, RoomOptions::default()).is_err());
+        let (t, token) = tournaments.create("Final", TournamentFormat::SingleElimination, None, 3, RoomOptions::default()).unwrap();
+        for pid in ["p1", "p2"] {
+            tournaments.register(&t.id, pid.to_string(), pid.to_string()).unwrap();
+        }
+        let mut updates = tournaments.subscribe();
+        tournaments.start(&t.id, &token).await.unwrap();
+        updates.recv().await.unwrap();
+
+        let room = rooms.get(&format!("tournament-{}-r1-t1", t.id)).unwrap();
+        for pid in ["p1", "p2"] {
+            room.join(pid.to_string(), pid.to_string(), String::new()).await.unwrap();
+        }
+        for game in 1..=2 {
+            let mut state = room.watch();
+            state.wait_for(|g| g.phase != GamePhase::GameEnd).await.unwrap();
+            room.act("p2".to_string(), PlayerAction::Resign, ActionGuard::default()).await.unwrap();
+            let update = updates.recv().await.unwrap();
+            assert_eq!(update.tournament.rounds[0].pairings[0].games.len(), game);
+        }
+        let bracket = tournaments.get(&t.id).unwrap().tournament.bracket().unwrap();
+        assert_eq!(bracket.champion.as_deref(), Some("p1"));
+    }
+}
@@ -0,0 +1,156 @@
+use crate::errors::ErrorCode;
+use crate::metrics::metrics;
+use crate::protocol::{self, Frame, Hello, CAPABILITIES, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION};
+use crate::session::{Connection, Services, Session};
+use axum::extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio::sync::Notify;
+use tracing::warn;
+
+/// Frames a client may fall behind by before it is disconnected.
+const OUTBOX_CAPACITY: usize = 256;
+
+/// Frames queued for one WebSocket, written by a separate task so room
+/// broadcasts never wait on a slow client. A client that lets the queue
+/// fill up is dropped rather than buffered without bound.
+struct WsConnection {
+    outbox: mpsc::Sender<Message>,
+    overflowed: Notify,
+}
+
+impl WsConnection {
+    fn send(&self, frame: &Frame) -> bool {
+        self.queue(Message::Text(serde_json::to_string(frame).expect("frames serialize")))
+    }
+
+    fn queue(&self, message: Message) -> bool {
+        match self.outbox.try_send(message) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                self.overflowed.notify_one();
+                false
+            },
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+}
+
+impl Connection for WsConnection {
+    fn emit(&self, event: &str, data: &Value) -> bool {
+        self.send(&Frame { event: event.to_string(), id: None, data: data.clone() })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct WsQuery {
+    version: Option<u32>, // Protocol version the client was written for
+}
+
+/// The plain WebSocket endpoint, `/ws`. See protocol.md for the frames.
+pub fn router(services: Services) -> Router {
+    Router::new().route("/ws", get(upgrade)).with_state(services)
+}
+
+async fn upgrade(ws: WebSocketUpgrade, State(services): State<Services>, Query(query): Query<WsQuery>) -> Response {
+    ws.on_upgrade(move |socket| run(socket, services, query.version))
+}
+
+async fn run(socket: WebSocket, services: Services, version: Option<u32>) {
+    let (mut sink, mut stream) = socket.split();
+    let (outbox, mut queued) = mpsc::channel::<Message>(OUTBOX_CAPACITY);
+    let writer = tokio::spawn(async move {
+        while let Some(message) = queued.recv().await {
+            if sink.send(message).await.is_err() {
+                break;
+            }
+        }
+        let _ = sink.close().await;
+    });
+
+    let conn = Arc::new(WsConnection { outbox, overflowed: Notify::new() });
+    let hello = Hello::new(CAPABILITIES.iter().map(|c| c.to_string()).collect());
+    conn.emit("hello", &serde_json::to_value(&hello).expect("hello serializes"));
+    if let Some(v) = version.filter(|v| !protocol::supports(*v)) {
+        // Say why in both the message and the close frame, then hang up.
+        metrics().record_error(ErrorCode::IncompatibleClient);
+        conn.emit("error", &json!(format!(
+            "This client speaks protocol {} but the server supports {} to {}; please update the client",
+            v, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+        )));
+        let reason = ErrorCode::IncompatibleClient.as_str().into();
+        conn.queue(Message::Close(Some(CloseFrame { code: 4000, reason })));
+        drop(conn);
+        let _ = writer.await;
+        return;
+    }
+
+    metrics().socket_connected();
+    let session = Session::new(conn.clone(), services);
+    // Frames from one client are handled in order.
+    let mut overflowed = false;
+    loop {
+        let message = tokio::select! {
+            message = stream.next() => message,
+            _ = conn.overflowed.notified() => {
+                overflowed = true;
+                break;
+            },
+        };
+        let Some(Ok(message)) = message else { break };
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        match serde_json::from_str::<Frame>(&text) {
+            Ok(frame) => {
+                let ack = session.handle(&frame.event, frame.data).await;
+                if frame.id.is_some() {
+                    conn.send(&Frame { event: "ack".to_string(), id: frame.id, data: serde_json::to_value(&ack).expect("acks serialize") });
+                }
+            },
+            Err(e) => {
+                metrics().record_error(ErrorCode::InvalidMessage);
+                conn.emit("error", &json!(format!("Invalid frame: {}", e)));
+            },
+        }
+    }
+    metrics().socket_disconnected();
+
+    // Dropping the last senders lets the writer flush and close; a client
+    // that fell behind is cut off without waiting for its backlog.
+    drop(session);
+    drop(conn);
+    if overflowed {
+        warn!("Disconnected a /ws client more than {} frames behind", OUTBOX_CAPACITY);
+        writer.abort();
+    }
+    let _ = writer.await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_full_outbox_drops_the_client() {
+        let (outbox, mut queued) = mpsc::channel(OUTBOX_CAPACITY);
+        let conn = WsConnection { outbox, overflowed: Notify::new() };
+        for _ in 0..OUTBOX_CAPACITY {
+            assert!(conn.emit("pong", &json!("pong")));
+        }
+        assert!(!conn.emit("pong", &json!("pong")));
+        tokio::time::timeout(std::time::Duration::from_secs(1), conn.overflowed.notified()).await.unwrap();
+
+        queued.recv().await.unwrap();
+        assert!(conn.emit("pong", &json!("pong")));
+    }
+}